@@ -0,0 +1,77 @@
+//! Stacking features across batches of examples.
+
+use crate::{
+    error::Error,
+    markers::FeatureElement,
+    types::{feature_mismatch, Example, ExampleExt},
+};
+#[cfg(feature = "with-ndarray")]
+use ndarray::{ArrayD, IxDyn};
+use std::iter;
+#[cfg(feature = "with-tch")]
+use tch::Tensor;
+
+/// Stack a feature across examples into an array.
+///
+/// Every example must have the feature with `shape.iter().product()` values of type `T`.
+/// The returned array has shape `[batch_size, shape...]`.
+#[cfg(feature = "with-ndarray")]
+pub fn stack_array<'a, T, I>(examples: I, name: &str, shape: &[usize]) -> Result<ArrayD<T>, Error>
+where
+    T: FeatureElement,
+    I: IntoIterator<Item = &'a Example>,
+{
+    let (batch_size, values) = stack_values::<T, _>(examples, name, shape.iter().product())?;
+    let shape = iter::once(batch_size)
+        .chain(shape.iter().cloned())
+        .collect::<Vec<_>>();
+    Ok(ArrayD::from_shape_vec(IxDyn(&shape), values).unwrap())
+}
+
+/// Stack a feature across examples into a tensor.
+///
+/// Every example must have the feature with `shape.iter().product()` values of type `T`.
+/// The returned tensor has shape `[batch_size, shape...]`.
+#[cfg(feature = "with-tch")]
+pub fn stack_tensor<'a, T, I>(examples: I, name: &str, shape: &[i64]) -> Result<Tensor, Error>
+where
+    T: FeatureElement,
+    I: IntoIterator<Item = &'a Example>,
+{
+    if shape.iter().any(|&size| size < 0) {
+        return Err(Error::InvalidArgumentsError {
+            desc: format!("the shape {:?} must not have negative sizes", shape),
+        });
+    }
+    let num_values = shape.iter().product::<i64>() as usize;
+    let (batch_size, values) = stack_values::<T, _>(examples, name, num_values)?;
+    let shape = iter::once(batch_size as i64)
+        .chain(shape.iter().cloned())
+        .collect::<Vec<_>>();
+    T::into_feature(values).to_tensor::<T>(&shape)
+}
+
+fn stack_values<'a, T, I>(
+    examples: I,
+    name: &str,
+    num_values: usize,
+) -> Result<(usize, Vec<T>), Error>
+where
+    T: FeatureElement,
+    I: IntoIterator<Item = &'a Example>,
+{
+    let mut batch_size = 0;
+    let mut values = vec![];
+    for example in examples {
+        let feature = example.get_feature(name)?;
+        let example_values = T::feature_values(feature)
+            .ok_or_else(|| feature_mismatch(name, T::TYPE_NAME, feature))?;
+        if example_values.len() != num_values {
+            let expect = format!("{} of {} values", T::TYPE_NAME, num_values);
+            return Err(feature_mismatch(name, &expect, feature));
+        }
+        values.extend_from_slice(example_values);
+        batch_size += 1;
+    }
+    Ok((batch_size, values))
+}
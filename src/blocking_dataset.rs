@@ -0,0 +1,509 @@
+#![cfg(feature = "blocking_dataset")]
+
+//! The blocking dataset API that accesses multiple TFRecord files without an async runtime.
+//!
+//! The module is available when the `blocking_dataset` feature is enabled.
+//! The [BlockingDataset] type can be constructed using [BlockingDatasetInit] initializer.
+//! It indexes the files the same way as the [dataset](crate::dataset) module with the same options.
+
+pub use crate::indexing::{DirFilter, FileStats, IndexProgress, ProgressCallback};
+use crate::{
+    error::Error,
+    indexing::{ProgressTracker, ScanOptions},
+    markers::GenericRecord,
+    projection::Projection,
+};
+#[cfg(feature = "with-rayon")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::{
+    fs::File,
+    io::{BufReader, Seek, SeekFrom},
+    marker::PhantomData,
+    mem,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+};
+
+#[cfg(feature = "with-rayon")]
+const PAR_ITER_CHUNK_SIZE: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RecordIndex {
+    path: Arc<PathBuf>,
+    offset: u64,
+    len: usize,
+}
+
+/// The blocking dataset initializer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct BlockingDatasetInit {
+    /// Verify the checksum or not.
+    pub check_integrity: bool,
+    /// Maximum number of open files.
+    ///
+    /// Limit the number of open files if it is `Some(_)`
+    /// It has no limit if it is `None`.
+    pub max_open_files: Option<NonZeroUsize>,
+    /// Maximum number of indexing threads.
+    ///
+    /// If it is `None`, it defaults to [num_cpus::get].
+    pub max_workers: Option<NonZeroUsize>,
+    /// Skip the corrupted regions of files instead of returning error.
+    ///
+    /// The corrupted records are detected by checksums if `check_integrity` is set.
+    /// The skipped regions are listed in [file_stats](BlockingDataset::file_stats).
+    pub skip_corrupted: bool,
+    /// Leave an incomplete record at the end of a file unindexed instead of returning error.
+    ///
+    /// The unindexed bytes are listed in [file_stats](BlockingDataset::file_stats).
+    pub allow_incomplete: bool,
+    /// The callback to report the indexing progress.
+    pub on_progress: Option<ProgressCallback>,
+    /// Decode only the selected features of examples.
+    ///
+    /// It can be changed later by [set_projection](BlockingDataset::set_projection).
+    pub projection: Option<Projection>,
+}
+
+impl Default for BlockingDatasetInit {
+    fn default() -> Self {
+        Self {
+            check_integrity: true,
+            max_open_files: None,
+            max_workers: None,
+            skip_corrupted: false,
+            allow_incomplete: false,
+            on_progress: None,
+            projection: None,
+        }
+    }
+}
+
+impl BlockingDatasetInit {
+    /// Set whether to verify the checksums.
+    pub fn with_check_integrity(mut self, check_integrity: bool) -> Self {
+        self.check_integrity = check_integrity;
+        self
+    }
+
+    /// Limit the number of open files.
+    pub fn with_max_open_files(mut self, max_open_files: NonZeroUsize) -> Self {
+        self.max_open_files = Some(max_open_files);
+        self
+    }
+
+    /// Limit the number of concurrent workers.
+    pub fn with_max_workers(mut self, max_workers: NonZeroUsize) -> Self {
+        self.max_workers = Some(max_workers);
+        self
+    }
+
+    /// Set whether to skip the corrupted regions of files.
+    pub fn with_skip_corrupted(mut self, skip_corrupted: bool) -> Self {
+        self.skip_corrupted = skip_corrupted;
+        self
+    }
+
+    /// Set whether to leave an incomplete record at the end of a file unindexed.
+    pub fn with_allow_incomplete(mut self, allow_incomplete: bool) -> Self {
+        self.allow_incomplete = allow_incomplete;
+        self
+    }
+
+    /// Report the indexing progress to the callback.
+    pub fn with_on_progress(mut self, on_progress: ProgressCallback) -> Self {
+        self.on_progress = Some(on_progress);
+        self
+    }
+
+    /// Decode only the selected features of examples.
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = Some(projection);
+        self
+    }
+
+    /// Open TFRecord files by a path prefix.
+    ///
+    /// If the path ends with "/", it searchs for all files under the directory.
+    /// Otherwise, it lists the files with the path prefix.
+    /// The enumerated paths will be sorted in alphabetical order.
+    pub fn from_prefix(self, prefix: &str) -> Result<BlockingDataset, Error> {
+        let paths = crate::indexing::list_prefix(prefix)?;
+        self.from_paths(&paths)
+    }
+
+    /// Open TFRecord files matching a glob pattern.
+    ///
+    /// The pattern follows the syntax of [glob](https://docs.rs/glob/).
+    /// Directories matching the pattern are ignored.
+    /// The enumerated paths will be sorted in alphabetical order.
+    pub fn from_glob(self, pattern: &str) -> Result<BlockingDataset, Error> {
+        let paths = crate::indexing::list_glob(pattern)?;
+        self.from_paths(&paths)
+    }
+
+    /// Open TFRecord files by walking through a directory recursively.
+    ///
+    /// The file paths relative to `dir` are tested against the include and exclude
+    /// patterns in [DirFilter]. Symbolic links to directories are not followed.
+    /// The enumerated paths will be sorted in alphabetical order.
+    pub fn from_dir<P>(self, dir: P, filter: &DirFilter) -> Result<BlockingDataset, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let paths = filter.walk(dir.as_ref())?;
+        self.from_paths(&paths)
+    }
+
+    /// Open TFRecord files by a set of path.
+    ///
+    /// It assumes every path is a TFRecord file, otherwise it returns error.
+    /// The files are indexed on up to `max_workers` threads, and the order of
+    /// the paths affects the order of record indexes.
+    pub fn from_paths<P>(self, paths: &[P]) -> Result<BlockingDataset, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let Self {
+            check_integrity,
+            max_open_files,
+            max_workers,
+            skip_corrupted,
+            allow_incomplete,
+            on_progress,
+            projection,
+        } = self;
+        let max_workers = max_workers
+            .map(|num| num.get())
+            .unwrap_or_else(num_cpus::get);
+        let open_file_semaphore =
+            max_open_files.map(|num| Arc::new(BlockingSemaphore::new(num.get())));
+        let scan_options = ScanOptions {
+            check_integrity,
+            skip_corrupted,
+            allow_incomplete,
+        };
+        let tracker = Arc::new(ProgressTracker::new(on_progress, paths.len()));
+
+        let paths = paths
+            .iter()
+            .map(|path| Arc::new(path.as_ref().to_owned()))
+            .collect::<Vec<_>>();
+        let paths = Arc::new(paths);
+        let results = Arc::new(Mutex::new(
+            (0..paths.len()).map(|_| None).collect::<Vec<_>>(),
+        ));
+        let next_path = Arc::new(AtomicUsize::new(0));
+
+        // spawn indexing workers that take paths in turn
+        let workers = (0..max_workers.min(paths.len()))
+            .map(|_| {
+                let paths = paths.clone();
+                let results = results.clone();
+                let next_path = next_path.clone();
+                let open_file_semaphore = open_file_semaphore.clone();
+                let tracker = tracker.clone();
+
+                std::thread::spawn(move || loop {
+                    let path_index = next_path.fetch_add(1, Ordering::SeqCst);
+                    let path = match paths.get(path_index) {
+                        Some(path) => path,
+                        None => break,
+                    };
+
+                    // acquire open file permission
+                    let permit = open_file_semaphore
+                        .as_ref()
+                        .map(|semaphore| semaphore.clone().acquire());
+                    let result = index_file(path, scan_options, &tracker);
+                    mem::drop(permit);
+
+                    results.lock().unwrap()[path_index] = Some(result);
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for worker in workers {
+            if let Err(payload) = worker.join() {
+                std::panic::resume_unwind(payload);
+            }
+        }
+
+        let results = mem::take(&mut *results.lock().unwrap());
+        let mut record_indexes = vec![];
+        let mut file_stats = vec![];
+        for result in results {
+            let (indexes, stats) = result.unwrap()?;
+            record_indexes.extend(indexes);
+            file_stats.push(stats);
+        }
+
+        let dataset = BlockingDataset {
+            state: Arc::new(BlockingDatasetState {
+                record_indexes,
+                file_stats,
+                #[cfg(feature = "with-rayon")]
+                max_open_files,
+            }),
+            open_file_semaphore,
+            projection,
+            open_file: None,
+        };
+
+        Ok(dataset)
+    }
+}
+
+#[derive(Debug)]
+struct BlockingDatasetState {
+    pub record_indexes: Vec<RecordIndex>,
+    pub file_stats: Vec<FileStats>,
+    #[cfg(feature = "with-rayon")]
+    pub max_open_files: Option<NonZeroUsize>,
+}
+
+/// The blocking dataset type.
+///
+/// It is analogous to [Dataset](crate::dataset::Dataset) but does not require an async runtime.
+/// Each clone keeps its own open file.
+#[derive(Debug)]
+pub struct BlockingDataset {
+    state: Arc<BlockingDatasetState>,
+    open_file_semaphore: Option<Arc<BlockingSemaphore>>,
+    projection: Option<Projection>,
+    open_file: Option<(Arc<PathBuf>, BufReader<File>, Option<SemaphorePermit>)>,
+}
+
+impl Clone for BlockingDataset {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            open_file_semaphore: self.open_file_semaphore.clone(),
+            projection: self.projection.clone(),
+            open_file: None,
+        }
+    }
+}
+
+impl BlockingDataset {
+    /// Get the number of indexed records.
+    pub fn num_records(&self) -> usize {
+        self.state.record_indexes.len()
+    }
+
+    /// Get the summaries of the files the dataset is built from.
+    pub fn file_stats(&self) -> &[FileStats] {
+        &self.state.file_stats
+    }
+
+    /// Get a record by an index number.
+    ///
+    /// It returns `None` if the index number is greater than or equal to [num_records](BlockingDataset::num_records).
+    pub fn get<T>(&mut self, index: usize) -> Result<Option<T>, Error>
+    where
+        T: GenericRecord,
+    {
+        // try to get record index
+        let record_index = match self.state.record_indexes.get(index) {
+            Some(record_index) => record_index.to_owned(),
+            None => return Ok(None),
+        };
+        let RecordIndex { offset, len, path } = record_index;
+
+        let reader = self.open_file(&path)?;
+        reader.seek(SeekFrom::Start(offset))?;
+        let bytes = crate::io::blocking::try_read_record_data(reader, len, false)?;
+        let record = match &self.projection {
+            Some(projection) => T::from_bytes_projected(bytes, projection)?,
+            None => T::from_bytes(bytes)?,
+        };
+        Ok(Some(record))
+    }
+
+    /// Get the projection applied to the decoded records.
+    pub fn projection(&self) -> Option<&Projection> {
+        self.projection.as_ref()
+    }
+
+    /// Set the projection applied to the decoded records.
+    ///
+    /// It affects [get](BlockingDataset::get) and the iterators created afterwards,
+    /// while the clones of the dataset keep their projections.
+    pub fn set_projection(&mut self, projection: Option<Projection>) {
+        self.projection = projection;
+    }
+
+    /// Iterate over the records in index order.
+    pub fn iter<T>(&self) -> BlockingDatasetIter<T>
+    where
+        T: GenericRecord,
+    {
+        BlockingDatasetIter {
+            dataset: self.clone(),
+            position: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Iterate over the records in parallel on the [rayon] thread pool.
+    ///
+    /// The records are split into chunks of consecutive records in the same file,
+    /// and each chunk is read by a fresh clone of the dataset. The records are yielded
+    /// in index order when collected. The workers have their own `max_open_files` budget
+    /// apart from the files kept open by this dataset and its clones, so the files opened
+    /// by [get](BlockingDataset::get) elsewhere cannot block the workers.
+    #[cfg(feature = "with-rayon")]
+    pub fn par_iter<T>(&self) -> impl ParallelIterator<Item = Result<T, Error>>
+    where
+        T: GenericRecord + Send,
+    {
+        // split the records at file boundaries
+        let record_indexes = &self.state.record_indexes;
+        let mut chunks = vec![];
+        let mut start = 0;
+
+        for end in 1..=record_indexes.len() {
+            let is_boundary = end == record_indexes.len()
+                || end - start >= PAR_ITER_CHUNK_SIZE
+                || record_indexes[end].path != record_indexes[start].path;
+            if is_boundary {
+                chunks.push(start..end);
+                start = end;
+            }
+        }
+
+        let dataset = BlockingDataset {
+            state: self.state.clone(),
+            open_file_semaphore: self
+                .state
+                .max_open_files
+                .map(|num| Arc::new(BlockingSemaphore::new(num.get()))),
+            projection: self.projection.clone(),
+            open_file: None,
+        };
+        chunks.into_par_iter().flat_map(move |range| {
+            // read the whole chunk to release the file before yielding
+            let mut dataset = dataset.clone();
+            range
+                .map(|index| dataset.get::<T>(index).map(|record| record.unwrap()))
+                .collect::<Vec<_>>()
+        })
+    }
+
+    fn open_file(&mut self, path: &Arc<PathBuf>) -> Result<&mut BufReader<File>, Error> {
+        // re-open file if path is distinct
+        match self.open_file.take() {
+            Some((opened_path, reader, permit)) if opened_path == *path => {
+                self.open_file = Some((opened_path, reader, permit));
+            }
+            args => {
+                mem::drop(args); // drop previous permit and reader
+                let permit = self
+                    .open_file_semaphore
+                    .as_ref()
+                    .map(|semaphore| semaphore.clone().acquire());
+                let reader = BufReader::new(File::open(&**path)?);
+                self.open_file = Some((path.clone(), reader, permit));
+            }
+        }
+
+        Ok(&mut self.open_file.as_mut().unwrap().1)
+    }
+}
+
+/// The record iterator of a [BlockingDataset].
+///
+/// It is created by [BlockingDataset::iter].
+#[derive(Debug)]
+pub struct BlockingDatasetIter<T> {
+    dataset: BlockingDataset,
+    position: usize,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Iterator for BlockingDatasetIter<T>
+where
+    T: GenericRecord,
+{
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.dataset.get(self.position).transpose()?;
+        self.position += 1;
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.dataset.num_records().saturating_sub(self.position);
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for BlockingDatasetIter<T> where T: GenericRecord {}
+
+#[derive(Debug)]
+struct BlockingSemaphore {
+    permits: Mutex<usize>,
+    condvar: Condvar,
+}
+
+impl BlockingSemaphore {
+    fn new(permits: usize) -> Self {
+        Self {
+            permits: Mutex::new(permits),
+            condvar: Condvar::new(),
+        }
+    }
+
+    fn acquire(self: Arc<Self>) -> SemaphorePermit {
+        {
+            let mut permits = self.permits.lock().unwrap();
+            while *permits == 0 {
+                permits = self.condvar.wait(permits).unwrap();
+            }
+            *permits -= 1;
+        }
+        SemaphorePermit { semaphore: self }
+    }
+}
+
+#[derive(Debug)]
+struct SemaphorePermit {
+    semaphore: Arc<BlockingSemaphore>,
+}
+
+impl Drop for SemaphorePermit {
+    fn drop(&mut self) {
+        *self.semaphore.permits.lock().unwrap() += 1;
+        self.semaphore.condvar.notify_one();
+    }
+}
+
+fn index_file(
+    path: &Arc<PathBuf>,
+    scan_options: ScanOptions,
+    tracker: &ProgressTracker,
+) -> Result<(Vec<RecordIndex>, FileStats), Error> {
+    let file = File::open(&**path)?;
+    let size = file.metadata()?.len();
+    let output = crate::indexing::scan_records(file, size, 0, scan_options)?;
+
+    let stats = output.file_stats((**path).clone());
+    tracker.file_done(&stats);
+
+    let indexes = output
+        .indexes
+        .into_iter()
+        .map(|(offset, len)| RecordIndex {
+            path: path.clone(),
+            offset,
+            len,
+        })
+        .collect();
+    Ok((indexes, stats))
+}
@@ -81,6 +81,89 @@ impl From<&Example> for RawExample {
     }
 }
 
+// protobuf SequenceExample from/to crate's SequenceExample
+
+impl From<RawSequenceExample> for SequenceExample {
+    fn from(from: RawSequenceExample) -> Self {
+        let context = from
+            .context
+            .map(|features| {
+                features
+                    .feature
+                    .into_iter()
+                    .map(|(name, feature)| (name, Feature::from(feature)))
+                    .collect::<HashMap<_, _>>()
+            })
+            .unwrap_or_default();
+        let feature_lists = from
+            .feature_lists
+            .map(|lists| {
+                lists
+                    .feature_list
+                    .into_iter()
+                    .map(|(name, list)| {
+                        let features = list.feature.into_iter().map(Feature::from).collect();
+                        (name, features)
+                    })
+                    .collect::<HashMap<_, _>>()
+            })
+            .unwrap_or_default();
+
+        Self {
+            context,
+            feature_lists,
+        }
+    }
+}
+
+impl From<&RawSequenceExample> for SequenceExample {
+    fn from(from: &RawSequenceExample) -> Self {
+        Self::from(from.to_owned())
+    }
+}
+
+impl From<SequenceExample> for RawSequenceExample {
+    fn from(from: SequenceExample) -> Self {
+        let SequenceExample {
+            context,
+            feature_lists,
+        } = from;
+
+        let context = if context.is_empty() {
+            None
+        } else {
+            let feature = context
+                .into_iter()
+                .map(|(name, feature)| (name, RawFeature::from(feature)))
+                .collect::<HashMap<_, _>>();
+            Some(Features { feature })
+        };
+        let feature_lists = if feature_lists.is_empty() {
+            None
+        } else {
+            let feature_list = feature_lists
+                .into_iter()
+                .map(|(name, features)| {
+                    let feature = features.into_iter().map(RawFeature::from).collect();
+                    (name, FeatureList { feature })
+                })
+                .collect::<HashMap<_, _>>();
+            Some(FeatureLists { feature_list })
+        };
+
+        RawSequenceExample {
+            context,
+            feature_lists,
+        }
+    }
+}
+
+impl From<&SequenceExample> for RawSequenceExample {
+    fn from(from: &SequenceExample) -> Self {
+        Self::from(from.to_owned())
+    }
+}
+
 // built-in Histogram to HistogramProto
 
 impl From<Histogram> for HistogramProto {
@@ -185,3 +268,160 @@ where
         From::<&[_]>::from(from.as_ref())
     }
 }
+
+// TensorProto to values
+
+impl TensorProto {
+    /// Get the shape of the tensor.
+    ///
+    /// A tensor without shape is a scalar. It returns error if the rank is unknown
+    /// or a dimension is negative.
+    pub fn shape(&self) -> Result<Vec<usize>, Error> {
+        let tensor_shape = match &self.tensor_shape {
+            Some(tensor_shape) => tensor_shape,
+            None => return Ok(vec![]),
+        };
+        if tensor_shape.unknown_rank {
+            return Err(Error::ConversionError {
+                desc: "the tensor has unknown rank".into(),
+            });
+        }
+        tensor_shape
+            .dim
+            .iter()
+            .map(|dim| {
+                usize::try_from(dim.size).map_err(|_| Error::ConversionError {
+                    desc: format!("the tensor has invalid dimension size {}", dim.size),
+                })
+            })
+            .collect()
+    }
+
+    /// Decode the values in row-major order.
+    ///
+    /// The values are read from `tensor_content` if it is not empty, or the typed field
+    /// such as `float_val` otherwise. As TensorFlow does, the typed values are truncated
+    /// or padded with the last value, or zeros if there are no values, to fit the shape.
+    /// It returns error if `T` does not match the data type of the tensor.
+    pub fn to_vec<T>(&self) -> Result<Vec<T>, Error>
+    where
+        T: TensorProtoDecode,
+    {
+        self.check_dtype(T::DATA_TYPE)?;
+        let num_elements = self.num_elements()?;
+        let elem_size = mem::size_of::<T>();
+        let num_bytes =
+            num_elements
+                .checked_mul(elem_size)
+                .ok_or_else(|| Error::ConversionError {
+                    desc: format!("the size of {} elements overflows", num_elements),
+                })?;
+
+        if !self.tensor_content.is_empty() {
+            if self.tensor_content.len() != num_bytes {
+                return Err(Error::ConversionError {
+                    desc: format!(
+                        "expect {} bytes of tensor content for {} elements, but found {} bytes",
+                        num_bytes,
+                        num_elements,
+                        self.tensor_content.len()
+                    ),
+                });
+            }
+            let values = self
+                .tensor_content
+                .chunks(elem_size)
+                .map(T::from_bytes)
+                .collect();
+            return Ok(values);
+        }
+
+        let zero = T::from_bytes(&vec![0; elem_size]);
+        fill_values(T::typed_values(self), num_elements, zero)
+    }
+
+    /// Decode the values of a string tensor in row-major order.
+    ///
+    /// It handles both the `string_val` field and the `tensor_content` encoding
+    /// written by `tf.io.serialize_tensor`.
+    pub fn to_bytes_list(&self) -> Result<Vec<Vec<u8>>, Error> {
+        self.check_dtype(DataType::DtString)?;
+        let num_elements = self.num_elements()?;
+
+        if self.tensor_content.is_empty() {
+            return fill_values(self.string_val.clone(), num_elements, vec![]);
+        }
+
+        // the varint lengths of all strings are followed by the concatenated bytes
+        let truncated = || Error::ConversionError {
+            desc: "the string tensor content is truncated".into(),
+        };
+        if num_elements > self.tensor_content.len() {
+            return Err(truncated());
+        }
+        let mut content = self.tensor_content.as_slice();
+        let mut lengths = Vec::with_capacity(num_elements);
+        for _ in 0..num_elements {
+            let (len, num_bytes) = u32::decode_var(content);
+            if num_bytes == 0 || content[num_bytes - 1] & 0x80 != 0 {
+                return Err(truncated());
+            }
+            lengths.push(len as usize);
+            content = &content[num_bytes..];
+        }
+        let mut values = Vec::with_capacity(num_elements);
+        for len in lengths {
+            if content.len() < len {
+                return Err(truncated());
+            }
+            let (value, remaining) = content.split_at(len);
+            values.push(value.to_vec());
+            content = remaining;
+        }
+        if !content.is_empty() {
+            return Err(Error::ConversionError {
+                desc: "the string tensor content has trailing bytes".into(),
+            });
+        }
+        Ok(values)
+    }
+
+    fn num_elements(&self) -> Result<usize, Error> {
+        let shape = self.shape()?;
+        shape
+            .iter()
+            .try_fold(1usize, |product, &size| product.checked_mul(size))
+            .ok_or_else(|| Error::ConversionError {
+                desc: format!("the number of elements of shape {:?} overflows", shape),
+            })
+    }
+
+    fn check_dtype(&self, expect: DataType) -> Result<(), Error> {
+        if self.dtype != expect as i32 {
+            let found = DataType::from_i32(self.dtype)
+                .map(|dtype| format!("{:?}", dtype))
+                .unwrap_or_else(|| format!("invalid data type {}", self.dtype));
+            return Err(Error::ConversionError {
+                desc: format!("expect {:?} tensor, but found {}", expect, found),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn fill_values<T>(mut values: Vec<T>, num_elements: usize, zero: T) -> Result<Vec<T>, Error>
+where
+    T: Clone,
+{
+    // the shape can be arbitrary large while the values are few
+    let additional = num_elements.saturating_sub(values.len());
+    values
+        .try_reserve_exact(additional)
+        .map_err(|_| Error::ConversionError {
+            desc: format!("cannot allocate {} elements", num_elements),
+        })?;
+
+    let last = values.last().cloned().unwrap_or(zero);
+    values.resize(num_elements, last);
+    Ok(values)
+}
@@ -1,17 +1,19 @@
 use crate::{
     error::Error,
-    markers::{HistogramProtoElement, TensorProtoElement, TryInfoImageList},
+    markers::{HistogramProtoElement, TensorProtoDecode, TensorProtoElement, TryInfoImageList},
     protos::{
         feature::Kind, summary::Image, tensor_shape_proto::Dim, BytesList, DataType,
-        Example as RawExample, Feature as RawFeature, Features, FloatList, HistogramProto,
-        Int64List, TensorProto, TensorShapeProto,
+        Example as RawExample, Feature as RawFeature, FeatureList, FeatureLists, Features,
+        FloatList, HistogramProto, Int64List, SequenceExample as RawSequenceExample, TensorProto,
+        TensorShapeProto,
     },
-    types::{Example, Feature, Histogram},
+    types::{Example, Feature, Histogram, SequenceExample},
 };
 use integer_encoding::VarInt;
 use noisy_float::types::R64;
 use std::{
-    collections::HashMap, convert::TryFrom, io::Cursor, ops::Deref, slice, sync::atomic::Ordering,
+    collections::HashMap, convert::TryFrom, io::Cursor, mem, ops::Deref, slice,
+    sync::atomic::Ordering,
 };
 
 mod basic_conv;
@@ -81,22 +83,18 @@ where
         T: AsRef<[u8]>,
     {
         self.verify_shape(data.len());
-        let len_iter = data
-            .iter()
-            .flat_map(|bytes| (bytes.as_ref().len() as i32).encode_var_vec());
-        let bytes_iter = data.iter().flat_map(|bytes| bytes.as_ref().iter().cloned());
-        let tensor_content = len_iter.chain(bytes_iter).collect::<Vec<_>>();
+        let string_val = data.iter().map(|bytes| bytes.as_ref().to_vec()).collect();
 
         TensorProto {
             dtype: DataType::DtString as i32,
             tensor_shape: self.build_tensor_shape(),
             version_number: 0,
-            tensor_content,
+            tensor_content: vec![],
             half_val: vec![],
             float_val: vec![],
             double_val: vec![],
             int_val: vec![],
-            string_val: vec![],
+            string_val,
             scomplex_val: vec![],
             int64_val: vec![],
             bool_val: vec![],
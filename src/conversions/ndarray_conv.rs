@@ -1,21 +1,23 @@
 #![cfg(feature = "with-ndarray")]
 
 use super::*;
-use ndarray::{ArrayBase, Data, Dimension, RawData};
+use crate::{markers::FeatureElement, types::describe_feature};
+use ndarray::{ArrayBase, ArrayD, Data, Dimension, IxDyn, RawData};
 
 // to histogram
 
-impl<S, D> TryFrom<&ArrayBase<S, D>> for HistogramProto
+impl<S, D, T> TryFrom<&ArrayBase<S, D>> for HistogramProto
 where
-    S: RawData<Elem = f64> + Data,
+    S: RawData<Elem = T> + Data,
     D: Dimension,
+    T: HistogramProtoElement,
 {
     type Error = Error;
 
     fn try_from(from: &ArrayBase<S, D>) -> Result<Self, Self::Error> {
         let histogram = Histogram::default();
-        let values_iter = from.iter().cloned().map(|value| {
-            R64::try_new(value).ok_or_else(|| Error::ConversionError {
+        let values_iter = from.iter().map(|value| {
+            R64::try_new(value.to_f64()).ok_or_else(|| Error::ConversionError {
                 desc: "non-finite floating value found".into(),
             })
         });
@@ -29,10 +31,11 @@ where
     }
 }
 
-impl<S, D> TryFrom<ArrayBase<S, D>> for HistogramProto
+impl<S, D, T> TryFrom<ArrayBase<S, D>> for HistogramProto
 where
-    S: RawData<Elem = f64> + Data,
+    S: RawData<Elem = T> + Data,
     D: Dimension,
+    T: HistogramProtoElement,
 {
     type Error = Error;
 
@@ -67,3 +70,99 @@ where
         Self::from(&from)
     }
 }
+
+// tensor to array
+
+impl<T> TryFrom<&TensorProto> for ArrayD<T>
+where
+    T: TensorProtoDecode,
+{
+    type Error = Error;
+
+    fn try_from(from: &TensorProto) -> Result<Self, Self::Error> {
+        let shape = from.shape()?;
+        let values = from.to_vec::<T>()?;
+        Ok(ArrayD::from_shape_vec(IxDyn(&shape), values).unwrap())
+    }
+}
+
+impl<T> TryFrom<TensorProto> for ArrayD<T>
+where
+    T: TensorProtoDecode,
+{
+    type Error = Error;
+
+    fn try_from(from: TensorProto) -> Result<Self, Self::Error> {
+        Self::try_from(&from)
+    }
+}
+
+impl TryFrom<&TensorProto> for ArrayD<Vec<u8>> {
+    type Error = Error;
+
+    fn try_from(from: &TensorProto) -> Result<Self, Self::Error> {
+        let shape = from.shape()?;
+        let values = from.to_bytes_list()?;
+        Ok(ArrayD::from_shape_vec(IxDyn(&shape), values).unwrap())
+    }
+}
+
+impl TryFrom<TensorProto> for ArrayD<Vec<u8>> {
+    type Error = Error;
+
+    fn try_from(from: TensorProto) -> Result<Self, Self::Error> {
+        Self::try_from(&from)
+    }
+}
+
+// array to feature
+
+impl<S, D, T> From<&ArrayBase<S, D>> for Feature
+where
+    D: Dimension,
+    S: RawData<Elem = T> + Data,
+    T: FeatureElement,
+{
+    fn from(from: &ArrayBase<S, D>) -> Self {
+        T::into_feature(from.iter().cloned().collect())
+    }
+}
+
+impl<S, D, T> From<ArrayBase<S, D>> for Feature
+where
+    D: Dimension,
+    S: RawData<Elem = T> + Data,
+    T: FeatureElement,
+{
+    fn from(from: ArrayBase<S, D>) -> Self {
+        Self::from(&from)
+    }
+}
+
+// feature to array
+
+impl Feature {
+    /// Convert the values to an array with the shape in row-major order.
+    ///
+    /// It returns error if the feature does not have values of type `T`,
+    /// or the number of values does not match the shape.
+    pub fn to_array<T>(&self, shape: &[usize]) -> Result<ArrayD<T>, Error>
+    where
+        T: FeatureElement,
+    {
+        let values = T::feature_values(self).ok_or_else(|| Error::ConversionError {
+            desc: format!(
+                "expect {}, but found {}",
+                T::TYPE_NAME,
+                describe_feature(self)
+            ),
+        })?;
+        ArrayD::from_shape_vec(IxDyn(shape), values.to_vec()).map_err(|_| Error::ConversionError {
+            desc: format!(
+                "cannot reshape {} values to shape {:?}",
+                values.len(),
+                shape
+            ),
+        })
+    }
+}
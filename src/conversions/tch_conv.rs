@@ -1,6 +1,8 @@
 #![cfg(feature = "with-tch")]
 
 use super::*;
+use crate::{markers::FeatureElement, types::describe_feature};
+use half::{bf16, f16};
 use image::{png::PngEncoder, ColorType};
 use tch::{IndexOp, Kind, Tensor};
 
@@ -35,8 +37,17 @@ macro_rules! tensor_to_r64_vec {
 }
 
 macro_rules! tensor_to_proto {
-    ($tensor:ident, $ty:ident) => {{
-        let values = tensor_to_vec!($tensor, $ty);
+    ($tensor:ident, $ty:ident) => {
+        tensor_to_converted_proto!($tensor, $ty, |value| value)
+    };
+}
+
+macro_rules! tensor_to_converted_proto {
+    ($tensor:ident, $ty:ident, $convert:expr) => {{
+        let values = tensor_to_vec!($tensor, $ty)
+            .into_iter()
+            .map($convert)
+            .collect::<Vec<_>>();
         let size = $tensor
             .size()
             .into_iter()
@@ -114,6 +125,10 @@ impl TryFrom<&Tensor> for HistogramProto {
             Kind::Int64 => tensor_to_r64_vec!(from, i64)?,
             Kind::Float => tensor_to_r64_vec!(from, f32)?,
             Kind::Double => tensor_to_r64_vec!(from, f64)?,
+            Kind::Bool | Kind::Half | Kind::BFloat16 => {
+                let tensor = from.f_to_kind(Kind::Double)?;
+                tensor_to_r64_vec!(tensor, f64)?
+            }
             _ => {
                 return Err(Error::ConversionError {
                     desc: format!("unsupported tensor kind {:?}", kind),
@@ -151,6 +166,18 @@ impl TryFrom<&Tensor> for TensorProto {
             Kind::Int64 => tensor_to_proto!(from, i64),
             Kind::Float => tensor_to_proto!(from, f32),
             Kind::Double => tensor_to_proto!(from, f64),
+            Kind::Bool => {
+                let tensor = from.f_to_kind(Kind::Uint8)?;
+                tensor_to_converted_proto!(tensor, u8, |value| value != 0)
+            }
+            Kind::Half => {
+                let tensor = from.f_to_kind(Kind::Float)?;
+                tensor_to_converted_proto!(tensor, f32, f16::from_f32)
+            }
+            Kind::BFloat16 => {
+                let tensor = from.f_to_kind(Kind::Float)?;
+                tensor_to_converted_proto!(tensor, f32, bf16::from_f32)
+            }
             _ => {
                 return Err(Error::ConversionError {
                     desc: format!("unsupported tensor kind {:?}", kind),
@@ -169,6 +196,72 @@ impl TryFrom<Tensor> for TensorProto {
     }
 }
 
+// TensorProto to tensor
+
+impl TryFrom<&TensorProto> for Tensor {
+    type Error = Error;
+
+    fn try_from(from: &TensorProto) -> Result<Self, Self::Error> {
+        let shape = from
+            .shape()?
+            .into_iter()
+            .map(|size| size as i64)
+            .collect::<Vec<_>>();
+        let dtype = DataType::from_i32(from.dtype).ok_or_else(|| Error::ConversionError {
+            desc: format!("invalid data type {}", from.dtype),
+        })?;
+        let tensor = match dtype {
+            DataType::DtUint8 => Tensor::of_slice(&from.to_vec::<u8>()?),
+            DataType::DtInt8 => Tensor::of_slice(&from.to_vec::<i8>()?),
+            DataType::DtInt16 => Tensor::of_slice(&from.to_vec::<i16>()?),
+            DataType::DtInt32 => Tensor::of_slice(&from.to_vec::<i32>()?),
+            DataType::DtInt64 => Tensor::of_slice(&from.to_vec::<i64>()?),
+            DataType::DtFloat => Tensor::of_slice(&from.to_vec::<f32>()?),
+            DataType::DtDouble => Tensor::of_slice(&from.to_vec::<f64>()?),
+            DataType::DtBool => {
+                let values = from
+                    .to_vec::<bool>()?
+                    .into_iter()
+                    .map(|value| value as u8)
+                    .collect::<Vec<_>>();
+                Tensor::of_slice(&values).f_to_kind(Kind::Bool)?
+            }
+            DataType::DtHalf => {
+                let values = from
+                    .to_vec::<f16>()?
+                    .into_iter()
+                    .map(f32::from)
+                    .collect::<Vec<_>>();
+                Tensor::of_slice(&values).f_to_kind(Kind::Half)?
+            }
+            DataType::DtBfloat16 => {
+                let values = from
+                    .to_vec::<bf16>()?
+                    .into_iter()
+                    .map(f32::from)
+                    .collect::<Vec<_>>();
+                Tensor::of_slice(&values).f_to_kind(Kind::BFloat16)?
+            }
+            _ => {
+                return Err(Error::ConversionError {
+                    desc: format!(
+                        "the tensor with data type {:?} is not supported by tch",
+                        dtype
+                    ),
+                })
+            }
+        };
+        Ok(tensor.f_view(shape.as_slice())?)
+    }
+}
+
+impl TryFrom<TensorProto> for Tensor {
+    type Error = Error;
+    fn try_from(from: TensorProto) -> Result<Self, Self::Error> {
+        Self::try_from(&from)
+    }
+}
+
 // to Image
 impl TryFrom<&Tensor> for Image {
     type Error = Error;
@@ -275,3 +368,69 @@ impl TryInfoImageList for Tensor {
         TryInfoImageList::try_into_image_list(&self)
     }
 }
+
+// tensor to feature
+
+impl TryFrom<&Tensor> for Feature {
+    type Error = Error;
+
+    fn try_from(from: &Tensor) -> Result<Self, Self::Error> {
+        let kind = from.f_kind()?;
+        let feature = match kind {
+            Kind::Float => Feature::FloatList(tensor_to_vec!(from, f32)),
+            Kind::Int64 => Feature::Int64List(tensor_to_vec!(from, i64)),
+            _ => {
+                return Err(Error::ConversionError {
+                    desc: format!(
+                        "the tensor with kind {:?} cannot be converted to feature, it must be Float or Int64",
+                        kind
+                    ),
+                })
+            }
+        };
+        Ok(feature)
+    }
+}
+
+impl TryFrom<Tensor> for Feature {
+    type Error = Error;
+    fn try_from(from: Tensor) -> Result<Self, Self::Error> {
+        Self::try_from(&from)
+    }
+}
+
+// feature to tensor
+
+impl Feature {
+    /// Convert the values to a tensor with the shape in row-major order.
+    ///
+    /// It returns error if the feature does not have values of type `T`,
+    /// or the number of values does not match the shape.
+    pub fn to_tensor<T>(&self, shape: &[i64]) -> Result<Tensor, Error>
+    where
+        T: FeatureElement,
+    {
+        if T::feature_values(self).is_none() {
+            return Err(Error::ConversionError {
+                desc: format!(
+                    "expect {}, but found {}",
+                    T::TYPE_NAME,
+                    describe_feature(self)
+                ),
+            });
+        }
+        let tensor = match self {
+            Feature::FloatList(values) => Tensor::of_slice(values),
+            Feature::Int64List(values) => Tensor::of_slice(values),
+            _ => unreachable!("please report bug"),
+        };
+        tensor.f_view(shape).map_err(|err| Error::ConversionError {
+            desc: format!(
+                "cannot reshape {} values to shape {:?}: {:?}",
+                tensor.numel(),
+                shape,
+                err
+            ),
+        })
+    }
+}
@@ -0,0 +1,132 @@
+use super::*;
+
+/// The stream adaptor that groups consecutive records into batches.
+///
+/// It is created by [batch](DatasetStreamExt::batch).
+pub struct Batch<S>
+where
+    S: TryStream,
+{
+    stream: Pin<Box<S>>,
+    buffer: Vec<S::Ok>,
+    batch_size: usize,
+    drop_remainder: bool,
+    is_exhausted: bool,
+}
+
+/// The saved state of a [Batch] stream.
+///
+/// It keeps the records of the incomplete batch and the state of the underlying stream.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BatchState<I, T> {
+    /// The state of the underlying stream.
+    pub stream: I,
+    buffer: Vec<T>,
+    batch_size: usize,
+    drop_remainder: bool,
+    is_exhausted: bool,
+}
+
+impl<S> Batch<S>
+where
+    S: TryStream,
+{
+    pub(crate) fn new(stream: S, batch_size: usize, drop_remainder: bool) -> Self {
+        assert!(batch_size > 0, "the batch size must be positive");
+
+        Self {
+            stream: Box::pin(stream),
+            buffer: Vec::with_capacity(batch_size),
+            batch_size,
+            drop_remainder,
+            is_exhausted: false,
+        }
+    }
+
+    /// Resume the batching from a saved state.
+    ///
+    /// The `stream` must be the underlying stream restored from the `stream` field of the state.
+    pub fn from_state<I>(stream: S, state: BatchState<I, S::Ok>) -> Self {
+        let BatchState {
+            buffer,
+            batch_size,
+            drop_remainder,
+            is_exhausted,
+            ..
+        } = state;
+
+        Self {
+            stream: Box::pin(stream),
+            buffer,
+            batch_size,
+            drop_remainder,
+            is_exhausted,
+        }
+    }
+}
+
+impl<S> Checkpoint for Batch<S>
+where
+    S: TryStream + Checkpoint,
+    S::Ok: Clone,
+{
+    type State = BatchState<S::State, S::Ok>;
+
+    fn checkpoint(&self) -> Self::State {
+        BatchState {
+            stream: self.stream.checkpoint(),
+            buffer: self.buffer.clone(),
+            batch_size: self.batch_size,
+            drop_remainder: self.drop_remainder,
+            is_exhausted: self.is_exhausted,
+        }
+    }
+}
+
+impl<S> Unpin for Batch<S> where S: TryStream {}
+
+impl<S> Debug for Batch<S>
+where
+    S: TryStream,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Batch")
+            .field("buffer_len", &self.buffer.len())
+            .field("batch_size", &self.batch_size)
+            .field("drop_remainder", &self.drop_remainder)
+            .field("is_exhausted", &self.is_exhausted)
+            .finish()
+    }
+}
+
+impl<S> Stream for Batch<S>
+where
+    S: TryStream,
+{
+    type Item = Result<Vec<S::Ok>, S::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        while !this.is_exhausted && this.buffer.len() < this.batch_size {
+            match this.stream.as_mut().try_poll_next(cx) {
+                Poll::Ready(Some(Ok(record))) => this.buffer.push(record),
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => this.is_exhausted = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        let is_full = this.buffer.len() == this.batch_size;
+        let is_remainder = !this.buffer.is_empty() && !this.drop_remainder;
+
+        if is_full || is_remainder {
+            let batch = mem::replace(&mut this.buffer, Vec::with_capacity(this.batch_size));
+            Poll::Ready(Some(Ok(batch)))
+        } else {
+            this.buffer.clear();
+            Poll::Ready(None)
+        }
+    }
+}
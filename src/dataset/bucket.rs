@@ -0,0 +1,265 @@
+use super::*;
+
+/// The initializer of length-bucketed batching.
+///
+/// The records are grouped into buckets by their lengths. With boundaries
+/// `[b0, b1, ..., bn]`, the buckets hold lengths in `[0, b0)`, `[b0, b1)`, ..., `[bn, ∞)`
+/// respectively. The records are not padded. Construct it by [new](BucketInit::new).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct BucketInit {
+    /// The upper length boundaries of buckets in increasing order.
+    pub boundaries: Vec<usize>,
+    /// The batch size of each bucket.
+    ///
+    /// It must have one more element than `boundaries`.
+    pub batch_sizes: Vec<usize>,
+    /// Drop the incomplete batches at the end of stream.
+    pub drop_remainder: bool,
+}
+
+impl BucketInit {
+    /// Create an initializer with the length boundaries and the batch sizes of buckets.
+    ///
+    /// The incomplete batches at the end of stream are kept.
+    pub fn new(boundaries: Vec<usize>, batch_sizes: Vec<usize>) -> Self {
+        Self {
+            boundaries,
+            batch_sizes,
+            drop_remainder: false,
+        }
+    }
+
+    /// Set whether to drop the incomplete batches at the end of stream.
+    pub fn with_drop_remainder(mut self, drop_remainder: bool) -> Self {
+        self.drop_remainder = drop_remainder;
+        self
+    }
+
+    /// Build a bucketed batching stream with a length function.
+    ///
+    /// It is analogous to `tf.data.experimental.bucket_by_sequence_length`. The `length_fn`
+    /// computes the length of a record, for example, [feature_len] computes the number
+    /// of values of a feature in an [Example]. It returns error if the boundaries are not
+    /// increasing or the number of batch sizes does not match.
+    pub fn from_stream<S, F>(self, stream: S, length_fn: F) -> Result<BucketBatch<S, F>, Error>
+    where
+        S: TryStream,
+        F: FnMut(&S::Ok) -> Result<usize, S::Error>,
+    {
+        let Self {
+            boundaries,
+            batch_sizes,
+            drop_remainder,
+        } = self;
+
+        let is_increasing = boundaries
+            .iter()
+            .zip(boundaries.iter().skip(1))
+            .all(|(lhs, rhs)| lhs < rhs);
+        if !is_increasing {
+            return Err(Error::InvalidArgumentsError {
+                desc: format!(
+                    "the bucket boundaries must be increasing, but get {:?}",
+                    boundaries
+                ),
+            });
+        }
+        if batch_sizes.len() != boundaries.len() + 1 || batch_sizes.contains(&0) {
+            return Err(Error::InvalidArgumentsError {
+                desc: format!(
+                    "expect {} positive batch sizes, but get {:?}",
+                    boundaries.len() + 1,
+                    batch_sizes
+                ),
+            });
+        }
+
+        let buckets = batch_sizes.iter().map(|_| vec![]).collect();
+
+        Ok(BucketBatch {
+            stream: Box::pin(stream),
+            length_fn,
+            boundaries,
+            batch_sizes,
+            buckets,
+            drop_remainder,
+            is_exhausted: false,
+        })
+    }
+}
+
+/// Build a length function that counts the values of a feature in an [Example].
+///
+/// It is used with [BucketInit::from_stream]. The length function returns error
+/// if the feature is missing.
+pub fn feature_len(name: &str) -> impl FnMut(&Example) -> Result<usize, Error> {
+    let name = name.to_owned();
+
+    move |example| {
+        let feature = example
+            .get(&name)
+            .ok_or_else(|| Error::InvalidArgumentsError {
+                desc: format!(r#"the feature "{}" is missing in a record"#, name),
+            })?;
+        let len = match feature {
+            Feature::BytesList(list) => list.len(),
+            Feature::FloatList(list) => list.len(),
+            Feature::Int64List(list) => list.len(),
+            Feature::None => 0,
+        };
+        Ok(len)
+    }
+}
+
+/// The stream adaptor that batches records of similar lengths.
+///
+/// It is created by [BucketInit::from_stream].
+pub struct BucketBatch<S, F>
+where
+    S: TryStream,
+{
+    stream: Pin<Box<S>>,
+    length_fn: F,
+    boundaries: Vec<usize>,
+    batch_sizes: Vec<usize>,
+    buckets: Vec<Vec<S::Ok>>,
+    drop_remainder: bool,
+    is_exhausted: bool,
+}
+
+/// The saved state of a [BucketBatch] stream.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BucketBatchState<I, T> {
+    /// The state of the underlying stream.
+    pub stream: I,
+    boundaries: Vec<usize>,
+    batch_sizes: Vec<usize>,
+    buckets: Vec<Vec<T>>,
+    drop_remainder: bool,
+    is_exhausted: bool,
+}
+
+impl<S, F> BucketBatch<S, F>
+where
+    S: TryStream,
+    F: FnMut(&S::Ok) -> Result<usize, S::Error>,
+{
+    /// Resume the batching from a saved state.
+    ///
+    /// The `stream` must be the underlying stream restored from the `stream` field of the state.
+    pub fn from_state<I>(stream: S, length_fn: F, state: BucketBatchState<I, S::Ok>) -> Self {
+        let BucketBatchState {
+            boundaries,
+            batch_sizes,
+            buckets,
+            drop_remainder,
+            is_exhausted,
+            ..
+        } = state;
+
+        Self {
+            stream: Box::pin(stream),
+            length_fn,
+            boundaries,
+            batch_sizes,
+            buckets,
+            drop_remainder,
+            is_exhausted,
+        }
+    }
+}
+
+impl<S, F> Checkpoint for BucketBatch<S, F>
+where
+    S: TryStream + Checkpoint,
+    S::Ok: Clone,
+{
+    type State = BucketBatchState<S::State, S::Ok>;
+
+    fn checkpoint(&self) -> Self::State {
+        BucketBatchState {
+            stream: self.stream.checkpoint(),
+            boundaries: self.boundaries.clone(),
+            batch_sizes: self.batch_sizes.clone(),
+            buckets: self.buckets.clone(),
+            drop_remainder: self.drop_remainder,
+            is_exhausted: self.is_exhausted,
+        }
+    }
+}
+
+impl<S, F> Unpin for BucketBatch<S, F> where S: TryStream {}
+
+impl<S, F> Debug for BucketBatch<S, F>
+where
+    S: TryStream,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let bucket_lens = self
+            .buckets
+            .iter()
+            .map(|bucket| bucket.len())
+            .collect::<Vec<_>>();
+        f.debug_struct("BucketBatch")
+            .field("boundaries", &self.boundaries)
+            .field("batch_sizes", &self.batch_sizes)
+            .field("bucket_lens", &bucket_lens)
+            .field("drop_remainder", &self.drop_remainder)
+            .field("is_exhausted", &self.is_exhausted)
+            .finish()
+    }
+}
+
+impl<S, F> Stream for BucketBatch<S, F>
+where
+    S: TryStream,
+    F: FnMut(&S::Ok) -> Result<usize, S::Error>,
+{
+    type Item = Result<Vec<S::Ok>, S::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        while !this.is_exhausted {
+            let record = match this.stream.as_mut().try_poll_next(cx) {
+                Poll::Ready(Some(Ok(record))) => record,
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => {
+                    this.is_exhausted = true;
+                    break;
+                }
+                Poll::Pending => return Poll::Pending,
+            };
+
+            let len = match (this.length_fn)(&record) {
+                Ok(len) => len,
+                Err(err) => return Poll::Ready(Some(Err(err))),
+            };
+            let bucket_index = match this.boundaries.binary_search(&len) {
+                Ok(index) => index + 1,
+                Err(index) => index,
+            };
+
+            let bucket = &mut this.buckets[bucket_index];
+            bucket.push(record);
+            if bucket.len() >= this.batch_sizes[bucket_index] {
+                let batch = mem::take(bucket);
+                return Poll::Ready(Some(Ok(batch)));
+            }
+        }
+
+        // flush the remaining buckets
+        if this.drop_remainder {
+            this.buckets.iter_mut().for_each(|bucket| bucket.clear());
+            return Poll::Ready(None);
+        }
+        let batch = this
+            .buckets
+            .iter_mut()
+            .find(|bucket| !bucket.is_empty())
+            .map(mem::take);
+        Poll::Ready(batch.map(Ok))
+    }
+}
@@ -0,0 +1,289 @@
+use super::*;
+
+const CACHE_INDEX_MAGIC: &str = "tfrecord-cache-v1";
+
+/// The caching mode used by [Dataset::cache].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CacheMode {
+    /// Keep the raw bytes of records in memory up to `max_bytes` bytes.
+    ///
+    /// The records are cached when they are read for the first time.
+    /// The records beyond the budget are read from the source files every time.
+    Memory { max_bytes: usize },
+    /// Copy the records to a local TFRecord file.
+    ///
+    /// The record index is saved along with the file at `path` with the extra
+    /// `.index` extension. The cache is rebuilt if the sizes or modification times
+    /// of the source files change. Only the sizes are checked for files on a [Storage].
+    Disk { path: PathBuf },
+}
+
+impl Dataset {
+    /// Build a dataset that caches the records.
+    ///
+    /// It is analogous to `tf.data.Dataset.cache`. The returned dataset and the datasets
+    /// derived from it share the same cache. In [Disk](CacheMode::Disk) mode, it reuses
+    /// the cache file created by previous runs if the source files are unchanged.
+    /// Otherwise, it copies all records to the cache file before it returns.
+    pub async fn cache(&self, mode: CacheMode) -> Result<Dataset, Error> {
+        match mode {
+            CacheMode::Memory { max_bytes } => {
+                let memory_cache = MemoryCache {
+                    max_bytes,
+                    inner: Mutex::new(MemoryCacheInner {
+                        num_bytes: 0,
+                        records: HashMap::new(),
+                    }),
+                };
+
+                Ok(Dataset {
+                    state: Arc::new(DatasetState {
+                        record_indexes: self.state.record_indexes.clone(),
+                        max_workers: self.state.max_workers,
+                        open_file_semaphore: self.state.open_file_semaphore.clone(),
+                        memory_cache: Some(Arc::new(memory_cache)),
+                        storage: self.state.storage.clone(),
+                        source: self.state.source.clone(),
+                        scan_options: self.state.scan_options,
+                        file_stats: self.state.file_stats.clone(),
+                    }),
+                    projection: self.projection.clone(),
+                    open_file: None,
+                })
+            }
+            CacheMode::Disk { path } => self.cache_to_disk(path).await,
+        }
+    }
+
+    async fn cache_to_disk(&self, path: PathBuf) -> Result<Dataset, Error> {
+        let index_path = {
+            let mut index_path = path.clone().into_os_string();
+            index_path.push(".index");
+            PathBuf::from(index_path)
+        };
+        let fingerprint = self.source_fingerprint().await?;
+
+        // reuse the cache if it is up to date
+        let cached = load_cache_index(&path, &index_path, fingerprint).await?;
+        let entries = match cached {
+            Some(entries) => entries,
+            None => {
+                let entries = self.write_cache(&path).await?;
+                save_cache_index(&index_path, fingerprint, &entries).await?;
+                entries
+            }
+        };
+
+        let file_stats = vec![FileStats {
+            path: path.clone().into(),
+            num_records: entries.len(),
+            num_bytes: entries
+                .last()
+                .map(|&(offset, len)| offset + len as u64 + 4)
+                .unwrap_or(0),
+            corrupted_regions: vec![],
+            incomplete_tail: None,
+        }];
+        let path = Arc::new(path);
+        let record_indexes = entries
+            .into_iter()
+            .map(|(offset, len)| RecordIndex {
+                path: path.clone(),
+                offset,
+                len,
+            })
+            .collect();
+
+        // the cache file is always on the local file system
+        Ok(Dataset {
+            state: Arc::new(DatasetState {
+                record_indexes,
+                max_workers: self.state.max_workers,
+                open_file_semaphore: self.state.open_file_semaphore.clone(),
+                memory_cache: self.state.memory_cache.clone(),
+                storage: None,
+                source: None,
+                scan_options: self.state.scan_options,
+                file_stats,
+            }),
+            projection: self.projection.clone(),
+            open_file: None,
+        })
+    }
+
+    async fn write_cache(&self, path: &Path) -> Result<Vec<(u64, usize)>, Error> {
+        // write to a temporary file to avoid partially written caches
+        let tmp_path = {
+            let mut tmp_path = path.to_owned().into_os_string();
+            tmp_path.push(".tmp");
+            PathBuf::from(tmp_path)
+        };
+
+        let mut writer = BufWriter::new(File::create(&tmp_path).await?);
+        let mut stream = self.stream::<Vec<u8>>();
+        let mut entries = Vec::with_capacity(self.num_records());
+        let mut offset = 0;
+
+        while let Some(bytes) = stream.try_next().await? {
+            let len = bytes.len();
+            crate::io::async_::try_write_record(&mut writer, bytes).await?;
+
+            // skip the length and its checksum
+            entries.push((offset + 12, len));
+            offset += len as u64 + 16;
+        }
+
+        writer.flush().await?;
+        mem::drop(writer);
+        async_std::fs::rename(&tmp_path, path).await?;
+
+        Ok(entries)
+    }
+
+    async fn source_fingerprint(&self) -> Result<u64, Error> {
+        let mut hash = FNV1A_INIT;
+        let mut update = |bytes: &[u8]| hash = fnv1a(hash, bytes);
+
+        let mut visited = HashSet::new();
+        for index in self.state.record_indexes.iter() {
+            let RecordIndex { path, offset, len } = index;
+
+            if visited.insert(path.clone()) {
+                // the storage backends only provide file sizes
+                let (file_len, mtime) = match &self.state.storage {
+                    Some(storage) => (storage.size(&path.to_string_lossy()).await?, 0),
+                    None => {
+                        let metadata = async_std::fs::metadata(&**path).await?;
+                        let mtime = metadata
+                            .modified()?
+                            .duration_since(UNIX_EPOCH)
+                            .map(|duration| duration.as_nanos())
+                            .unwrap_or(0);
+                        (metadata.len(), mtime)
+                    }
+                };
+
+                update(path.to_string_lossy().as_bytes());
+                update(&file_len.to_le_bytes());
+                update(&mtime.to_le_bytes());
+            }
+
+            update(&offset.to_le_bytes());
+            update(&(*len as u64).to_le_bytes());
+        }
+
+        Ok(splitmix64(hash))
+    }
+}
+
+async fn load_cache_index(
+    path: &Path,
+    index_path: &Path,
+    fingerprint: u64,
+) -> Result<Option<Vec<(u64, usize)>>, Error> {
+    if !path.is_file().await || !index_path.is_file().await {
+        return Ok(None);
+    }
+
+    let text = async_std::fs::read_to_string(index_path).await?;
+    let file_len = async_std::fs::metadata(path).await?.len();
+
+    // treat a malformed or outdated index as a cache miss
+    let parse = || {
+        let mut lines = text.lines();
+        if lines.next()? != CACHE_INDEX_MAGIC {
+            return None;
+        }
+
+        let mut header = |key: &str| {
+            let mut tokens = lines.next()?.split_whitespace();
+            if tokens.next()? != key {
+                return None;
+            }
+            tokens.next()?.parse::<u64>().ok()
+        };
+        let expect_fingerprint = header("fingerprint")?;
+        let expect_file_len = header("size")?;
+        let num_records = header("records")? as usize;
+        if expect_fingerprint != fingerprint || expect_file_len != file_len {
+            return None;
+        }
+
+        let entries = lines
+            .map(|line| {
+                let mut tokens = line.split_whitespace();
+                let offset = tokens.next()?.parse::<u64>().ok()?;
+                let len = tokens.next()?.parse::<usize>().ok()?;
+                Some((offset, len))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if entries.len() != num_records {
+            return None;
+        }
+
+        Some(entries)
+    };
+
+    Ok(parse())
+}
+
+async fn save_cache_index(
+    index_path: &Path,
+    fingerprint: u64,
+    entries: &[(u64, usize)],
+) -> Result<(), Error> {
+    let file_len = entries
+        .last()
+        .map(|&(offset, len)| offset + len as u64 + 4)
+        .unwrap_or(0);
+
+    let mut text = format!(
+        "{}\nfingerprint {}\nsize {}\nrecords {}\n",
+        CACHE_INDEX_MAGIC,
+        fingerprint,
+        file_len,
+        entries.len()
+    );
+    for (offset, len) in entries {
+        text.push_str(&format!("{} {}\n", offset, len));
+    }
+
+    let tmp_path = {
+        let mut tmp_path = index_path.to_owned().into_os_string();
+        tmp_path.push(".tmp");
+        PathBuf::from(tmp_path)
+    };
+    async_std::fs::write(&tmp_path, text).await?;
+    async_std::fs::rename(&tmp_path, index_path).await?;
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub(super) struct MemoryCache {
+    max_bytes: usize,
+    inner: Mutex<MemoryCacheInner>,
+}
+
+#[derive(Debug)]
+struct MemoryCacheInner {
+    num_bytes: usize,
+    records: HashMap<RecordIndex, Vec<u8>>,
+}
+
+impl MemoryCache {
+    pub(super) fn get(&self, index: &RecordIndex) -> Option<Vec<u8>> {
+        let inner = self.inner.lock().unwrap();
+        inner.records.get(index).cloned()
+    }
+
+    pub(super) fn insert(&self, index: &RecordIndex, bytes: &[u8]) {
+        let mut inner = self.inner.lock().unwrap();
+        let num_bytes = inner.num_bytes + bytes.len();
+
+        if num_bytes <= self.max_bytes && !inner.records.contains_key(index) {
+            inner.records.insert(index.clone(), bytes.to_vec());
+            inner.num_bytes = num_bytes;
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use super::*;
+
+/// The trait for streams whose iteration state can be saved and restored.
+///
+/// The state is a plain value that can be serialized when the `with-serde` feature
+/// is enabled. Each checkpointable stream type provides a `from_state` constructor
+/// or an equivalent method to resume the iteration from a saved state.
+pub trait Checkpoint {
+    type State;
+
+    /// Save the current iteration state.
+    ///
+    /// The state records the position right after the last yielded record. Records
+    /// that are being read but not yet yielded will be read again after restoring.
+    fn checkpoint(&self) -> Self::State;
+}
+
+/// The saved state of a seeded random number generator.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RngState {
+    seed: u64,
+    stream: u64,
+    word_pos: u64,
+}
+
+/// The random number generator that can be checkpointed.
+#[derive(Debug, Clone)]
+pub(crate) struct SeededRng {
+    rng: ChaCha8Rng,
+    seed: u64,
+    stream: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64, stream: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(stream);
+        Self { rng, seed, stream }
+    }
+
+    pub fn from_state(state: &RngState) -> Self {
+        let RngState {
+            seed,
+            stream,
+            word_pos,
+        } = *state;
+        let mut rng = Self::new(seed, stream);
+        rng.rng.set_word_pos(word_pos as u128);
+        rng
+    }
+
+    pub fn set_stream(&mut self, stream: u64) {
+        *self = Self::new(self.seed, stream);
+    }
+
+    pub fn state(&self) -> RngState {
+        RngState {
+            seed: self.seed,
+            stream: self.stream,
+            word_pos: self.rng.get_word_pos() as u64,
+        }
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
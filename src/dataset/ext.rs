@@ -0,0 +1,84 @@
+use super::*;
+
+/// The extension trait that adds dataset adaptors to record streams.
+///
+/// It is implemented for every [TryStream], including the streams from
+/// [Dataset::stream] and [RecordStreamInit](crate::RecordStreamInit).
+pub trait DatasetStreamExt
+where
+    Self: TryStream + Sized,
+{
+    /// Randomly shuffle the records with a buffer of `buffer_size` records.
+    ///
+    /// It is analogous to `tf.data.Dataset.shuffle`. The same seed always produces
+    /// the same order. Call [with_epoch](Shuffle::with_epoch) on the returned stream
+    /// to reshuffle in each epoch. A buffer size of 0 or 1 keeps the original order.
+    fn shuffle(self, buffer_size: usize, seed: u64) -> Shuffle<Self> {
+        Shuffle::new(self, buffer_size, seed)
+    }
+
+    /// Group consecutive records into batches of `batch_size` records.
+    ///
+    /// It is analogous to `tf.data.Dataset.batch`. The last batch may have fewer
+    /// records, unless `drop_remainder` is set.
+    ///
+    /// # Panics
+    /// It panics if `batch_size` is zero.
+    fn batch(self, batch_size: usize, drop_remainder: bool) -> Batch<Self> {
+        Batch::new(self, batch_size, drop_remainder)
+    }
+
+    /// Read at most `num_records` records ahead on a background task.
+    ///
+    /// It is analogous to `tf.data.Dataset.prefetch`, so that decoding and I/O
+    /// overlap with the consumer.
+    fn prefetch(self, num_records: usize) -> Prefetch<Self::Ok, Self::Error>
+    where
+        Self: 'static + Send,
+        Self::Ok: 'static + Send,
+        Self::Error: 'static + Send,
+    {
+        Prefetch::new(self, num_records, |_| 1)
+    }
+
+    /// Read records ahead on a background task until they occupy `max_bytes` bytes.
+    ///
+    /// The size of each record is computed by `size_fn`. A record larger than
+    /// `max_bytes` is read only if no other records are pending.
+    fn prefetch_bytes<F>(self, max_bytes: usize, size_fn: F) -> Prefetch<Self::Ok, Self::Error>
+    where
+        Self: 'static + Send,
+        Self::Ok: 'static + Send,
+        Self::Error: 'static + Send,
+        F: 'static + Fn(&Self::Ok) -> usize + Send,
+    {
+        Prefetch::new(self, max_bytes, size_fn)
+    }
+
+    /// Transform the records by `f` on at most `num_workers` background threads.
+    ///
+    /// It is analogous to `tf.data.Dataset.map` with `num_parallel_calls`.
+    /// The output preserves the order of input records.
+    fn par_map<U, F>(self, num_workers: usize, f: F) -> BoxStream<'static, Result<U, Self::Error>>
+    where
+        Self: 'static + Send,
+        Self::Ok: 'static + Send,
+        Self::Error: 'static + Send,
+        U: 'static + Send,
+        F: 'static + Fn(Self::Ok) -> Result<U, Self::Error> + Send + Sync,
+    {
+        map::par_map(self, num_workers, f)
+    }
+
+    /// Keep the records for which `predicate` returns true.
+    ///
+    /// It is analogous to `tf.data.Dataset.filter`. Errors are always passed through.
+    fn filter_ok<F>(self, predicate: F) -> FilterOk<Self, F>
+    where
+        F: FnMut(&Self::Ok) -> bool,
+    {
+        FilterOk::new(self, predicate)
+    }
+}
+
+impl<S> DatasetStreamExt for S where S: TryStream {}
@@ -0,0 +1,72 @@
+use super::*;
+use crate::indexing::RangeSource;
+pub use crate::indexing::{DirFilter, FileStats, IndexProgress, ProgressCallback};
+pub(super) use crate::indexing::{ProgressTracker, ScanOptions, ScanOutput};
+
+/// The file to be scanned by ranged reads.
+pub(super) enum ScanSource {
+    File(PathBuf),
+    Storage(Arc<dyn Storage>, String),
+}
+
+/// Scan the records of a file starting from `start` on a blocking thread.
+pub(super) async fn scan_records(
+    source: ScanSource,
+    start: u64,
+    options: ScanOptions,
+) -> Result<ScanOutput, Error> {
+    match source {
+        ScanSource::File(path) => {
+            async_std::task::spawn_blocking(move || {
+                let file = std::fs::File::open(&path)?;
+                let size = file.metadata()?.len();
+                crate::indexing::scan_records(file, size, start, options)
+            })
+            .await
+        }
+        ScanSource::Storage(storage, path) => {
+            let size = storage.size(&path).await?;
+            async_std::task::spawn_blocking(move || {
+                let source = StorageSource {
+                    storage: &*storage,
+                    path: &path,
+                };
+                crate::indexing::scan_records(source, size, start, options)
+            })
+            .await
+        }
+    }
+}
+
+pub(super) async fn list_prefix(prefix: &str) -> Result<Vec<PathBuf>, Error> {
+    let prefix = prefix.to_owned();
+    let paths =
+        async_std::task::spawn_blocking(move || crate::indexing::list_prefix(&prefix)).await?;
+    Ok(paths.into_iter().map(PathBuf::from).collect())
+}
+
+pub(super) async fn list_glob(pattern: &str) -> Result<Vec<PathBuf>, Error> {
+    let pattern = pattern.to_owned();
+    let paths =
+        async_std::task::spawn_blocking(move || crate::indexing::list_glob(&pattern)).await?;
+    Ok(paths.into_iter().map(PathBuf::from).collect())
+}
+
+pub(super) async fn walk_dir(dir: &Path, filter: &DirFilter) -> Result<Vec<PathBuf>, Error> {
+    let dir = dir.to_owned();
+    let filter = filter.clone();
+    let paths = async_std::task::spawn_blocking(move || filter.walk(dir.as_ref())).await?;
+    Ok(paths.into_iter().map(PathBuf::from).collect())
+}
+
+/// Reads the ranges of a storage file by blocking on the storage futures.
+struct StorageSource<'a> {
+    storage: &'a dyn Storage,
+    path: &'a str,
+}
+
+impl<'a> RangeSource for StorageSource<'a> {
+    fn read_range(&mut self, offset: u64, len: usize) -> Result<Vec<u8>, Error> {
+        futures::executor::block_on(self.storage.read_range(self.path, offset, len))
+    }
+}
@@ -0,0 +1,499 @@
+use super::*;
+
+/// The policy to follow when one of the combined streams runs out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum StopPolicy {
+    /// Stop as soon as any of the streams is exhausted.
+    FirstExhausted,
+    /// Skip the exhausted streams and stop after all streams are exhausted.
+    AllExhausted,
+}
+
+impl Default for StopPolicy {
+    fn default() -> Self {
+        Self::AllExhausted
+    }
+}
+
+/// The round-robin interleaving initializer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct InterleaveInit {
+    /// The number of streams that are consumed concurrently.
+    ///
+    /// When an active stream is exhausted, the next pending stream takes its place.
+    /// All streams are active if it is `None`.
+    pub cycle_length: Option<NonZeroUsize>,
+    /// The number of consecutive records taken from a stream before switching to the next one.
+    pub block_length: NonZeroUsize,
+    /// The policy to follow when a stream runs out.
+    pub stop_policy: StopPolicy,
+}
+
+impl Default for InterleaveInit {
+    fn default() -> Self {
+        Self {
+            cycle_length: None,
+            block_length: NonZeroUsize::new(1).unwrap(),
+            stop_policy: StopPolicy::AllExhausted,
+        }
+    }
+}
+
+impl InterleaveInit {
+    /// Limit the number of streams that are consumed concurrently.
+    pub fn with_cycle_length(mut self, cycle_length: NonZeroUsize) -> Self {
+        self.cycle_length = Some(cycle_length);
+        self
+    }
+
+    /// Set the number of consecutive records taken from a stream.
+    pub fn with_block_length(mut self, block_length: NonZeroUsize) -> Self {
+        self.block_length = block_length;
+        self
+    }
+
+    /// Set the policy to follow when a stream runs out.
+    pub fn with_stop_policy(mut self, stop_policy: StopPolicy) -> Self {
+        self.stop_policy = stop_policy;
+        self
+    }
+
+    /// Interleave the records from a list of streams.
+    ///
+    /// It is analogous to `tf.data.Dataset.interleave`. For example, the streams can
+    /// be obtained from [Dataset::stream] of several datasets.
+    pub fn from_streams<S>(self, streams: Vec<S>) -> Interleave<S>
+    where
+        S: TryStream,
+    {
+        let Self {
+            cycle_length,
+            block_length,
+            stop_policy,
+        } = self;
+
+        let mut pending = streams
+            .into_iter()
+            .map(Box::pin)
+            .enumerate()
+            .collect::<VecDeque<_>>();
+        let cycle_length = cycle_length
+            .map(|len| len.get())
+            .unwrap_or_else(|| pending.len());
+        let active = (0..cycle_length)
+            .filter_map(|_| pending.pop_front())
+            .collect();
+
+        Interleave {
+            active,
+            pending,
+            cursor: 0,
+            block_count: 0,
+            block_length: block_length.get(),
+            stop_policy,
+            is_done: false,
+        }
+    }
+}
+
+/// The stream that interleaves records from multiple streams in round-robin order.
+///
+/// It is created by [InterleaveInit::from_streams].
+pub struct Interleave<S>
+where
+    S: TryStream,
+{
+    active: Vec<(usize, Pin<Box<S>>)>,
+    pending: VecDeque<(usize, Pin<Box<S>>)>,
+    cursor: usize,
+    block_count: usize,
+    block_length: usize,
+    stop_policy: StopPolicy,
+    is_done: bool,
+}
+
+/// The saved state of an [Interleave] stream.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InterleaveState<I> {
+    /// The positions in the input list and the states of active streams.
+    pub active: Vec<(usize, I)>,
+    /// The positions in the input list and the states of pending streams.
+    pub pending: Vec<(usize, I)>,
+    cursor: usize,
+    block_count: usize,
+    block_length: usize,
+    stop_policy: StopPolicy,
+    is_done: bool,
+}
+
+impl<S> Interleave<S>
+where
+    S: TryStream,
+{
+    /// Resume the interleaving from a saved state.
+    ///
+    /// The `restore` function is called with the position of each unfinished stream
+    /// in the original input list and its saved state, and returns the restored stream.
+    pub fn from_state<I, F>(state: InterleaveState<I>, mut restore: F) -> Self
+    where
+        F: FnMut(usize, I) -> S,
+    {
+        let InterleaveState {
+            active,
+            pending,
+            cursor,
+            block_count,
+            block_length,
+            stop_policy,
+            is_done,
+        } = state;
+
+        let mut restore = |(index, state)| (index, Box::pin(restore(index, state)));
+        let active = active.into_iter().map(&mut restore).collect();
+        let pending = pending.into_iter().map(&mut restore).collect();
+
+        Self {
+            active,
+            pending,
+            cursor,
+            block_count,
+            block_length,
+            stop_policy,
+            is_done,
+        }
+    }
+}
+
+impl<S> Checkpoint for Interleave<S>
+where
+    S: TryStream + Checkpoint,
+{
+    type State = InterleaveState<S::State>;
+
+    fn checkpoint(&self) -> Self::State {
+        let save = |(index, stream): &(usize, Pin<Box<S>>)| (*index, stream.checkpoint());
+
+        InterleaveState {
+            active: self.active.iter().map(save).collect(),
+            pending: self.pending.iter().map(save).collect(),
+            cursor: self.cursor,
+            block_count: self.block_count,
+            block_length: self.block_length,
+            stop_policy: self.stop_policy,
+            is_done: self.is_done,
+        }
+    }
+}
+
+impl<S> Unpin for Interleave<S> where S: TryStream {}
+
+impl<S> Debug for Interleave<S>
+where
+    S: TryStream,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interleave")
+            .field("num_active", &self.active.len())
+            .field("num_pending", &self.pending.len())
+            .field("cursor", &self.cursor)
+            .field("block_length", &self.block_length)
+            .field("stop_policy", &self.stop_policy)
+            .finish()
+    }
+}
+
+impl<S> Stream for Interleave<S>
+where
+    S: TryStream,
+{
+    type Item = Result<S::Ok, S::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.is_done || this.active.is_empty() {
+                this.is_done = true;
+                return Poll::Ready(None);
+            }
+
+            match this.active[this.cursor].1.as_mut().try_poll_next(cx) {
+                Poll::Ready(Some(Ok(record))) => {
+                    this.block_count += 1;
+                    if this.block_count >= this.block_length {
+                        this.block_count = 0;
+                        this.cursor = (this.cursor + 1) % this.active.len();
+                    }
+                    return Poll::Ready(Some(Ok(record)));
+                }
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => {
+                    this.block_count = 0;
+
+                    match this.stop_policy {
+                        StopPolicy::FirstExhausted => {
+                            this.is_done = true;
+                        }
+                        StopPolicy::AllExhausted => match this.pending.pop_front() {
+                            Some(stream) => {
+                                this.active[this.cursor] = stream;
+                            }
+                            None => {
+                                this.active.remove(this.cursor);
+                                if this.cursor >= this.active.len() {
+                                    this.cursor = 0;
+                                }
+                            }
+                        },
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// The weighted random sampling initializer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct SampleInit {
+    /// The random seed.
+    pub seed: u64,
+    /// The policy to follow when a stream runs out.
+    pub stop_policy: StopPolicy,
+}
+
+impl Default for SampleInit {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            stop_policy: StopPolicy::AllExhausted,
+        }
+    }
+}
+
+impl SampleInit {
+    /// Set the random seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Set the policy to follow when a stream runs out.
+    pub fn with_stop_policy(mut self, stop_policy: StopPolicy) -> Self {
+        self.stop_policy = stop_policy;
+        self
+    }
+
+    /// Randomly sample records from a list of streams with weights.
+    ///
+    /// It is analogous to `tf.data.Dataset.sample_from_datasets`. A stream is picked
+    /// with probability proportional to its weight. The weights must be non-negative
+    /// and have positive sum, otherwise it returns error.
+    pub fn from_streams<S>(self, streams: Vec<(S, f64)>) -> Result<Sample<S>, Error>
+    where
+        S: TryStream,
+    {
+        let Self { seed, stop_policy } = self;
+
+        let is_valid = streams
+            .iter()
+            .all(|(_, weight)| weight.is_finite() && *weight >= 0.0);
+        let sum: f64 = streams.iter().map(|(_, weight)| weight).sum();
+
+        if !is_valid || sum <= 0.0 {
+            return Err(Error::InvalidArgumentsError {
+                desc: "the weights must be non-negative and have positive sum".into(),
+            });
+        }
+
+        let sources = streams
+            .into_iter()
+            .enumerate()
+            .map(|(index, (stream, weight))| (index, Box::pin(stream), weight))
+            .collect();
+
+        Ok(Sample {
+            sources,
+            rng: SeededRng::new(seed, 0),
+            selected: None,
+            stop_policy,
+        })
+    }
+}
+
+/// The stream that randomly samples records from multiple streams.
+///
+/// It is created by [SampleInit::from_streams]. The sequence of picked streams
+/// is determined by the seed, regardless of how fast the streams produce records.
+pub struct Sample<S>
+where
+    S: TryStream,
+{
+    sources: Vec<(usize, Pin<Box<S>>, f64)>,
+    rng: SeededRng,
+    selected: Option<usize>,
+    stop_policy: StopPolicy,
+}
+
+/// The saved state of a [Sample] stream.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SampleState<I> {
+    /// The positions in the input list, the states and the weights of unfinished streams.
+    pub sources: Vec<(usize, I, f64)>,
+    rng: RngState,
+    selected: Option<usize>,
+    stop_policy: StopPolicy,
+}
+
+impl<S> Sample<S>
+where
+    S: TryStream,
+{
+    /// Resume the sampling from a saved state.
+    ///
+    /// The `restore` function is called with the position of each unfinished stream
+    /// in the original input list and its saved state, and returns the restored stream.
+    pub fn from_state<I, F>(state: SampleState<I>, mut restore: F) -> Self
+    where
+        F: FnMut(usize, I) -> S,
+    {
+        let SampleState {
+            sources,
+            rng,
+            selected,
+            stop_policy,
+        } = state;
+
+        let sources = sources
+            .into_iter()
+            .map(|(index, state, weight)| (index, Box::pin(restore(index, state)), weight))
+            .collect();
+
+        Self {
+            sources,
+            rng: SeededRng::from_state(&rng),
+            selected,
+            stop_policy,
+        }
+    }
+
+    fn select(&mut self) -> Option<usize> {
+        let sum: f64 = self.sources.iter().map(|(_, _, weight)| weight).sum();
+        if sum <= 0.0 {
+            return None;
+        }
+
+        let mut value = self.rng.gen::<f64>() * sum;
+        let index = self
+            .sources
+            .iter()
+            .position(|(_, _, weight)| {
+                if value < *weight {
+                    true
+                } else {
+                    value -= weight;
+                    false
+                }
+            })
+            .unwrap_or_else(|| {
+                // fall back to the last stream with positive weight due to rounding errors
+                self.sources
+                    .iter()
+                    .rposition(|(_, _, weight)| *weight > 0.0)
+                    .unwrap()
+            });
+        Some(index)
+    }
+}
+
+impl<S> Checkpoint for Sample<S>
+where
+    S: TryStream + Checkpoint,
+{
+    type State = SampleState<S::State>;
+
+    fn checkpoint(&self) -> Self::State {
+        let sources = self
+            .sources
+            .iter()
+            .map(|(index, stream, weight)| (*index, stream.checkpoint(), *weight))
+            .collect();
+
+        SampleState {
+            sources,
+            rng: self.rng.state(),
+            selected: self.selected,
+            stop_policy: self.stop_policy,
+        }
+    }
+}
+
+impl<S> Unpin for Sample<S> where S: TryStream {}
+
+impl<S> Debug for Sample<S>
+where
+    S: TryStream,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let weights = self
+            .sources
+            .iter()
+            .map(|(_, _, weight)| *weight)
+            .collect::<Vec<_>>();
+        f.debug_struct("Sample")
+            .field("weights", &weights)
+            .field("selected", &self.selected)
+            .field("stop_policy", &self.stop_policy)
+            .finish()
+    }
+}
+
+impl<S> Stream for Sample<S>
+where
+    S: TryStream,
+{
+    type Item = Result<S::Ok, S::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            // keep the selection across pending polls for reproducibility
+            let index = match this.selected {
+                Some(index) => index,
+                None => match this.select() {
+                    Some(index) => {
+                        this.selected = Some(index);
+                        index
+                    }
+                    None => return Poll::Ready(None),
+                },
+            };
+
+            match this.sources[index].1.as_mut().try_poll_next(cx) {
+                Poll::Ready(Some(result)) => {
+                    this.selected = None;
+                    return Poll::Ready(Some(result));
+                }
+                Poll::Ready(None) => {
+                    this.selected = None;
+
+                    match this.stop_policy {
+                        StopPolicy::FirstExhausted => {
+                            this.sources.clear();
+                        }
+                        StopPolicy::AllExhausted => {
+                            this.sources.remove(index);
+                        }
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
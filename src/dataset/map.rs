@@ -0,0 +1,95 @@
+use super::*;
+
+/// The stream adaptor that keeps the records satisfying a predicate.
+///
+/// It is created by [filter_ok](DatasetStreamExt::filter_ok). Errors are passed through.
+pub struct FilterOk<S, F>
+where
+    S: TryStream,
+{
+    stream: Pin<Box<S>>,
+    predicate: F,
+}
+
+impl<S, F> FilterOk<S, F>
+where
+    S: TryStream,
+    F: FnMut(&S::Ok) -> bool,
+{
+    pub(crate) fn new(stream: S, predicate: F) -> Self {
+        Self {
+            stream: Box::pin(stream),
+            predicate,
+        }
+    }
+}
+
+impl<S, F> Checkpoint for FilterOk<S, F>
+where
+    S: TryStream + Checkpoint,
+{
+    type State = S::State;
+
+    fn checkpoint(&self) -> Self::State {
+        self.stream.checkpoint()
+    }
+}
+
+impl<S, F> Unpin for FilterOk<S, F> where S: TryStream {}
+
+impl<S, F> Debug for FilterOk<S, F>
+where
+    S: TryStream,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilterOk").finish()
+    }
+}
+
+impl<S, F> Stream for FilterOk<S, F>
+where
+    S: TryStream,
+    F: FnMut(&S::Ok) -> bool,
+{
+    type Item = Result<S::Ok, S::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            match this.stream.as_mut().try_poll_next(cx) {
+                Poll::Ready(Some(Ok(record))) => {
+                    if (this.predicate)(&record) {
+                        return Poll::Ready(Some(Ok(record)));
+                    }
+                }
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+pub(crate) fn par_map<S, U, F>(
+    stream: S,
+    num_workers: usize,
+    f: F,
+) -> BoxStream<'static, Result<U, S::Error>>
+where
+    S: 'static + TryStream + Send,
+    S::Ok: 'static + Send,
+    S::Error: 'static + Send,
+    U: 'static + Send,
+    F: 'static + Fn(S::Ok) -> Result<U, S::Error> + Send + Sync,
+{
+    let f = Arc::new(f);
+
+    stream
+        .map_ok(move |record| {
+            let f = f.clone();
+            async_std::task::spawn_blocking(move || f(record))
+        })
+        .try_buffered(num_workers.max(1))
+        .boxed()
+}
@@ -0,0 +1,653 @@
+#![cfg(feature = "dataset")]
+
+//! The dataset API that accesses multiple TFRecord files.
+//!
+//! The module is available when the `dataset` feature is enabled.
+//! The [Dataset] type can be constructed using [DatasetInit] initializer.
+
+use crate::{
+    error::Error,
+    markers::GenericRecord,
+    projection::Projection,
+    types::{Example, Feature},
+};
+use async_std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf, MAIN_SEPARATOR},
+};
+use futures::{
+    channel::mpsc,
+    future::{BoxFuture, FutureExt},
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+    stream::{BoxStream, Stream, StreamExt, TryStream, TryStreamExt},
+    task::{AtomicWaker, Context, Poll},
+};
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::{self, Debug, Formatter},
+    hash::Hash,
+    io::SeekFrom,
+    mem,
+    num::NonZeroUsize,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::UNIX_EPOCH,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+mod batch;
+mod bucket;
+mod cache;
+mod checkpoint;
+mod ext;
+mod index;
+mod interleave;
+mod map;
+mod prefetch;
+mod refresh;
+mod repeat;
+#[cfg(feature = "s3")]
+mod s3;
+mod shuffle;
+mod split;
+mod storage;
+mod stream;
+
+pub use batch::*;
+pub use bucket::*;
+pub use cache::*;
+pub use checkpoint::*;
+pub use ext::*;
+pub use index::*;
+pub use interleave::*;
+pub use map::*;
+pub use prefetch::*;
+use refresh::*;
+pub use repeat::*;
+#[cfg(feature = "s3")]
+pub use s3::*;
+pub use shuffle::*;
+pub use split::*;
+pub use storage::*;
+pub use stream::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RecordIndex {
+    path: Arc<PathBuf>,
+    offset: u64,
+    len: usize,
+}
+
+/// The dataset initializer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct DatasetInit {
+    /// Verify the checksum or not.
+    pub check_integrity: bool,
+    /// Maximum number of open files.
+    ///
+    /// Limit the number of open files if it is `Some(_)`
+    /// It has no limit if it is `None`. For the datasets on a [Storage], it limits
+    /// the number of concurrent ranged reads instead.
+    pub max_open_files: Option<NonZeroUsize>,
+    /// Maximum number of concurrent workers.
+    ///
+    /// If it is `None`, it defaults to [num_cpus::get].
+    pub max_workers: Option<NonZeroUsize>,
+    /// Skip the corrupted regions of files instead of returning error.
+    ///
+    /// The corrupted records are detected by checksums if `check_integrity` is set.
+    /// The skipped regions are listed in [file_stats](Dataset::file_stats).
+    pub skip_corrupted: bool,
+    /// Leave an incomplete record at the end of a file unindexed instead of returning error.
+    ///
+    /// Enable it for the files still being written, and index the completed records
+    /// later by [refresh](Dataset::refresh). The unindexed bytes are listed in
+    /// [file_stats](Dataset::file_stats).
+    pub allow_incomplete: bool,
+    /// The callback to report the indexing progress.
+    pub on_progress: Option<ProgressCallback>,
+    /// Decode only the selected features of examples.
+    ///
+    /// It can be changed later by [set_projection](Dataset::set_projection).
+    pub projection: Option<Projection>,
+}
+
+impl Default for DatasetInit {
+    fn default() -> Self {
+        Self {
+            check_integrity: true,
+            max_open_files: None,
+            max_workers: None,
+            skip_corrupted: false,
+            allow_incomplete: false,
+            on_progress: None,
+            projection: None,
+        }
+    }
+}
+
+impl DatasetInit {
+    /// Set whether to verify the checksums.
+    pub fn with_check_integrity(mut self, check_integrity: bool) -> Self {
+        self.check_integrity = check_integrity;
+        self
+    }
+
+    /// Limit the number of open files.
+    pub fn with_max_open_files(mut self, max_open_files: NonZeroUsize) -> Self {
+        self.max_open_files = Some(max_open_files);
+        self
+    }
+
+    /// Limit the number of concurrent workers.
+    pub fn with_max_workers(mut self, max_workers: NonZeroUsize) -> Self {
+        self.max_workers = Some(max_workers);
+        self
+    }
+
+    /// Set whether to skip the corrupted regions of files.
+    pub fn with_skip_corrupted(mut self, skip_corrupted: bool) -> Self {
+        self.skip_corrupted = skip_corrupted;
+        self
+    }
+
+    /// Set whether to leave an incomplete record at the end of a file unindexed.
+    pub fn with_allow_incomplete(mut self, allow_incomplete: bool) -> Self {
+        self.allow_incomplete = allow_incomplete;
+        self
+    }
+
+    /// Report the indexing progress to the callback.
+    pub fn with_on_progress(mut self, on_progress: ProgressCallback) -> Self {
+        self.on_progress = Some(on_progress);
+        self
+    }
+
+    /// Decode only the selected features of examples.
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = Some(projection);
+        self
+    }
+
+    /// Open TFRecord files by a path prefix.
+    ///
+    /// If the path ends with "/", it searchs for all files under the directory.
+    /// Otherwise, it lists the files with the path prefix.
+    /// The enumerated paths will be sorted in alphabetical order.
+    pub async fn from_prefix(self, prefix: &str) -> Result<Dataset, Error> {
+        let paths = list_prefix(prefix).await?;
+        let dataset = self.from_paths(&paths).await?;
+        Ok(dataset.with_source(DatasetSource::Prefix(prefix.to_owned())))
+    }
+
+    /// Open TFRecord files matching a glob pattern.
+    ///
+    /// The pattern follows the syntax of [glob](https://docs.rs/glob/), for example
+    /// `data/**/train-*.tfrecord*`. Directories matching the pattern are ignored.
+    /// The enumerated paths will be sorted in alphabetical order.
+    pub async fn from_glob(self, pattern: &str) -> Result<Dataset, Error> {
+        let paths = list_glob(pattern).await?;
+        let dataset = self.from_paths(&paths).await?;
+        Ok(dataset.with_source(DatasetSource::Glob(pattern.to_owned())))
+    }
+
+    /// Open TFRecord files by walking through a directory recursively.
+    ///
+    /// The file paths relative to `dir` are tested against the include and exclude
+    /// patterns in [DirFilter]. Symbolic links to directories are not followed.
+    /// The enumerated paths will be sorted in alphabetical order.
+    pub async fn from_dir<P>(self, dir: P, filter: &DirFilter) -> Result<Dataset, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        let paths = walk_dir(dir, filter).await?;
+        let dataset = self.from_paths(&paths).await?;
+        Ok(dataset.with_source(DatasetSource::Dir(dir.to_owned(), filter.clone())))
+    }
+
+    /// Open TFRecord files by a set of path.
+    ///
+    /// It assumes every path is a TFRecord file, otherwise it returns error.
+    /// The order of the paths affects the order of record indexes.
+    pub async fn from_paths<P>(self, paths: &[P]) -> Result<Dataset, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let Self {
+            check_integrity,
+            max_open_files,
+            max_workers,
+            skip_corrupted,
+            allow_incomplete,
+            on_progress,
+            projection,
+        } = self;
+
+        let max_open_files = max_open_files.map(|num| num.get());
+        let max_workers = max_workers
+            .map(|num| num.get())
+            .unwrap_or_else(|| num_cpus::get());
+        let open_file_semaphore = max_open_files.map(|num| Arc::new(Semaphore::new(num)));
+        let scan_options = ScanOptions {
+            check_integrity,
+            skip_corrupted,
+            allow_incomplete,
+        };
+        let tracker = Arc::new(ProgressTracker::new(on_progress, paths.len()));
+
+        // build record index
+        let (record_indexes, file_stats) = {
+            // spawn indexing worker per path
+            let future_iter = paths
+                .iter()
+                .map(|path| Arc::new(path.as_ref().to_owned()))
+                .map(|path| {
+                    let open_file_semaphore = open_file_semaphore.clone();
+                    let tracker = tracker.clone();
+
+                    async move {
+                        // acquire open file permission
+                        let permit = match open_file_semaphore {
+                            Some(semaphore) => Some(semaphore.acquire_owned().await),
+                            None => None,
+                        };
+
+                        let source = ScanSource::File((*path).clone());
+                        let output = scan_records(source, 0, scan_options).await?;
+                        mem::drop(permit);
+
+                        Result::<_, Error>::Ok(index_file_output(path, output, &tracker))
+                    }
+                })
+                .map(async_std::task::spawn);
+
+            // limit workers by max_workers
+            let outputs = futures::stream::iter(future_iter)
+                .buffered(max_workers)
+                .try_collect::<Vec<_>>()
+                .await?;
+
+            concat_file_outputs(outputs)
+        };
+
+        let dataset = Dataset {
+            state: Arc::new(DatasetState {
+                record_indexes,
+                max_workers,
+                open_file_semaphore,
+                memory_cache: None,
+                storage: None,
+                source: Some(DatasetSource::Paths(
+                    paths.iter().map(|path| path.as_ref().to_owned()).collect(),
+                )),
+                scan_options,
+                file_stats,
+            }),
+            projection,
+            open_file: None,
+        };
+
+        Ok(dataset)
+    }
+
+    /// Open TFRecord files by a path prefix on a storage backend.
+    ///
+    /// The prefix is interpreted by [Storage::list].
+    /// The enumerated paths will be sorted in alphabetical order.
+    pub async fn from_storage_prefix(
+        self,
+        storage: Arc<dyn Storage>,
+        prefix: &str,
+    ) -> Result<Dataset, Error> {
+        let mut paths = storage.list(prefix).await?;
+        paths.sort();
+        let dataset = self.from_storage_paths(storage, &paths).await?;
+        Ok(dataset.with_source(DatasetSource::StoragePrefix(prefix.to_owned())))
+    }
+
+    /// Open TFRecord files by a set of paths on a storage backend.
+    ///
+    /// The records are indexed and read by ranged reads on the storage.
+    /// The `max_open_files` option limits the number of concurrent ranged reads.
+    pub async fn from_storage_paths<P>(
+        self,
+        storage: Arc<dyn Storage>,
+        paths: &[P],
+    ) -> Result<Dataset, Error>
+    where
+        P: AsRef<str>,
+    {
+        let Self {
+            check_integrity,
+            max_open_files,
+            max_workers,
+            skip_corrupted,
+            allow_incomplete,
+            on_progress,
+            projection,
+        } = self;
+        let max_workers = max_workers
+            .map(|num| num.get())
+            .unwrap_or_else(num_cpus::get);
+        let open_file_semaphore = max_open_files.map(|num| Arc::new(Semaphore::new(num.get())));
+        let scan_options = ScanOptions {
+            check_integrity,
+            skip_corrupted,
+            allow_incomplete,
+        };
+        let tracker = Arc::new(ProgressTracker::new(on_progress, paths.len()));
+
+        // index files concurrently and keep the order of paths
+        let future_iter = paths.iter().map(|path| {
+            let storage = storage.clone();
+            let open_file_semaphore = open_file_semaphore.clone();
+            let tracker = tracker.clone();
+            let path = path.as_ref().to_owned();
+            async_std::task::spawn(async move {
+                let _permit = match open_file_semaphore {
+                    Some(semaphore) => Some(semaphore.acquire_owned().await),
+                    None => None,
+                };
+                let source = ScanSource::Storage(storage, path.clone());
+                let output = scan_records(source, 0, scan_options).await?;
+                let path = Arc::new(PathBuf::from(path));
+                Result::<_, Error>::Ok(index_file_output(path, output, &tracker))
+            })
+        });
+        let outputs = futures::stream::iter(future_iter)
+            .buffered(max_workers)
+            .try_collect::<Vec<_>>()
+            .await?;
+        let (record_indexes, file_stats) = concat_file_outputs(outputs);
+
+        let dataset = Dataset {
+            state: Arc::new(DatasetState {
+                record_indexes,
+                max_workers,
+                open_file_semaphore,
+                memory_cache: None,
+                storage: Some(storage),
+                source: Some(DatasetSource::StoragePaths(
+                    paths.iter().map(|path| path.as_ref().to_owned()).collect(),
+                )),
+                scan_options,
+                file_stats,
+            }),
+            projection,
+            open_file: None,
+        };
+
+        Ok(dataset)
+    }
+}
+
+#[derive(Debug)]
+struct DatasetState {
+    pub record_indexes: Vec<RecordIndex>,
+    pub max_workers: usize,
+    pub open_file_semaphore: Option<Arc<Semaphore>>,
+    pub memory_cache: Option<Arc<MemoryCache>>,
+    pub storage: Option<Arc<dyn Storage>>,
+    pub source: Option<DatasetSource>,
+    pub scan_options: ScanOptions,
+    pub file_stats: Vec<FileStats>,
+}
+
+/// The dataset type.
+#[derive(Debug)]
+pub struct Dataset {
+    state: Arc<DatasetState>,
+    projection: Option<Projection>,
+    open_file: Option<(PathBuf, BufReader<File>, Option<OwnedSemaphorePermit>)>,
+}
+
+impl Clone for Dataset {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            projection: self.projection.clone(),
+            open_file: None,
+        }
+    }
+}
+
+impl Dataset {
+    /// Get the number of indexed records.
+    pub fn num_records(&self) -> usize {
+        self.state.record_indexes.len()
+    }
+
+    /// Get the summaries of the files the dataset is built from.
+    ///
+    /// The datasets derived from this dataset share the same file summaries.
+    pub fn file_stats(&self) -> &[FileStats] {
+        &self.state.file_stats
+    }
+
+    /// Get an example by an index number.
+    ///
+    /// It returns `None` if the index number is greater than or equal to [num_records](Dataset::num_records).
+    pub async fn get<T>(&mut self, index: usize) -> Result<Option<T>, Error>
+    where
+        T: GenericRecord,
+    {
+        // try to get record index
+        let record_index = match self.state.record_indexes.get(index) {
+            Some(record_index) => record_index.to_owned(),
+            None => return Ok(None),
+        };
+
+        // try to hit the memory cache
+        let memory_cache = self.state.memory_cache.clone();
+        let cached_bytes = memory_cache
+            .as_ref()
+            .and_then(|cache| cache.get(&record_index));
+        if let Some(bytes) = cached_bytes {
+            let record = self.decode_record(bytes)?;
+            return Ok(Some(record));
+        }
+
+        let RecordIndex { offset, len, path } = &record_index;
+        let bytes = match self.state.storage.clone() {
+            Some(storage) => {
+                let _permit = match self.state.open_file_semaphore.clone() {
+                    Some(semaphore) => Some(semaphore.acquire_owned().await),
+                    None => None,
+                };
+                let path = path.to_string_lossy();
+                let bytes = storage.read_range(&path, *offset, *len).await?;
+                if bytes.len() != *len {
+                    return Err(Error::UnexpectedEofError);
+                }
+                bytes
+            }
+            None => {
+                let reader = self.open_file(&**path).await?;
+                try_read_record_at(reader, *offset, *len).await?
+            }
+        };
+        if let Some(cache) = memory_cache {
+            cache.insert(&record_index, &bytes);
+        }
+        let record = self.decode_record(bytes)?;
+        Ok(Some(record))
+    }
+
+    /// Get the projection applied to the decoded records.
+    pub fn projection(&self) -> Option<&Projection> {
+        self.projection.as_ref()
+    }
+
+    /// Set the projection applied to the decoded records.
+    ///
+    /// It affects [get](Dataset::get) and the streams created afterwards,
+    /// while the clones of the dataset keep their projections.
+    pub fn set_projection(&mut self, projection: Option<Projection>) {
+        self.projection = projection;
+    }
+
+    /// Gets the record stream.
+    pub fn stream<T>(&self) -> DatasetStream<T>
+    where
+        T: 'static + GenericRecord + Send,
+    {
+        self.stream_from_state(&DatasetStreamState {
+            position: 0,
+            order: RecordOrder::Sequential,
+        })
+    }
+
+    /// Gets the record stream in a seeded random order.
+    ///
+    /// Every record is visited exactly once in the order given by [Permutation].
+    /// The same seed and epoch always produce the same order, while distinct epochs
+    /// produce distinct orders.
+    pub fn shuffled_stream<T>(&self, seed: u64, epoch: u64) -> DatasetStream<T>
+    where
+        T: 'static + GenericRecord + Send,
+    {
+        self.stream_from_state(&DatasetStreamState {
+            position: 0,
+            order: RecordOrder::Permuted { seed, epoch },
+        })
+    }
+
+    /// Resume the record stream from a state saved by [checkpoint](Checkpoint::checkpoint).
+    pub fn stream_from_state<T>(&self, state: &DatasetStreamState) -> DatasetStream<T>
+    where
+        T: 'static + GenericRecord + Send,
+    {
+        DatasetStream::new(self.clone(), state.clone())
+    }
+
+    fn with_record_indexes(&self, record_indexes: Vec<RecordIndex>) -> Dataset {
+        Dataset {
+            state: Arc::new(DatasetState {
+                record_indexes,
+                max_workers: self.state.max_workers,
+                open_file_semaphore: self.state.open_file_semaphore.clone(),
+                memory_cache: self.state.memory_cache.clone(),
+                storage: self.state.storage.clone(),
+                source: None,
+                scan_options: self.state.scan_options,
+                file_stats: self.state.file_stats.clone(),
+            }),
+            projection: self.projection.clone(),
+            open_file: None,
+        }
+    }
+
+    fn decode_record<T>(&self, bytes: Vec<u8>) -> Result<T, Error>
+    where
+        T: GenericRecord,
+    {
+        match &self.projection {
+            Some(projection) => T::from_bytes_projected(bytes, projection),
+            None => T::from_bytes(bytes),
+        }
+    }
+
+    fn with_source(mut self, source: DatasetSource) -> Dataset {
+        // the state is not shared yet right after construction
+        if let Some(state) = Arc::get_mut(&mut self.state) {
+            state.source = Some(source);
+        }
+        self
+    }
+
+    async fn open_file<P>(&mut self, path: P) -> Result<&mut BufReader<File>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        // re-open file if path is distinct
+        match self.open_file.take() {
+            Some((opened_path, reader, permit)) if opened_path == path => {
+                self.open_file = Some((opened_path, reader, permit));
+            }
+            args => {
+                mem::drop(args); // drop previous permit and reader
+                let semaphore_opt = self.state.open_file_semaphore.clone();
+                let permit = match semaphore_opt {
+                    Some(semaphore) => Some(semaphore.acquire_owned().await),
+                    None => None,
+                };
+                let reader = BufReader::new(File::open(&path).await?);
+                self.open_file = Some((path.to_owned(), reader, permit));
+            }
+        }
+
+        Ok(&mut self.open_file.as_mut().unwrap().1)
+    }
+}
+
+static_assertions::assert_impl_all!(Dataset: Send, Sync);
+
+fn index_file_output(
+    path: Arc<PathBuf>,
+    output: ScanOutput,
+    tracker: &ProgressTracker,
+) -> (Vec<RecordIndex>, FileStats) {
+    let stats = output.file_stats((*path).clone().into());
+    tracker.file_done(&stats);
+
+    let indexes = output
+        .indexes
+        .into_iter()
+        .map(|(offset, len)| RecordIndex {
+            path: path.clone(),
+            offset,
+            len,
+        })
+        .collect();
+    (indexes, stats)
+}
+
+fn concat_file_outputs(
+    outputs: Vec<(Vec<RecordIndex>, FileStats)>,
+) -> (Vec<RecordIndex>, Vec<FileStats>) {
+    let mut record_indexes = vec![];
+    let mut file_stats = vec![];
+    for (indexes, stats) in outputs {
+        record_indexes.extend(indexes);
+        file_stats.push(stats);
+    }
+    (record_indexes, file_stats)
+}
+
+async fn try_read_record_at<R>(reader: &mut R, offset: u64, len: usize) -> Result<Vec<u8>, Error>
+where
+    R: AsyncReadExt + AsyncSeekExt + Unpin,
+{
+    reader.seek(SeekFrom::Start(offset)).await?;
+    let bytes = crate::io::async_::try_read_record_data(reader, len, false).await?;
+
+    Ok(bytes)
+}
+
+const FNV1A_INIT: u64 = 0xcbf29ce484222325;
+
+/// Update the FNV-1a hash, which starts from [FNV1A_INIT], with the bytes.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,116 @@
+use super::*;
+
+/// The stream adaptor that reads records ahead on a background task.
+///
+/// It is created by [prefetch](DatasetStreamExt::prefetch) and
+/// [prefetch_bytes](DatasetStreamExt::prefetch_bytes). The background task stops
+/// when the underlying stream ends or the adaptor is dropped.
+///
+/// It does not implement [Checkpoint] because the records that are read ahead
+/// but not yet consumed would be lost after restoring.
+pub struct Prefetch<T, E> {
+    receiver: mpsc::UnboundedReceiver<(usize, Result<T, E>)>,
+    budget: Arc<PrefetchBudget>,
+}
+
+impl<T, E> Prefetch<T, E>
+where
+    T: 'static + Send,
+    E: 'static + Send,
+{
+    pub(crate) fn new<S, F>(stream: S, capacity: usize, size_fn: F) -> Self
+    where
+        S: 'static + TryStream<Ok = T, Error = E> + Send,
+        F: 'static + Fn(&T) -> usize + Send,
+    {
+        let (sender, receiver) = mpsc::unbounded();
+        let budget = Arc::new(PrefetchBudget {
+            capacity: capacity.max(1),
+            in_flight: AtomicUsize::new(0),
+            is_closed: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        });
+
+        let producer_budget = budget.clone();
+        async_std::task::spawn(async move {
+            let budget = producer_budget;
+            let mut stream = Box::pin(stream.into_stream());
+
+            while let Some(result) = stream.next().await {
+                // a single record larger than the capacity is still passed through
+                let size = match &result {
+                    Ok(record) => size_fn(record).min(budget.capacity),
+                    Err(_) => 0,
+                };
+
+                // wait until the consumer takes enough records
+                let is_closed = futures::future::poll_fn(|cx| {
+                    budget.waker.register(cx.waker());
+                    let in_flight = budget.in_flight.load(Ordering::SeqCst);
+
+                    if budget.is_closed.load(Ordering::SeqCst) {
+                        Poll::Ready(true)
+                    } else if in_flight == 0 || in_flight + size <= budget.capacity {
+                        Poll::Ready(false)
+                    } else {
+                        Poll::Pending
+                    }
+                })
+                .await;
+
+                if is_closed {
+                    break;
+                }
+
+                budget.in_flight.fetch_add(size, Ordering::SeqCst);
+                if sender.unbounded_send((size, result)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { receiver, budget }
+    }
+}
+
+impl<T, E> Debug for Prefetch<T, E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Prefetch")
+            .field("capacity", &self.budget.capacity)
+            .field("in_flight", &self.budget.in_flight.load(Ordering::SeqCst))
+            .finish()
+    }
+}
+
+impl<T, E> Stream for Prefetch<T, E> {
+    type Item = Result<T, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        match this.receiver.poll_next_unpin(cx) {
+            Poll::Ready(Some((size, result))) => {
+                this.budget.in_flight.fetch_sub(size, Ordering::SeqCst);
+                this.budget.waker.wake();
+                Poll::Ready(Some(result))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T, E> Drop for Prefetch<T, E> {
+    fn drop(&mut self) {
+        self.budget.is_closed.store(true, Ordering::SeqCst);
+        self.budget.waker.wake();
+    }
+}
+
+#[derive(Debug)]
+struct PrefetchBudget {
+    capacity: usize,
+    in_flight: AtomicUsize,
+    is_closed: AtomicBool,
+    waker: AtomicWaker,
+}
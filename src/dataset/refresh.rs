@@ -0,0 +1,130 @@
+use super::*;
+
+/// The source of files a dataset is opened from.
+#[derive(Debug, Clone)]
+pub(super) enum DatasetSource {
+    Prefix(String),
+    Glob(String),
+    Dir(PathBuf, DirFilter),
+    Paths(Vec<PathBuf>),
+    StoragePrefix(String),
+    StoragePaths(Vec<String>),
+}
+
+impl Dataset {
+    /// Index the records added to the files since the dataset was opened.
+    ///
+    /// It lists the prefix, glob pattern or directory the dataset was opened from again
+    /// and indexes the new files. For the files indexed before, it only indexes the
+    /// records appended after the last indexed record. It returns the number of new records.
+    ///
+    /// An incomplete record at the end of a file is an error, unless the dataset is opened
+    /// with `allow_incomplete`, in which case the record is left to the next refresh.
+    ///
+    /// The new records are appended to the record indexes, so the existing records keep
+    /// their index numbers. The clones of the dataset and the running streams are not
+    /// affected. The datasets derived by [shard](Dataset::shard) and [split](Dataset::split)
+    /// cannot be refreshed.
+    pub async fn refresh(&mut self) -> Result<usize, Error> {
+        let source = match &self.state.source {
+            Some(source) => source.clone(),
+            None => {
+                return Err(Error::InvalidArgumentsError {
+                    desc: "the dataset is derived from another dataset and cannot be refreshed"
+                        .into(),
+                })
+            }
+        };
+
+        // list files again
+        let paths = match &source {
+            DatasetSource::Prefix(prefix) => list_prefix(prefix).await?,
+            DatasetSource::Glob(pattern) => list_glob(pattern).await?,
+            DatasetSource::Dir(dir, filter) => walk_dir(dir, filter).await?,
+            DatasetSource::Paths(paths) => paths.clone(),
+            DatasetSource::StoragePrefix(prefix) => {
+                let storage = self.state.storage.as_ref().unwrap();
+                let mut paths = storage.list(prefix).await?;
+                paths.sort();
+                paths.into_iter().map(PathBuf::from).collect()
+            }
+            DatasetSource::StoragePaths(paths) => paths.iter().map(PathBuf::from).collect(),
+        };
+
+        // find the scanned bytes per file
+        let scanned_ends: HashMap<std::path::PathBuf, (usize, u64)> = self
+            .state
+            .file_stats
+            .iter()
+            .enumerate()
+            .map(|(index, stats)| (stats.path.clone(), (index, stats.num_bytes)))
+            .collect();
+
+        // index the new parts of files
+        let storage = self.state.storage.clone();
+        let open_file_semaphore = self.state.open_file_semaphore.clone();
+        let scan_options = self.state.scan_options;
+        let tracker = Arc::new(ProgressTracker::new(None, paths.len()));
+        let future_iter = paths.into_iter().map(|path| {
+            let storage = storage.clone();
+            let open_file_semaphore = open_file_semaphore.clone();
+            let tracker = tracker.clone();
+            let (stats_index, start) = match scanned_ends.get(path.as_ref() as &std::path::Path) {
+                Some(&(index, end)) => (Some(index), end),
+                None => (None, 0),
+            };
+
+            async_std::task::spawn(async move {
+                let _permit = match open_file_semaphore {
+                    Some(semaphore) => Some(semaphore.acquire_owned().await),
+                    None => None,
+                };
+                let source = match storage {
+                    Some(storage) => ScanSource::Storage(storage, path.to_string_lossy().into()),
+                    None => ScanSource::File(path.clone()),
+                };
+                let output = scan_records(source, start, scan_options).await?;
+                let (indexes, stats) = index_file_output(Arc::new(path), output, &tracker);
+                Result::<_, Error>::Ok((stats_index, indexes, stats))
+            })
+        });
+        let outputs = futures::stream::iter(future_iter)
+            .buffered(self.state.max_workers)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        // append new records and update file summaries
+        let mut record_indexes = self.state.record_indexes.clone();
+        let mut file_stats = self.state.file_stats.clone();
+        let num_records_before = record_indexes.len();
+
+        for (stats_index, indexes, new_stats) in outputs {
+            record_indexes.extend(indexes);
+
+            match stats_index {
+                Some(index) => {
+                    let stats = &mut file_stats[index];
+                    stats.num_records += new_stats.num_records;
+                    stats.num_bytes = new_stats.num_bytes;
+                    stats.corrupted_regions.extend(new_stats.corrupted_regions);
+                    stats.incomplete_tail = new_stats.incomplete_tail;
+                }
+                None => file_stats.push(new_stats),
+            }
+        }
+        let num_new_records = record_indexes.len() - num_records_before;
+
+        self.state = Arc::new(DatasetState {
+            record_indexes,
+            max_workers: self.state.max_workers,
+            open_file_semaphore: self.state.open_file_semaphore.clone(),
+            memory_cache: self.state.memory_cache.clone(),
+            storage: self.state.storage.clone(),
+            source: Some(source),
+            scan_options: self.state.scan_options,
+            file_stats,
+        });
+
+        Ok(num_new_records)
+    }
+}
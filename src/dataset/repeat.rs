@@ -0,0 +1,160 @@
+use super::*;
+
+/// The per-epoch hook used by [Dataset::repeat].
+pub type StreamHook<T> = fn(&Dataset, usize) -> DatasetStream<T>;
+
+/// The stream that iterates over a dataset for multiple epochs.
+///
+/// It is created by [Dataset::repeat] and [Dataset::repeat_with].
+pub struct Repeat<S, F> {
+    dataset: Dataset,
+    hook: F,
+    epochs: Option<usize>,
+    epoch: usize,
+    stream: Option<Pin<Box<S>>>,
+}
+
+/// The saved state of a [Repeat] stream.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RepeatState<I> {
+    /// The state of the stream of the current epoch.
+    ///
+    /// It is `None` if the current epoch is not started yet.
+    pub stream: Option<I>,
+    epochs: Option<usize>,
+    epoch: usize,
+}
+
+impl<S, F> Repeat<S, F>
+where
+    S: TryStream,
+    F: FnMut(&Dataset, usize) -> S,
+{
+    /// Resume the repetition from a saved state.
+    ///
+    /// The `stream` is the stream of the current epoch restored from the `stream` field
+    /// of the state. It must be `Some(_)` if the field is `Some(_)`. The `hook` is called
+    /// for the following epochs.
+    pub fn from_state<I>(
+        dataset: &Dataset,
+        stream: Option<S>,
+        hook: F,
+        state: RepeatState<I>,
+    ) -> Self {
+        let RepeatState { epochs, epoch, .. } = state;
+
+        Self {
+            dataset: dataset.clone(),
+            hook,
+            epochs,
+            epoch,
+            stream: stream.map(Box::pin),
+        }
+    }
+
+    /// Get the current epoch number, starting from zero.
+    pub fn epoch(&self) -> usize {
+        self.epoch
+    }
+}
+
+impl<S, F> Checkpoint for Repeat<S, F>
+where
+    S: Checkpoint,
+{
+    type State = RepeatState<S::State>;
+
+    fn checkpoint(&self) -> Self::State {
+        RepeatState {
+            stream: self.stream.as_ref().map(|stream| stream.checkpoint()),
+            epochs: self.epochs,
+            epoch: self.epoch,
+        }
+    }
+}
+
+impl<S, F> Unpin for Repeat<S, F> {}
+
+impl<S, F> Debug for Repeat<S, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Repeat")
+            .field("epochs", &self.epochs)
+            .field("epoch", &self.epoch)
+            .finish()
+    }
+}
+
+impl<S, F> Stream for Repeat<S, F>
+where
+    S: TryStream,
+    F: FnMut(&Dataset, usize) -> S,
+{
+    type Item = Result<S::Ok, S::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this
+                .epochs
+                .map(|epochs| this.epoch >= epochs)
+                .unwrap_or(false)
+            {
+                return Poll::Ready(None);
+            }
+
+            // start a new epoch
+            if this.stream.is_none() {
+                let stream = (this.hook)(&this.dataset, this.epoch);
+                this.stream = Some(Box::pin(stream));
+            }
+
+            match this.stream.as_mut().unwrap().as_mut().try_poll_next(cx) {
+                Poll::Ready(Some(result)) => return Poll::Ready(Some(result)),
+                Poll::Ready(None) => {
+                    this.stream = None;
+                    this.epoch += 1;
+
+                    // avoid looping forever on an empty dataset
+                    if this.dataset.num_records() == 0 && this.epochs.is_none() {
+                        return Poll::Ready(None);
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Dataset {
+    /// Iterate over the records in index order for `epochs` epochs.
+    ///
+    /// It is analogous to `tf.data.Dataset.repeat`. It repeats forever if `epochs` is `None`.
+    pub fn repeat<T>(&self, epochs: Option<usize>) -> Repeat<DatasetStream<T>, StreamHook<T>>
+    where
+        T: 'static + GenericRecord + Send,
+    {
+        self.repeat_with(epochs, |dataset, _| dataset.stream())
+    }
+
+    /// Iterate over the dataset for `epochs` epochs with a per-epoch hook.
+    ///
+    /// The `hook` is called with the dataset and the epoch number at the beginning of
+    /// each epoch, and returns the record stream of the epoch. For example, it can reshuffle
+    /// the records by `|dataset, epoch| dataset.shuffled_stream(seed, epoch as u64)`.
+    /// It repeats forever if `epochs` is `None`.
+    pub fn repeat_with<S, F>(&self, epochs: Option<usize>, hook: F) -> Repeat<S, F>
+    where
+        S: TryStream,
+        F: FnMut(&Dataset, usize) -> S,
+    {
+        Repeat {
+            dataset: self.clone(),
+            hook,
+            epochs,
+            epoch: 0,
+            stream: None,
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use super::*;
+
+/// The stream adaptor that randomly shuffles records with a fixed-size buffer.
+///
+/// It is created by [shuffle](DatasetStreamExt::shuffle). It works like
+/// `tf.data.Dataset.shuffle`: it fills a buffer with records from the underlying
+/// stream, and randomly picks records from the buffer while refilling it.
+/// The order is determined by the seed and the epoch number.
+pub struct Shuffle<S>
+where
+    S: TryStream,
+{
+    stream: Pin<Box<S>>,
+    buffer: Vec<S::Ok>,
+    buffer_size: usize,
+    rng: SeededRng,
+    is_exhausted: bool,
+}
+
+/// The saved state of a [Shuffle] stream.
+///
+/// It keeps the records in the shuffle buffer and the state of the underlying stream.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ShuffleState<I, T> {
+    /// The state of the underlying stream.
+    pub stream: I,
+    buffer: Vec<T>,
+    buffer_size: usize,
+    rng: RngState,
+    is_exhausted: bool,
+}
+
+impl<S> Shuffle<S>
+where
+    S: TryStream,
+{
+    pub(crate) fn new(stream: S, buffer_size: usize, seed: u64) -> Self {
+        let buffer_size = buffer_size.max(1);
+
+        Self {
+            stream: Box::pin(stream),
+            buffer: Vec::with_capacity(buffer_size),
+            buffer_size,
+            rng: SeededRng::new(seed, 0),
+            is_exhausted: false,
+        }
+    }
+
+    /// Resume the shuffling from a saved state.
+    ///
+    /// The `stream` must be the underlying stream restored from the `stream` field of the state.
+    pub fn from_state<I>(stream: S, state: ShuffleState<I, S::Ok>) -> Self {
+        let ShuffleState {
+            buffer,
+            buffer_size,
+            rng,
+            is_exhausted,
+            ..
+        } = state;
+
+        Self {
+            stream: Box::pin(stream),
+            buffer,
+            buffer_size,
+            rng: SeededRng::from_state(&rng),
+            is_exhausted,
+        }
+    }
+
+    /// Reshuffle the records for the given epoch.
+    ///
+    /// Streams with the same seed and epoch yield records in the same order.
+    /// Pass a distinct epoch number to obtain a different order in each epoch.
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.rng.set_stream(epoch);
+        self
+    }
+}
+
+impl<S> Checkpoint for Shuffle<S>
+where
+    S: TryStream + Checkpoint,
+    S::Ok: Clone,
+{
+    type State = ShuffleState<S::State, S::Ok>;
+
+    fn checkpoint(&self) -> Self::State {
+        ShuffleState {
+            stream: self.stream.checkpoint(),
+            buffer: self.buffer.clone(),
+            buffer_size: self.buffer_size,
+            rng: self.rng.state(),
+            is_exhausted: self.is_exhausted,
+        }
+    }
+}
+
+impl<S> Unpin for Shuffle<S> where S: TryStream {}
+
+impl<S> Debug for Shuffle<S>
+where
+    S: TryStream,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shuffle")
+            .field("buffer_len", &self.buffer.len())
+            .field("buffer_size", &self.buffer_size)
+            .field("is_exhausted", &self.is_exhausted)
+            .finish()
+    }
+}
+
+impl<S> Stream for Shuffle<S>
+where
+    S: TryStream,
+{
+    type Item = Result<S::Ok, S::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        // fill the buffer
+        while !this.is_exhausted && this.buffer.len() < this.buffer_size {
+            match this.stream.as_mut().try_poll_next(cx) {
+                Poll::Ready(Some(Ok(record))) => this.buffer.push(record),
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => this.is_exhausted = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        // pick a random record
+        if this.buffer.is_empty() {
+            return Poll::Ready(None);
+        }
+        let index = this.rng.gen_range(0, this.buffer.len());
+        let record = this.buffer.swap_remove(index);
+        Poll::Ready(Some(Ok(record)))
+    }
+}
+
+/// A seeded random permutation of record indexes.
+///
+/// It visits every index in `0..len` exactly once in a random order determined by
+/// the seed and the epoch number. The permutation is computed on the fly by a Feistel
+/// network, and does not store the indexes in memory.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Permutation {
+    len: usize,
+    position: usize,
+    half_bits: u32,
+    round_keys: [u64; 4],
+}
+
+impl Permutation {
+    /// Create a permutation over `0..len`.
+    pub fn new(len: usize, seed: u64, epoch: u64) -> Self {
+        // the Feistel network works on the domain of 2^(2 * half_bits) values
+        let bits = (usize_bits() - len.saturating_sub(1).leading_zeros()).max(2);
+        let half_bits = bits / 2 + bits % 2;
+
+        let mut rng = SeededRng::new(seed, epoch);
+        let round_keys = [rng.gen(), rng.gen(), rng.gen(), rng.gen()];
+
+        Self {
+            len,
+            position: 0,
+            half_bits,
+            round_keys,
+        }
+    }
+
+    /// Get the permuted index at the position.
+    ///
+    /// It returns `None` if the position is out of range.
+    pub fn get(&self, position: usize) -> Option<usize> {
+        if position >= self.len {
+            return None;
+        }
+
+        // walk the cycle until the value falls in the range
+        let mut value = position as u64;
+        loop {
+            value = self.feistel(value);
+            if value < self.len as u64 {
+                break Some(value as usize);
+            }
+        }
+    }
+
+    fn feistel(&self, value: u64) -> u64 {
+        let mask = (1u64 << self.half_bits) - 1;
+        let mut left = value >> self.half_bits;
+        let mut right = value & mask;
+
+        for key in self.round_keys.iter() {
+            let next_right = left ^ (splitmix64(right ^ key) & mask);
+            left = right;
+            right = next_right;
+        }
+
+        (left << self.half_bits) | right
+    }
+}
+
+impl Iterator for Permutation {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.get(self.position)?;
+        self.position += 1;
+        Some(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.position;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Permutation {}
+
+fn usize_bits() -> u32 {
+    (mem::size_of::<usize>() * 8) as u32
+}
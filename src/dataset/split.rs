@@ -0,0 +1,173 @@
+use super::*;
+
+/// The unit of sharding used by [Dataset::shard].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShardBy {
+    /// Assign whole files to shards in a round-robin fashion.
+    ///
+    /// The files are numbered in the order they first appear in the dataset. The records
+    /// of a file go to the same shard even if they are not contiguous in the dataset.
+    File,
+    /// Assign records to shards in a round-robin fashion.
+    Record,
+}
+
+impl Dataset {
+    /// Get the `index`-th of `num_shards` disjoint shards of the dataset.
+    ///
+    /// It is analogous to `tf.data.Dataset.shard`. The shards of the same dataset
+    /// are disjoint and cover all records. It returns error if `num_shards` is zero
+    /// or `index` is not less than `num_shards`.
+    pub fn shard(&self, num_shards: usize, index: usize, by: ShardBy) -> Result<Dataset, Error> {
+        if index >= num_shards {
+            return Err(Error::InvalidArgumentsError {
+                desc: format!(
+                    "the shard index {} must be less than the number of shards {}",
+                    index, num_shards
+                ),
+            });
+        }
+
+        let record_indexes = match by {
+            ShardBy::Record => self
+                .state
+                .record_indexes
+                .iter()
+                .enumerate()
+                .filter(|(record_no, _)| record_no % num_shards == index)
+                .map(|(_, record_index)| record_index.clone())
+                .collect(),
+            ShardBy::File => {
+                let mut file_nos: HashMap<&Arc<PathBuf>, usize> = HashMap::new();
+
+                self.state
+                    .record_indexes
+                    .iter()
+                    .filter(|record_index| {
+                        let num_files = file_nos.len();
+                        let file_no = *file_nos.entry(&record_index.path).or_insert(num_files);
+                        file_no % num_shards == index
+                    })
+                    .cloned()
+                    .collect()
+            }
+        };
+
+        Ok(self.with_record_indexes(record_indexes))
+    }
+
+    /// Partition the records into datasets by ratios with a random seed.
+    ///
+    /// For example, `split(&[0.8, 0.1, 0.1], seed)` returns train, validation and test
+    /// datasets. The ratios are normalized by their sum. Each record is assigned by
+    /// a hash of the seed, its file path and its offset in the file, so the partition
+    /// is stable across runs and does not depend on the order of records.
+    pub fn split(&self, ratios: &[f64], seed: u64) -> Result<Vec<Dataset>, Error> {
+        let thresholds = split_thresholds(ratios)?;
+        let hashes = self.state.record_indexes.iter().map(|record_index| {
+            let path = record_index.path.to_string_lossy();
+            let hash = fnv1a(FNV1A_INIT, path.as_bytes());
+            let hash = fnv1a(hash, &record_index.offset.to_le_bytes());
+            Ok(splitmix64(seed ^ splitmix64(hash)))
+        });
+        self.split_by_hashes(&thresholds, hashes)
+    }
+
+    /// Partition the records into datasets by ratios with the hash of a feature.
+    ///
+    /// It reads every record as [Example](crate::Example) and hashes the content of the feature
+    /// `name`, so that records with identical feature values always fall into the same
+    /// dataset regardless of the record order. It returns error if a record does not have the feature.
+    pub async fn split_by_feature(
+        &self,
+        ratios: &[f64],
+        name: &str,
+    ) -> Result<Vec<Dataset>, Error> {
+        let thresholds = split_thresholds(ratios)?;
+        let hashes = self
+            .stream::<Example>()
+            .and_then(|example| async move {
+                let feature = example
+                    .get(name)
+                    .ok_or_else(|| Error::InvalidArgumentsError {
+                        desc: format!(r#"the feature "{}" is missing in a record"#, name),
+                    })?;
+                Ok(feature_hash(feature))
+            })
+            .try_collect::<Vec<_>>()
+            .await?;
+        self.split_by_hashes(&thresholds, hashes.into_iter().map(Ok))
+    }
+
+    fn split_by_hashes<I>(&self, thresholds: &[u64], hashes: I) -> Result<Vec<Dataset>, Error>
+    where
+        I: IntoIterator<Item = Result<u64, Error>>,
+    {
+        let mut parts = vec![vec![]; thresholds.len()];
+
+        for (record_index, hash) in self.state.record_indexes.iter().zip(hashes) {
+            let hash = hash?;
+            let part_index = thresholds
+                .iter()
+                .position(|&threshold| hash < threshold)
+                .unwrap_or(thresholds.len() - 1);
+            parts[part_index].push(record_index.clone());
+        }
+
+        let datasets = parts
+            .into_iter()
+            .map(|record_indexes| self.with_record_indexes(record_indexes))
+            .collect();
+        Ok(datasets)
+    }
+}
+
+/// Convert the ratios to cumulative thresholds in the range of u64.
+fn split_thresholds(ratios: &[f64]) -> Result<Vec<u64>, Error> {
+    let is_valid = !ratios.is_empty()
+        && ratios
+            .iter()
+            .all(|&ratio| ratio.is_finite() && ratio >= 0.0);
+    let sum: f64 = ratios.iter().sum();
+
+    if !is_valid || sum <= 0.0 {
+        return Err(Error::InvalidArgumentsError {
+            desc: format!(
+                "the ratios must be non-negative and have positive sum, but get {:?}",
+                ratios
+            ),
+        });
+    }
+
+    let mut acc = 0.0;
+    let thresholds = ratios
+        .iter()
+        .map(|ratio| {
+            acc += ratio / sum;
+            if acc >= 1.0 {
+                u64::MAX
+            } else {
+                (acc * u64::MAX as f64) as u64
+            }
+        })
+        .collect();
+    Ok(thresholds)
+}
+
+/// A hash of the feature content that is stable across runs and platforms.
+fn feature_hash(feature: &Feature) -> u64 {
+    let mut hash = FNV1A_INIT;
+    let mut update = |bytes: &[u8]| hash = fnv1a(hash, bytes);
+
+    match feature {
+        Feature::BytesList(list) => list.iter().for_each(|bytes| {
+            update(&(bytes.len() as u64).to_le_bytes());
+            update(bytes);
+        }),
+        Feature::FloatList(list) => list.iter().for_each(|value| update(&value.to_le_bytes())),
+        Feature::Int64List(list) => list.iter().for_each(|value| update(&value.to_le_bytes())),
+        Feature::None => (),
+    }
+
+    splitmix64(hash)
+}
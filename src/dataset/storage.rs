@@ -0,0 +1,99 @@
+use super::*;
+
+/// The storage backend that holds TFRecord files.
+///
+/// The paths are backend-specific strings, such as file paths for [LocalStorage]
+/// and `s3://bucket/key` URLs for `S3Storage`. The dataset built from a storage
+/// reads the records by ranged reads, see [DatasetInit::from_storage_paths].
+pub trait Storage: Debug + Send + Sync {
+    /// List the file paths starting with the prefix.
+    fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<Vec<String>, Error>>;
+
+    /// Get the size of a file in bytes.
+    fn size<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<u64, Error>>;
+
+    /// Read `len` bytes starting from `offset` of a file.
+    ///
+    /// It returns fewer bytes only if the range goes beyond the end of file.
+    fn read_range<'a>(
+        &'a self,
+        path: &'a str,
+        offset: u64,
+        len: usize,
+    ) -> BoxFuture<'a, Result<Vec<u8>, Error>>;
+}
+
+/// The storage on the local file system.
+///
+/// The listing follows the semantics of [DatasetInit::from_prefix]. If the prefix ends
+/// with the path separator, it lists the files under the directory. Otherwise, it lists
+/// the files in the parent directory with the file name prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct LocalStorage;
+
+impl Storage for LocalStorage {
+    fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<Vec<String>, Error>> {
+        async move {
+            let prefix_path: &Path = prefix.as_ref();
+            let (dir, file_name_prefix) = if prefix.ends_with(MAIN_SEPARATOR) {
+                (prefix_path, "")
+            } else {
+                let dir = match prefix_path.parent() {
+                    Some(dir) if dir.as_os_str().is_empty() => Path::new("."),
+                    Some(dir) => dir,
+                    None => Path::new("."),
+                };
+                let file_name_prefix = prefix_path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or("");
+                (dir, file_name_prefix)
+            };
+
+            let mut paths = vec![];
+            let mut entries = dir.read_dir().await?;
+            while let Some(entry) = entries.try_next().await? {
+                if !entry.metadata().await?.is_file() {
+                    continue;
+                }
+                let path = entry.path();
+                let (file_name, path) = match (entry.file_name().into_string(), path.to_str()) {
+                    (Ok(file_name), Some(path)) => (file_name, path.to_owned()),
+                    _ => {
+                        return Err(Error::UnicodeError {
+                            desc: format!(r#"the file path "{}" is not Unicode"#, path.display()),
+                        })
+                    }
+                };
+                if file_name.starts_with(file_name_prefix) {
+                    paths.push(path);
+                }
+            }
+
+            paths.sort();
+            Ok(paths)
+        }
+        .boxed()
+    }
+
+    fn size<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<u64, Error>> {
+        async move { Ok(async_std::fs::metadata(path).await?.len()) }.boxed()
+    }
+
+    fn read_range<'a>(
+        &'a self,
+        path: &'a str,
+        offset: u64,
+        len: usize,
+    ) -> BoxFuture<'a, Result<Vec<u8>, Error>> {
+        async move {
+            let mut file = File::open(path).await?;
+            file.seek(SeekFrom::Start(offset)).await?;
+
+            let mut bytes = vec![];
+            file.take(len as u64).read_to_end(&mut bytes).await?;
+            Ok(bytes)
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,144 @@
+use super::*;
+
+/// The order in which a [DatasetStream] visits the records.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RecordOrder {
+    /// Visit the records in index order.
+    Sequential,
+    /// Visit the records in the random order given by [Permutation].
+    Permuted { seed: u64, epoch: u64 },
+}
+
+/// The saved state of a [DatasetStream].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DatasetStreamState {
+    /// The number of yielded records.
+    pub position: usize,
+    /// The order of records.
+    pub order: RecordOrder,
+}
+
+/// The record stream of a [Dataset].
+///
+/// It is created by [Dataset::stream], [Dataset::shuffled_stream] and
+/// [Dataset::stream_from_state]. The stream ends after an error is yielded.
+pub struct DatasetStream<T> {
+    dataset: Option<Dataset>,
+    position: usize,
+    order: RecordOrder,
+    permutation: Option<Permutation>,
+    future: Option<BoxFuture<'static, (Dataset, Result<Option<T>, Error>)>>,
+    is_done: bool,
+}
+
+impl<T> DatasetStream<T>
+where
+    T: 'static + GenericRecord + Send,
+{
+    pub(crate) fn new(dataset: Dataset, state: DatasetStreamState) -> Self {
+        let DatasetStreamState { position, order } = state;
+        let permutation = match order {
+            RecordOrder::Sequential => None,
+            RecordOrder::Permuted { seed, epoch } => {
+                Some(Permutation::new(dataset.num_records(), seed, epoch))
+            }
+        };
+
+        Self {
+            dataset: Some(dataset),
+            position,
+            order,
+            permutation,
+            future: None,
+            is_done: false,
+        }
+    }
+
+    fn record_index(&self) -> Option<usize> {
+        match &self.permutation {
+            Some(permutation) => permutation.get(self.position),
+            None => Some(self.position),
+        }
+    }
+}
+
+impl<T> Checkpoint for DatasetStream<T> {
+    type State = DatasetStreamState;
+
+    fn checkpoint(&self) -> Self::State {
+        DatasetStreamState {
+            position: self.position,
+            order: self.order.clone(),
+        }
+    }
+}
+
+impl<T> Unpin for DatasetStream<T> {}
+
+impl<T> Debug for DatasetStream<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DatasetStream")
+            .field("position", &self.position)
+            .field("order", &self.order)
+            .field("is_done", &self.is_done)
+            .finish()
+    }
+}
+
+impl<T> Stream for DatasetStream<T>
+where
+    T: 'static + GenericRecord + Send,
+{
+    type Item = Result<T, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.is_done {
+            return Poll::Ready(None);
+        }
+
+        // start reading the next record
+        if this.future.is_none() {
+            let index = match this.record_index() {
+                Some(index) => index,
+                None => {
+                    this.is_done = true;
+                    return Poll::Ready(None);
+                }
+            };
+            let mut dataset = this.dataset.take().unwrap();
+            this.future = Some(
+                async move {
+                    let result = dataset.get::<T>(index).await;
+                    (dataset, result)
+                }
+                .boxed(),
+            );
+        }
+
+        let (dataset, result) = match this.future.as_mut().unwrap().poll_unpin(cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => return Poll::Pending,
+        };
+        this.dataset = Some(dataset);
+        this.future = None;
+
+        match result {
+            Ok(Some(record)) => {
+                this.position += 1;
+                Poll::Ready(Some(Ok(record)))
+            }
+            Ok(None) => {
+                this.is_done = true;
+                Poll::Ready(None)
+            }
+            Err(err) => {
+                this.is_done = true;
+                Poll::Ready(Some(Err(err)))
+            }
+        }
+    }
+}
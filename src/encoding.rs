@@ -0,0 +1,75 @@
+//! Deterministic encoding of examples.
+//!
+//! The features of examples are stored in hash maps, which are encoded in arbitrary
+//! order by ProtocolBuffer. The functions here write the map entries sorted by keys,
+//! producing the same bytes as TensorFlow's deterministic serialization. Equal examples
+//! always yield identical bytes, which makes the bytes suitable for hashing and comparison.
+//!
+//! [GenericRecord::to_bytes](crate::markers::GenericRecord::to_bytes) on examples and
+//! sequence examples uses this encoding.
+
+use crate::protos::{
+    Example as RawExample, FeatureLists, Features, SequenceExample as RawSequenceExample,
+};
+use prost::{
+    encoding::{encode_key, encode_varint, encoded_len_varint, key_len, message, string, WireType},
+    Message,
+};
+use std::collections::HashMap;
+
+/// Encode an example with features sorted by names.
+pub fn encode_example(example: &RawExample) -> Vec<u8> {
+    let RawExample { features } = example;
+
+    let mut buf = vec![];
+    if let Some(features) = features {
+        encode_map_message(1, &features.feature, &mut buf);
+    }
+    buf
+}
+
+/// Encode a sequence example with context features and feature lists sorted by names.
+pub fn encode_sequence_example(example: &RawSequenceExample) -> Vec<u8> {
+    let RawSequenceExample {
+        context,
+        feature_lists,
+    } = example;
+
+    let mut buf = vec![];
+    if let Some(Features { feature }) = context {
+        encode_map_message(1, feature, &mut buf);
+    }
+    if let Some(FeatureLists { feature_list }) = feature_lists {
+        encode_map_message(2, feature_list, &mut buf);
+    }
+    buf
+}
+
+/// Encode a message field whose only field is a string-keyed map at tag 1.
+fn encode_map_message<V>(tag: u32, map: &HashMap<String, V>, buf: &mut Vec<u8>)
+where
+    V: Message,
+{
+    let mut entries = map.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|(key, _)| *key);
+
+    // the map entries always contain keys and values like protobuf C++ does
+    let entry_len =
+        |key: &String, value: &V| string::encoded_len(1, key) + message::encoded_len(2, value);
+    let body_len: usize = entries
+        .iter()
+        .map(|(key, value)| {
+            let len = entry_len(key, value);
+            key_len(1) + encoded_len_varint(len as u64) + len
+        })
+        .sum();
+
+    encode_key(tag, WireType::LengthDelimited, buf);
+    encode_varint(body_len as u64, buf);
+    for (key, value) in entries {
+        encode_key(1, WireType::LengthDelimited, buf);
+        encode_varint(entry_len(key, value) as u64, buf);
+        string::encode(1, key, buf);
+        message::encode(2, value, buf);
+    }
+}
@@ -20,6 +20,14 @@ pub enum Error {
     IoError { error: std::io::Error },
     #[error("conversion error: {desc:}")]
     ConversionError { desc: String },
+    #[error("missing feature: {name:}")]
+    MissingFeatureError { name: String },
+    #[error(r#"feature mismatch error: expect {expect:} for "{name:}", but found {found:}"#)]
+    FeatureMismatchError {
+        name: String,
+        expect: String,
+        found: String,
+    },
     #[error("invalid arguments: {desc:}")]
     InvalidArgumentsError { desc: String },
     #[error("tch error: {desc:}")]
@@ -50,6 +58,30 @@ impl From<Infallible> for Error {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::ser::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::ConversionError {
+            desc: msg.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::de::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::ConversionError {
+            desc: msg.to_string(),
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::MissingFeatureError {
+            name: field.to_owned(),
+        }
+    }
+}
+
 #[cfg(feature = "with-tch")]
 impl From<tch::TchError> for Error {
     fn from(error: tch::TchError) -> Self {
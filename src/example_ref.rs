@@ -0,0 +1,601 @@
+//! Borrowed views of serialized examples.
+//!
+//! The [ExampleRef] type reads features directly from the ProtocolBuffer bytes of a
+//! record, such as those from [BytesReader](crate::BytesReader). The feature names
+//! and values are borrowed from the buffer, so the features not accessed cost nothing
+//! but a scan over their headers.
+
+use crate::{
+    error::Error,
+    types::{Example, Feature},
+};
+use prost::DecodeError;
+use std::{collections::HashMap, str};
+
+const WIRE_VARINT: u8 = 0;
+const WIRE_FIXED64: u8 = 1;
+const WIRE_LEN: u8 = 2;
+const WIRE_FIXED32: u8 = 5;
+
+/// The borrowed view of a serialized example.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExampleRef<'a> {
+    /// The body of the `features` field.
+    entries: &'a [u8],
+    /// The bytes of the top-level message, used when `features` is repeated.
+    bytes: &'a [u8],
+    /// Whether `features` appears more than once and must be merged.
+    split: bool,
+}
+
+impl<'a> ExampleRef<'a> {
+    /// Parse the bytes of a serialized `tensorflow.Example` message.
+    ///
+    /// It only checks the framing of the entries. The feature values are
+    /// parsed on access.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, Error> {
+        let mut entries: &[u8] = &[];
+        let mut num_features_fields = 0;
+
+        let mut reader = WireReader::new(bytes);
+        while let Some((field, value)) = reader.next_field()? {
+            if let (1, WireValue::Len(body)) = (field, value) {
+                entries = body;
+                num_features_fields += 1;
+            }
+        }
+
+        let example = Self {
+            entries,
+            bytes,
+            split: num_features_fields > 1,
+        };
+
+        // check the framing of map entries
+        for entry in example.iter() {
+            entry?;
+        }
+
+        Ok(example)
+    }
+
+    /// Iterate over the features in the order of appearance.
+    ///
+    /// A name appears more than once if the serialized message repeats it,
+    /// in which case the last one takes effect.
+    pub fn iter(&self) -> FeatureIter<'a> {
+        FeatureIter {
+            outer: if self.split {
+                Some(WireReader::new(self.bytes))
+            } else {
+                None
+            },
+            entries: if self.split {
+                WireReader::new(&[])
+            } else {
+                WireReader::new(self.entries)
+            },
+        }
+    }
+
+    /// Iterate over the feature names.
+    pub fn keys(&self) -> impl Iterator<Item = &'a str> {
+        self.iter()
+            .filter_map(|entry| entry.ok())
+            .map(|(name, _)| name)
+    }
+
+    /// Get a feature by name.
+    pub fn get(&self, name: &str) -> Result<Option<FeatureRef<'a>>, Error> {
+        let mut found = None;
+        for entry in self.iter() {
+            let (key, feature) = entry?;
+            if key == name {
+                found = Some(feature);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Get a feature by name, or return an error if it is missing.
+    pub fn get_feature(&self, name: &str) -> Result<FeatureRef<'a>, Error> {
+        self.get(name)?.ok_or_else(|| Error::MissingFeatureError {
+            name: name.to_owned(),
+        })
+    }
+
+    /// Get the values of a bytes list feature.
+    pub fn get_bytes_list(&self, name: &str) -> Result<BytesListRef<'a>, Error> {
+        match self.get_feature(name)? {
+            FeatureRef::BytesList(list) => Ok(list),
+            feature => Err(mismatch(name, "bytes list", &feature)),
+        }
+    }
+
+    /// Get the values of a float list feature.
+    pub fn get_float_list(&self, name: &str) -> Result<FloatListRef<'a>, Error> {
+        match self.get_feature(name)? {
+            FeatureRef::FloatList(list) => Ok(list),
+            feature => Err(mismatch(name, "float list", &feature)),
+        }
+    }
+
+    /// Get the values of an int64 list feature.
+    pub fn get_int64_list(&self, name: &str) -> Result<Int64ListRef<'a>, Error> {
+        match self.get_feature(name)? {
+            FeatureRef::Int64List(list) => Ok(list),
+            feature => Err(mismatch(name, "int64 list", &feature)),
+        }
+    }
+
+    /// Get the single bytes value of a feature without copying.
+    pub fn get_bytes(&self, name: &str) -> Result<&'a [u8], Error> {
+        let mut iter = self.get_bytes_list(name)?.iter();
+        match (iter.next().transpose()?, iter.next()) {
+            (Some(value), None) => Ok(value),
+            _ => Err(Error::FeatureMismatchError {
+                name: name.to_owned(),
+                expect: "a single bytes value".into(),
+                found: "zero or multiple values".into(),
+            }),
+        }
+    }
+
+    /// Get the single string value of a feature without copying.
+    pub fn get_str(&self, name: &str) -> Result<&'a str, Error> {
+        let bytes = self.get_bytes(name)?;
+        crate::types::bytes_to_str(name, bytes)
+    }
+
+    /// Copy the features to an owned [Example].
+    pub fn to_example(&self) -> Result<Example, Error> {
+        self.iter()
+            .map(|entry| {
+                let (name, feature) = entry?;
+                Ok((name.to_owned(), feature.to_feature()?))
+            })
+            .collect::<Result<HashMap<_, _>, Error>>()
+    }
+}
+
+/// The iterator over the features of an [ExampleRef].
+#[derive(Debug, Clone)]
+pub struct FeatureIter<'a> {
+    outer: Option<WireReader<'a>>,
+    entries: WireReader<'a>,
+}
+
+impl<'a> Iterator for FeatureIter<'a> {
+    type Item = Result<(&'a str, FeatureRef<'a>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.entries.next_field() {
+                Ok(Some((1, WireValue::Len(entry)))) => return Some(parse_entry(entry)),
+                Ok(Some(_)) => continue,
+                Ok(None) => (),
+                Err(err) => return Some(Err(err)),
+            }
+
+            // move to the next `features` field if the message repeats it
+            let outer = self.outer.as_mut()?;
+            match outer.next_field() {
+                Ok(Some((1, WireValue::Len(body)))) => self.entries = WireReader::new(body),
+                Ok(Some(_)) => (),
+                Ok(None) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// The borrowed view of a feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeatureRef<'a> {
+    BytesList(BytesListRef<'a>),
+    FloatList(FloatListRef<'a>),
+    Int64List(Int64ListRef<'a>),
+    None,
+}
+
+impl<'a> FeatureRef<'a> {
+    /// Copy the values to an owned [Feature].
+    pub fn to_feature(&self) -> Result<Feature, Error> {
+        let feature = match self {
+            Self::BytesList(list) => Feature::BytesList(
+                list.iter()
+                    .map(|bytes| bytes.map(|bytes| bytes.to_vec()))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Self::FloatList(list) => Feature::FloatList(list.iter().collect::<Result<_, _>>()?),
+            Self::Int64List(list) => Feature::Int64List(list.iter().collect::<Result<_, _>>()?),
+            Self::None => Feature::None,
+        };
+        Ok(feature)
+    }
+
+    fn kind_name(&self) -> &'static str {
+        match self {
+            Self::BytesList(_) => "bytes list",
+            Self::FloatList(_) => "float list",
+            Self::Int64List(_) => "int64 list",
+            Self::None => "none",
+        }
+    }
+}
+
+macro_rules! impl_list_ref {
+    ($name:ident, $iter:ident, $item:ty, $doc:literal) => {
+        #[doc = $doc]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name<'a> {
+            /// The map entry of the feature.
+            entry: &'a [u8],
+            /// Number of list fields overridden by a later case of the oneof.
+            skip: usize,
+        }
+
+        impl<'a> $name<'a> {
+            /// Iterate over the values.
+            pub fn iter(&self) -> $iter<'a> {
+                $iter::new(ListFields::new(self.entry, self.skip))
+            }
+
+            /// Count the values. It scans the values without copying them.
+            pub fn len(&self) -> Result<usize, Error> {
+                self.iter()
+                    .try_fold(0, |count, value| value.map(|_| count + 1))
+            }
+
+            /// Return true if the list has no values.
+            pub fn is_empty(&self) -> Result<bool, Error> {
+                Ok(self.iter().next().transpose()?.is_none())
+            }
+
+            /// Copy the values to a vector.
+            pub fn to_vec(&self) -> Result<Vec<$item>, Error> {
+                self.iter().collect()
+            }
+        }
+
+        impl<'a> IntoIterator for $name<'a> {
+            type Item = Result<$item, Error>;
+            type IntoIter = $iter<'a>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.iter()
+            }
+        }
+    };
+}
+
+impl_list_ref!(
+    BytesListRef,
+    BytesListIter,
+    &'a [u8],
+    "The borrowed values of a bytes list feature."
+);
+impl_list_ref!(
+    FloatListRef,
+    FloatListIter,
+    f32,
+    "The borrowed values of a float list feature."
+);
+impl_list_ref!(
+    Int64ListRef,
+    Int64ListIter,
+    i64,
+    "The borrowed values of an int64 list feature."
+);
+
+/// The iterator over the values of a bytes list feature.
+#[derive(Debug, Clone)]
+pub struct BytesListIter<'a> {
+    fields: ListFields<'a>,
+}
+
+impl<'a> BytesListIter<'a> {
+    fn new(fields: ListFields<'a>) -> Self {
+        Self { fields }
+    }
+}
+
+impl<'a> Iterator for BytesListIter<'a> {
+    type Item = Result<&'a [u8], Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.fields.next_field().transpose()? {
+                Ok((1, WireValue::Len(bytes))) => return Some(Ok(bytes)),
+                Ok(_) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// The iterator over the values of a float list feature.
+#[derive(Debug, Clone)]
+pub struct FloatListIter<'a> {
+    fields: ListFields<'a>,
+    /// The remaining values of the current packed field.
+    packed: WireReader<'a>,
+}
+
+impl<'a> FloatListIter<'a> {
+    fn new(fields: ListFields<'a>) -> Self {
+        Self {
+            fields,
+            packed: WireReader::new(&[]),
+        }
+    }
+}
+
+impl<'a> Iterator for FloatListIter<'a> {
+    type Item = Result<f32, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // values in a packed field
+            if !self.packed.is_empty() {
+                return Some(self.packed.read_fixed32().map(f32::from_bits));
+            }
+
+            match self.fields.next_field().transpose()? {
+                Ok((1, WireValue::Len(packed))) => {
+                    if packed.len() % 4 != 0 {
+                        self.fields.stop();
+                        return Some(Err(decode_error("invalid packed float list length")));
+                    }
+                    self.packed = WireReader::new(packed);
+                }
+                Ok((1, WireValue::Fixed32(bits))) => return Some(Ok(f32::from_bits(bits))),
+                Ok(_) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// The iterator over the values of an int64 list feature.
+#[derive(Debug, Clone)]
+pub struct Int64ListIter<'a> {
+    fields: ListFields<'a>,
+    /// The remaining values of the current packed field.
+    packed: WireReader<'a>,
+}
+
+impl<'a> Int64ListIter<'a> {
+    fn new(fields: ListFields<'a>) -> Self {
+        Self {
+            fields,
+            packed: WireReader::new(&[]),
+        }
+    }
+}
+
+impl<'a> Iterator for Int64ListIter<'a> {
+    type Item = Result<i64, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // values in a packed field
+            if !self.packed.is_empty() {
+                return Some(self.packed.read_varint().map(|value| value as i64));
+            }
+
+            match self.fields.next_field().transpose()? {
+                Ok((1, WireValue::Len(packed))) => self.packed = WireReader::new(packed),
+                Ok((1, WireValue::Varint(value))) => return Some(Ok(value as i64)),
+                Ok(_) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+fn parse_entry(entry: &[u8]) -> Result<(&str, FeatureRef<'_>), Error> {
+    let mut key: &[u8] = &[];
+    // the case of the oneof and the index of the first list field of the case
+    let mut case = None;
+    let mut num_lists = 0;
+
+    let mut reader = WireReader::new(entry);
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            (1, WireValue::Len(bytes)) => key = bytes,
+            (2, WireValue::Len(body)) => {
+                // like protobuf, the repeated lists of the same case are merged,
+                // while a distinct case replaces the previous one
+                let mut reader = WireReader::new(body);
+                while let Some((field, value)) = reader.next_field()? {
+                    if let (1..=3, WireValue::Len(_)) = (field, value) {
+                        match case {
+                            Some((prev, _)) if prev == field => (),
+                            _ => case = Some((field, num_lists)),
+                        }
+                        num_lists += 1;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let feature = match case {
+        Some((1, skip)) => FeatureRef::BytesList(BytesListRef { entry, skip }),
+        Some((2, skip)) => FeatureRef::FloatList(FloatListRef { entry, skip }),
+        Some((3, skip)) => FeatureRef::Int64List(Int64ListRef { entry, skip }),
+        _ => FeatureRef::None,
+    };
+    let key = str::from_utf8(key).map_err(|_| decode_error("invalid UTF-8 feature name"))?;
+    Ok((key, feature))
+}
+
+fn mismatch(name: &str, expect: &str, feature: &FeatureRef) -> Error {
+    Error::FeatureMismatchError {
+        name: name.to_owned(),
+        expect: expect.to_owned(),
+        found: feature.kind_name().to_owned(),
+    }
+}
+
+fn decode_error(desc: &'static str) -> Error {
+    Error::ExampleDecodeError {
+        error: DecodeError::new(desc),
+    }
+}
+
+/// The fields of the list messages of a feature, spanning the repeated lists.
+#[derive(Debug, Clone)]
+struct ListFields<'a> {
+    /// The remaining fields of the map entry.
+    entry: WireReader<'a>,
+    /// The remaining fields of the current feature message.
+    feature: WireReader<'a>,
+    /// The remaining fields of the current list message.
+    list: WireReader<'a>,
+    /// Number of list messages to skip.
+    skip: usize,
+}
+
+impl<'a> ListFields<'a> {
+    fn new(entry: &'a [u8], skip: usize) -> Self {
+        Self {
+            entry: WireReader::new(entry),
+            feature: WireReader::new(&[]),
+            list: WireReader::new(&[]),
+            skip,
+        }
+    }
+
+    fn stop(&mut self) {
+        *self = Self::new(&[], 0);
+    }
+
+    /// Read the next field of the list messages.
+    fn next_field(&mut self) -> Result<Option<(u32, WireValue<'a>)>, Error> {
+        let result = self.read_field();
+        if result.is_err() {
+            self.stop();
+        }
+        result
+    }
+
+    fn read_field(&mut self) -> Result<Option<(u32, WireValue<'a>)>, Error> {
+        loop {
+            if let Some(field) = self.list.next_field()? {
+                return Ok(Some(field));
+            }
+
+            // move to the next list message
+            if let Some((field, value)) = self.feature.next_field()? {
+                if let (1..=3, WireValue::Len(body)) = (field, value) {
+                    match self.skip {
+                        0 => self.list = WireReader::new(body),
+                        _ => self.skip -= 1,
+                    }
+                }
+                continue;
+            }
+
+            // move to the next feature message
+            match self.entry.next_field()? {
+                Some((2, WireValue::Len(body))) => self.feature = WireReader::new(body),
+                Some(_) => (),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum WireValue<'a> {
+    Varint(u64),
+    Fixed64,
+    Len(&'a [u8]),
+    Fixed32(u32),
+}
+
+/// A cursor over ProtocolBuffer wire format bytes.
+#[derive(Debug, Clone)]
+struct WireReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> WireReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Read the next field. The reader is emptied on error so that iteration stops.
+    fn next_field(&mut self) -> Result<Option<(u32, WireValue<'a>)>, Error> {
+        let result = self.read_field();
+        if result.is_err() {
+            self.bytes = &[];
+        }
+        result
+    }
+
+    fn read_field(&mut self) -> Result<Option<(u32, WireValue<'a>)>, Error> {
+        if self.bytes.is_empty() {
+            return Ok(None);
+        }
+
+        let key = self.read_varint()?;
+        let field = (key >> 3) as u32;
+        let value = match (key & 0x7) as u8 {
+            WIRE_VARINT => WireValue::Varint(self.read_varint()?),
+            WIRE_FIXED64 => {
+                self.take(8)?;
+                WireValue::Fixed64
+            }
+            WIRE_LEN => {
+                let len = self.read_varint()?;
+                if len > self.bytes.len() as u64 {
+                    return Err(decode_error("buffer underflow"));
+                }
+                WireValue::Len(self.take(len as usize)?)
+            }
+            WIRE_FIXED32 => WireValue::Fixed32(self.read_fixed32()?),
+            _ => return Err(decode_error("unsupported wire type")),
+        };
+        if field == 0 {
+            return Err(decode_error("invalid field number"));
+        }
+
+        Ok(Some((field, value)))
+    }
+
+    fn read_varint(&mut self) -> Result<u64, Error> {
+        let mut value = 0u64;
+        for (index, &byte) in self.bytes.iter().enumerate().take(10) {
+            value |= ((byte & 0x7f) as u64) << (index * 7);
+            if byte & 0x80 == 0 {
+                self.bytes = &self.bytes[(index + 1)..];
+                return Ok(value);
+            }
+        }
+        self.bytes = &[];
+        Err(decode_error("invalid varint"))
+    }
+
+    fn read_fixed32(&mut self) -> Result<u32, Error> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.bytes.len() {
+            self.bytes = &[];
+            return Err(decode_error("buffer underflow"));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+}
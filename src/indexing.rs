@@ -0,0 +1,498 @@
+//! The file listing and record indexing shared by the dataset types.
+//!
+//! The functions here are blocking. The [dataset](crate::dataset) module runs them
+//! on blocking threads, while the [blocking_dataset](crate::blocking_dataset) module
+//! runs them on its indexing threads.
+
+use crate::error::Error;
+use std::{
+    fmt::{self, Debug, Formatter},
+    fs::File,
+    hash::{Hash, Hasher},
+    io::{Read, Seek, SeekFrom},
+    num::NonZeroUsize,
+    ops::Range,
+    path::{Path, PathBuf, MAIN_SEPARATOR},
+    sync::{Arc, Mutex},
+};
+
+const READ_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// The summary of a TFRecord file indexed by a dataset.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FileStats {
+    /// The file path, or the storage-specific path.
+    pub path: PathBuf,
+    /// Number of indexed records.
+    pub num_records: usize,
+    /// Number of scanned bytes from the start of file.
+    pub num_bytes: u64,
+    /// The byte ranges skipped due to corruption.
+    ///
+    /// It is always empty unless `skip_corrupted` is enabled on the dataset initializer.
+    pub corrupted_regions: Vec<Range<u64>>,
+    /// The byte range of the incomplete record at the end of file, which is not indexed.
+    ///
+    /// It is always `None` unless `allow_incomplete` is enabled on the dataset initializer.
+    pub incomplete_tail: Option<Range<u64>>,
+}
+
+/// The progress of dataset indexing, reported once a file is indexed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IndexProgress {
+    /// Total number of files to index.
+    pub num_files: usize,
+    /// Number of indexed files.
+    pub num_files_done: usize,
+    /// Number of scanned bytes in indexed files.
+    pub num_bytes: u64,
+    /// Number of records found in indexed files.
+    pub num_records: usize,
+    /// The summary of the file just indexed.
+    pub file: FileStats,
+}
+
+/// The callback that receives [IndexProgress] during dataset indexing.
+///
+/// The callback is called from the indexing workers, and the calls are never concurrent.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(&IndexProgress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: 'static + Fn(&IndexProgress) + Send + Sync,
+    {
+        Self(Arc::new(callback))
+    }
+}
+
+impl Debug for ProgressCallback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "ProgressCallback")
+    }
+}
+
+impl PartialEq for ProgressCallback {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ProgressCallback {}
+
+impl Hash for ProgressCallback {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const u8).hash(state);
+    }
+}
+
+/// Accumulates the progress across indexing workers.
+#[derive(Debug)]
+pub(crate) struct ProgressTracker {
+    callback: Option<ProgressCallback>,
+    num_files: usize,
+    counts: Mutex<ProgressCounts>,
+}
+
+#[derive(Debug, Default)]
+struct ProgressCounts {
+    num_files_done: usize,
+    num_bytes: u64,
+    num_records: usize,
+}
+
+impl ProgressTracker {
+    pub(crate) fn new(callback: Option<ProgressCallback>, num_files: usize) -> Self {
+        Self {
+            callback,
+            num_files,
+            counts: Mutex::new(ProgressCounts::default()),
+        }
+    }
+
+    pub(crate) fn file_done(&self, file: &FileStats) {
+        let callback = match &self.callback {
+            Some(callback) => callback,
+            None => return,
+        };
+
+        // keep the lock during the call to serialize the reports
+        let mut counts = self.counts.lock().unwrap();
+        counts.num_files_done += 1;
+        counts.num_bytes += file.num_bytes;
+        counts.num_records += file.num_records;
+
+        (callback.0)(&IndexProgress {
+            num_files: self.num_files,
+            num_files_done: counts.num_files_done,
+            num_bytes: counts.num_bytes,
+            num_records: counts.num_records,
+            file: file.clone(),
+        });
+    }
+}
+
+/// The file filter used to open datasets from a directory.
+///
+/// The patterns follow the syntax of [glob](https://docs.rs/glob/) and are matched
+/// against the file paths relative to the walked directory, such as `2020-01-01/train-0.tfrecord`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct DirFilter {
+    /// The patterns to include files.
+    ///
+    /// A file is included if it matches any of the patterns.
+    /// It accepts all files if it is empty.
+    pub include: Vec<String>,
+    /// The patterns to exclude files.
+    ///
+    /// A file is excluded if it matches any of the patterns, even if it is included.
+    pub exclude: Vec<String>,
+    /// Maximum depth of directory recursion.
+    ///
+    /// The files directly under the walked directory have depth 1.
+    /// It has no limit if it is `None`.
+    pub max_depth: Option<NonZeroUsize>,
+}
+
+impl DirFilter {
+    /// List the matched files under the directory in alphabetical order.
+    pub(crate) fn walk(&self, dir: &Path) -> Result<Vec<PathBuf>, Error> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    glob::Pattern::new(pattern).map_err(|err| Error::InvalidArgumentsError {
+                        desc: format!(r#"invalid glob pattern "{}": {}"#, pattern, err),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let include = compile(&self.include)?;
+        let exclude = compile(&self.exclude)?;
+        let max_depth = self.max_depth.map(|depth| depth.get());
+
+        let mut paths = vec![];
+        let mut dirs = vec![(dir.to_owned(), 1)];
+
+        while let Some((curr_dir, depth)) = dirs.pop() {
+            for entry in curr_dir.read_dir()? {
+                let entry = entry?;
+                let path = entry.path();
+                let file_type = entry.file_type()?;
+
+                if file_type.is_dir() {
+                    if max_depth.map(|max_depth| depth < max_depth).unwrap_or(true) {
+                        dirs.push((path, depth + 1));
+                    }
+                    continue;
+                }
+
+                // follow symbolic links to files
+                if !path.is_file() {
+                    continue;
+                }
+
+                let rel_path =
+                    path.strip_prefix(dir)
+                        .map_err(|_| Error::InvalidArgumentsError {
+                            desc: format!(
+                                r#"the path "{}" is not under the directory "{}""#,
+                                path.display(),
+                                dir.display()
+                            ),
+                        })?;
+
+                let is_included = include.is_empty()
+                    || include.iter().any(|pattern| pattern.matches_path(rel_path));
+                let is_excluded = exclude.iter().any(|pattern| pattern.matches_path(rel_path));
+
+                if is_included && !is_excluded {
+                    paths.push(path);
+                }
+            }
+        }
+
+        paths.sort();
+        Ok(paths)
+    }
+}
+
+/// List the files by a path prefix in alphabetical order.
+///
+/// If the path ends with the path separator, it lists all files under the directory.
+/// Otherwise, it lists the files with the path prefix.
+pub(crate) fn list_prefix(prefix: &str) -> Result<Vec<PathBuf>, Error> {
+    let prefix_path: &Path = prefix.as_ref();
+
+    // assume the prefix is a directly if it ends with the separator
+    let (dir, file_name_prefix_opt) = if prefix.ends_with(MAIN_SEPARATOR) {
+        (prefix_path, None)
+    } else {
+        let dir = match prefix_path.parent() {
+            Some(dir) if dir.as_os_str().is_empty() => Path::new("."),
+            Some(dir) => dir,
+            None => Path::new("."),
+        };
+        let file_name_prefix = prefix_path
+            .file_name()
+            .ok_or_else(|| Error::InvalidArgumentsError {
+                desc: format!(r#"the prefix "{}" does not end with a file name"#, prefix),
+            })?
+            .to_str()
+            .ok_or_else(|| Error::UnicodeError {
+                desc: format!(r#"the prefix "{}" is not Unicode"#, prefix),
+            })?;
+        (dir, Some(file_name_prefix))
+    };
+
+    // filter paths
+    let mut paths = vec![];
+    for entry in dir.read_dir()? {
+        let entry = entry?;
+        if !entry.metadata()?.is_file() {
+            continue;
+        }
+
+        let path = entry.path();
+        let file_name = entry
+            .file_name()
+            .into_string()
+            .map_err(|_| Error::UnicodeError {
+                desc: format!(r#"the file path "{}" is not Unicode"#, path.display()),
+            })?;
+        let is_matched = file_name_prefix_opt
+            .map(|file_name_prefix| file_name.starts_with(file_name_prefix))
+            .unwrap_or(true);
+        if is_matched {
+            paths.push(path);
+        }
+    }
+
+    // sort paths
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// List the files matching a glob pattern in alphabetical order.
+pub(crate) fn list_glob(pattern: &str) -> Result<Vec<PathBuf>, Error> {
+    let entries = glob::glob(pattern).map_err(|err| Error::InvalidArgumentsError {
+        desc: format!(r#"invalid glob pattern "{}": {}"#, pattern, err),
+    })?;
+
+    let mut paths = vec![];
+    for entry in entries {
+        let path = entry
+            .map_err(|err| Error::from(std::io::Error::new(err.error().kind(), err.to_string())))?;
+        if path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// The options to scan records in a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct ScanOptions {
+    pub check_integrity: bool,
+    pub skip_corrupted: bool,
+    /// Stop at an incomplete record at the end of file instead of failing.
+    pub allow_incomplete: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct ScanOutput {
+    pub indexes: Vec<(u64, usize)>,
+    pub end: u64,
+    pub corrupted_regions: Vec<Range<u64>>,
+    pub incomplete_tail: Option<Range<u64>>,
+}
+
+impl ScanOutput {
+    /// Summarize the scanned file.
+    pub(crate) fn file_stats(&self, path: PathBuf) -> FileStats {
+        FileStats {
+            path,
+            num_records: self.indexes.len(),
+            num_bytes: self.end,
+            corrupted_regions: self.corrupted_regions.clone(),
+            incomplete_tail: self.incomplete_tail.clone(),
+        }
+    }
+}
+
+/// The file to be scanned by ranged reads.
+pub(crate) trait RangeSource {
+    /// Read `len` bytes starting from `offset`, or fewer bytes at the end of file.
+    fn read_range(&mut self, offset: u64, len: usize) -> Result<Vec<u8>, Error>;
+}
+
+impl RangeSource for File {
+    fn read_range(&mut self, offset: u64, len: usize) -> Result<Vec<u8>, Error> {
+        self.seek(SeekFrom::Start(offset))?;
+        let mut bytes = vec![];
+        self.take(len as u64).read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// Scan the records of a file starting from `start`.
+///
+/// The file is read in large chunks to avoid a read per record. In case of corrupted
+/// records, it looks for the next valid record byte by byte if `skip_corrupted` is set.
+pub(crate) fn scan_records<S>(
+    source: S,
+    size: u64,
+    start: u64,
+    options: ScanOptions,
+) -> Result<ScanOutput, Error>
+where
+    S: RangeSource,
+{
+    let ScanOptions {
+        check_integrity,
+        skip_corrupted,
+        allow_incomplete,
+    } = options;
+
+    if start > size {
+        return Err(Error::InvalidArgumentsError {
+            desc: format!(
+                "the file is truncated to {} bytes after {} bytes were indexed",
+                size, start
+            ),
+        });
+    }
+
+    let mut buffer = RangeBuffer {
+        source,
+        size,
+        start,
+        bytes: vec![],
+    };
+    let mut indexes = vec![];
+    let mut corrupted_regions = vec![];
+    let mut incomplete_tail = None;
+    let mut offset = start;
+
+    while offset < size {
+        match buffer.check_record(offset, check_integrity)? {
+            Scanned::Record(len) => {
+                indexes.push((offset + 12, len));
+                offset += len as u64 + 16;
+            }
+            Scanned::Incomplete if allow_incomplete => {
+                incomplete_tail = Some(offset..size);
+                break;
+            }
+            Scanned::Incomplete if skip_corrupted => {
+                corrupted_regions.push(offset..size);
+                offset = size;
+            }
+            Scanned::Incomplete => return Err(Error::UnexpectedEofError),
+            Scanned::Corrupted(_) if skip_corrupted => {
+                let mut next = offset + 1;
+                while next < size {
+                    if let Scanned::Record(_) = buffer.check_record(next, true)? {
+                        break;
+                    }
+                    next += 1;
+                }
+                corrupted_regions.push(offset..next);
+                offset = next;
+            }
+            Scanned::Corrupted(err) => return Err(err),
+        }
+    }
+
+    Ok(ScanOutput {
+        indexes,
+        end: offset,
+        corrupted_regions,
+        incomplete_tail,
+    })
+}
+
+enum Scanned {
+    Record(usize),
+    Incomplete,
+    Corrupted(Error),
+}
+
+struct RangeBuffer<S> {
+    source: S,
+    size: u64,
+    start: u64,
+    bytes: Vec<u8>,
+}
+
+impl<S> RangeBuffer<S>
+where
+    S: RangeSource,
+{
+    fn check_record(&mut self, offset: u64, check_integrity: bool) -> Result<Scanned, Error> {
+        if offset + 12 > self.size {
+            return Ok(Scanned::Incomplete);
+        }
+
+        let header = self.read(offset, 12)?;
+        let len_buf = &header[0..8];
+        let expect_cksum = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        if check_integrity {
+            if let Err(err) = crate::utils::verify_checksum(len_buf, expect_cksum) {
+                return Ok(Scanned::Corrupted(err));
+            }
+        }
+
+        let mut len_bytes = [0u8; 8];
+        len_bytes.copy_from_slice(len_buf);
+        let len = u64::from_le_bytes(len_bytes);
+        let data_offset = offset + 12;
+
+        // the length can be arbitrary large if the integrity is not checked
+        match data_offset
+            .checked_add(len)
+            .and_then(|end| end.checked_add(4))
+        {
+            Some(end) if end <= self.size => (),
+            _ => return Ok(Scanned::Incomplete),
+        }
+        let len = len as usize;
+
+        if check_integrity {
+            let data = self.read(data_offset, len + 4)?;
+            let expect_cksum =
+                u32::from_le_bytes([data[len], data[len + 1], data[len + 2], data[len + 3]]);
+            if let Err(err) = crate::utils::verify_checksum(&data[0..len], expect_cksum) {
+                return Ok(Scanned::Corrupted(err));
+            }
+        }
+
+        Ok(Scanned::Record(len))
+    }
+
+    fn read(&mut self, offset: u64, len: usize) -> Result<&[u8], Error> {
+        let end = offset + len as u64;
+        if end > self.size {
+            return Err(Error::UnexpectedEofError);
+        }
+
+        // fetch a new chunk if the range is not buffered
+        let buffer_end = self.start + self.bytes.len() as u64;
+        if offset < self.start || end > buffer_end {
+            let fetch_len = (len.max(READ_CHUNK_SIZE) as u64).min(self.size - offset);
+            let bytes = self.source.read_range(offset, fetch_len as usize)?;
+            if (bytes.len() as u64) < len as u64 {
+                return Err(Error::UnexpectedEofError);
+            }
+            self.start = offset;
+            self.bytes = bytes;
+        }
+
+        let begin = (offset - self.start) as usize;
+        Ok(&self.bytes[begin..(begin + len)])
+    }
+}
@@ -6,25 +6,47 @@
 //! - `full`: Enable all features.
 //! - `async_`: Enable async/await feature.
 //! - `dataset`: Enable the dataset API.
+//! - `s3`: Enable reading datasets from Amazon S3 and S3-compatible object stores.
+//! - `blocking_dataset`: Enable the blocking dataset API that does not require an async runtime.
 //! - `summary`: Enable the summary and event API, which is mainly targeted for TensorBoard.
+//! - `derive`: Enable `#[derive(TfExample)]` to convert user structs from and to examples.
 //!
 //! Third-party supports:
-//! - `with-serde`: Enable interoperability with [serde](https://crates.io/crates/serde) to serialize and deserialize example types.
-//! - `with-tch`: Enable [tch](https://crates.io/crates/tch) types support.
+//! - `with-serde`: Enable interoperability with [serde](https://crates.io/crates/serde) to serialize and deserialize example types, and to map serde-annotated structs onto examples.
+//! - `with-tch`: Enable [tch](https://crates.io/crates/tch) types support, including conversions between features and tensors.
 //! - `with-image`: Enable [image](https://crates.io/crates/image) types support.
-//! - `with-ndarray`: Enable [ndarray](https://crates.io/crates/ndarray) types support.
+//! - `with-ndarray`: Enable [ndarray](https://crates.io/crates/ndarray) types support, including conversions between features and arrays.
+//! - `with-rayon`: Enable parallel iteration over [BlockingDataset] using [rayon](https://crates.io/crates/rayon).
+//!
+//! The initializers, such as [RecordReaderInit], are non-exhaustive to allow new options
+//! in the future. Construct them by [Default::default] or their constructors, and change
+//! the options by the public fields or the `with_*` methods.
 
 // mods
 
+#[cfg(any(feature = "with-ndarray", feature = "with-tch"))]
+pub mod batch;
+#[cfg(feature = "blocking_dataset")]
+pub mod blocking_dataset;
 #[cfg(feature = "dataset")]
 pub mod dataset;
 
 mod conversions;
+pub mod encoding;
 pub mod error;
+pub mod example_ref;
+#[cfg(any(feature = "dataset", feature = "blocking_dataset"))]
+mod indexing;
 pub mod io;
 pub mod markers;
+pub mod parser;
+pub mod projection;
 pub mod protos;
 pub mod reader;
+pub mod schema;
+#[cfg(feature = "serde")]
+pub mod serde_example;
+pub mod statistics;
 pub mod summary;
 pub mod types;
 mod utils;
@@ -33,16 +55,42 @@ pub mod writer;
 // re-exports
 
 pub use error::Error;
-pub use markers::{GenericRecord, HistogramProtoElement, TensorProtoElement};
-pub use protos::{Event, Example as RawExample, Summary};
+pub use example_ref::{BytesListRef, ExampleRef, FeatureRef, FloatListRef, Int64ListRef};
+pub use markers::{
+    FeatureElement, FeatureValue, GenericRecord, HistogramProtoElement, TensorProtoDecode,
+    TensorProtoElement, TfExample,
+};
+pub use parser::{ExampleParser, FeatureSpec, FeatureType, ParsedExample, SparseSpec};
+pub use projection::Projection;
+pub use protos::{Event, Example as RawExample, SequenceExample as RawSequenceExample, Summary};
 
 #[cfg(feature = "async_")]
 pub use reader::RecordStreamInit;
-pub use reader::{BytesReader, ExampleReader, RawExampleReader, RecordReader, RecordReaderInit};
+pub use reader::{
+    BytesReader, ExampleReader, RawExampleReader, RawSequenceExampleReader, RecordReader,
+    RecordReaderInit, SequenceExampleReader,
+};
+pub use schema::{FeatureSchema, Schema};
+pub use statistics::{BytesStatistics, FeatureStatistics, NumericStatistics, Statistics};
 #[cfg(feature = "summary")]
 pub use summary::{EventInit, EventWriter, EventWriterInit, SummaryInit};
-pub use types::{Example, Feature, Histogram};
-pub use writer::{BytesWriter, ExampleWriter, RawExampleWriter, RecordWriter, RecordWriterInit};
+pub use types::{Example, ExampleBuilder, ExampleExt, Feature, Histogram, SequenceExample};
+pub use writer::{
+    BytesWriter, ExampleWriter, RawExampleWriter, RawSequenceExampleWriter, RecordWriter,
+    RecordWriterInit, SequenceExampleWriter,
+};
+
+#[cfg(feature = "derive")]
+pub use tfrecord_derive::TfExample;
 
+#[cfg(feature = "blocking_dataset")]
+pub use blocking_dataset::{BlockingDataset, BlockingDatasetInit};
 #[cfg(feature = "dataset")]
-pub use dataset::{Dataset, DatasetInit};
+pub use dataset::{
+    BucketInit, CacheMode, Checkpoint, Dataset, DatasetInit, DatasetStreamExt, InterleaveInit,
+    LocalStorage, SampleInit, ShardBy, StopPolicy, Storage,
+};
+#[cfg(feature = "s3")]
+pub use dataset::{S3Storage, S3StorageInit};
+#[cfg(any(feature = "dataset", feature = "blocking_dataset"))]
+pub use indexing::{DirFilter, FileStats, IndexProgress, ProgressCallback};
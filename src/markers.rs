@@ -1,11 +1,22 @@
 //! Marker traits.
 
 use crate::{
+    encoding,
     error::Error,
-    protos::{summary::Image, DataType, Event, Example as RawExample},
-    types::Example,
+    projection::Projection,
+    protos::{
+        summary::Image, DataType, Event, Example as RawExample,
+        SequenceExample as RawSequenceExample, TensorProto,
+    },
+    types::{
+        bytes_list, bytes_to_str, float_list, int64_list, single_value, Example, Feature,
+        SequenceExample,
+    },
 };
+use half::{bf16, f16};
+use num_complex::Complex;
 use prost::Message;
+use std::convert::{TryFrom, TryInto};
 
 /// The trait marks the type that can be serailized to or deserialized from TFRecord raw bytes.
 pub trait GenericRecord
@@ -14,6 +25,14 @@ where
 {
     fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error>;
     fn to_bytes(record: Self) -> Result<Vec<u8>, Error>;
+
+    /// Decode the record with only the features selected by the projection.
+    ///
+    /// It decodes the full record by default.
+    fn from_bytes_projected(bytes: Vec<u8>, projection: &Projection) -> Result<Self, Error> {
+        let _ = projection;
+        Self::from_bytes(bytes)
+    }
 }
 
 impl GenericRecord for Vec<u8> {
@@ -33,9 +52,7 @@ impl GenericRecord for RawExample {
     }
 
     fn to_bytes(record: Self) -> Result<Vec<u8>, Error> {
-        let mut bytes = vec![];
-        RawExample::encode(&record, &mut bytes)?;
-        Ok(bytes)
+        Ok(encoding::encode_example(&record))
     }
 }
 
@@ -47,10 +64,36 @@ impl GenericRecord for Example {
     }
 
     fn to_bytes(example: Self) -> Result<Vec<u8>, Error> {
-        let mut bytes = vec![];
         let raw_example = RawExample::from(example);
-        RawExample::encode(&raw_example, &mut bytes)?;
-        Ok(bytes)
+        Ok(encoding::encode_example(&raw_example))
+    }
+
+    fn from_bytes_projected(bytes: Vec<u8>, projection: &Projection) -> Result<Self, Error> {
+        projection.decode_example(&bytes)
+    }
+}
+
+impl GenericRecord for RawSequenceExample {
+    fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        let example = RawSequenceExample::decode(bytes.as_ref())?;
+        Ok(example)
+    }
+
+    fn to_bytes(record: Self) -> Result<Vec<u8>, Error> {
+        Ok(encoding::encode_sequence_example(&record))
+    }
+}
+
+impl GenericRecord for SequenceExample {
+    fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        let raw_example = RawSequenceExample::decode(bytes.as_ref())?;
+        let example = SequenceExample::from(raw_example);
+        Ok(example)
+    }
+
+    fn to_bytes(example: Self) -> Result<Vec<u8>, Error> {
+        let raw_example = RawSequenceExample::from(example);
+        Ok(encoding::encode_sequence_example(&raw_example))
     }
 }
 
@@ -67,6 +110,53 @@ impl GenericRecord for Event {
     }
 }
 
+/// The marker trait for value types of [Feature] lists.
+///
+/// It is implemented by `f32` for [Feature::FloatList] and `i64` for [Feature::Int64List].
+pub trait FeatureElement
+where
+    Self: Copy,
+{
+    /// The description of the feature type used in error messages.
+    const TYPE_NAME: &'static str;
+
+    /// Get the values if the feature has this value type.
+    fn feature_values(feature: &Feature) -> Option<&[Self]>;
+
+    /// Build a feature from values.
+    fn into_feature(values: Vec<Self>) -> Feature;
+}
+
+impl FeatureElement for f32 {
+    const TYPE_NAME: &'static str = "a float list";
+
+    fn feature_values(feature: &Feature) -> Option<&[Self]> {
+        match feature {
+            Feature::FloatList(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    fn into_feature(values: Vec<Self>) -> Feature {
+        Feature::FloatList(values)
+    }
+}
+
+impl FeatureElement for i64 {
+    const TYPE_NAME: &'static str = "an int64 list";
+
+    fn feature_values(feature: &Feature) -> Option<&[Self]> {
+        match feature {
+            Feature::Int64List(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    fn into_feature(values: Vec<Self>) -> Feature {
+        Feature::Int64List(values)
+    }
+}
+
 /// The marker trait that can be converted to elements of [TensorProto](crate::protos::TensorProto).
 pub trait TensorProtoElement
 where
@@ -77,8 +167,20 @@ where
     fn to_bytes(&self) -> Vec<u8>;
 }
 
+/// The trait that decodes elements of [TensorProto](crate::protos::TensorProto).
+pub trait TensorProtoDecode
+where
+    Self: TensorProtoElement,
+{
+    /// Decode a value from its little-endian bytes in `tensor_content`.
+    fn from_bytes(bytes: &[u8]) -> Self;
+
+    /// Get the values in the typed field of the tensor, such as `float_val`.
+    fn typed_values(tensor: &TensorProto) -> Vec<Self>;
+}
+
 macro_rules! impl_to_le_bytes {
-    ($ty:ty, $dtype:expr) => {
+    ($ty:ty, $dtype:expr, $field:ident, $convert:expr) => {
         impl TensorProtoElement for $ty {
             const DATA_TYPE: DataType = $dtype;
 
@@ -86,21 +188,96 @@ macro_rules! impl_to_le_bytes {
                 self.to_le_bytes().iter().cloned().collect()
             }
         }
+
+        impl TensorProtoDecode for $ty {
+            fn from_bytes(bytes: &[u8]) -> Self {
+                Self::from_le_bytes(bytes.try_into().unwrap())
+            }
+
+            fn typed_values(tensor: &TensorProto) -> Vec<Self> {
+                tensor.$field.iter().cloned().map($convert).collect()
+            }
+        }
+    };
+}
+
+impl_to_le_bytes!(u8, DataType::DtUint8, int_val, |value| value as u8);
+impl_to_le_bytes!(u16, DataType::DtUint16, int_val, |value| value as u16);
+impl_to_le_bytes!(u32, DataType::DtUint32, uint32_val, |value| value);
+impl_to_le_bytes!(u64, DataType::DtUint64, uint64_val, |value| value);
+impl_to_le_bytes!(i8, DataType::DtInt8, int_val, |value| value as i8);
+impl_to_le_bytes!(i16, DataType::DtInt16, int_val, |value| value as i16);
+impl_to_le_bytes!(i32, DataType::DtInt32, int_val, |value| value);
+impl_to_le_bytes!(i64, DataType::DtInt64, int64_val, |value| value);
+impl_to_le_bytes!(f32, DataType::DtFloat, float_val, |value| value);
+impl_to_le_bytes!(f64, DataType::DtDouble, double_val, |value| value);
+impl_to_le_bytes!(f16, DataType::DtHalf, half_val, |value| {
+    f16::from_bits(value as u16)
+});
+impl_to_le_bytes!(bf16, DataType::DtBfloat16, half_val, |value| {
+    bf16::from_bits(value as u16)
+});
+
+impl TensorProtoElement for bool {
+    const DATA_TYPE: DataType = DataType::DtBool;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        vec![*self as u8]
+    }
+}
+
+impl TensorProtoDecode for bool {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        bytes[0] != 0
+    }
+
+    fn typed_values(tensor: &TensorProto) -> Vec<Self> {
+        tensor.bool_val.clone()
+    }
+}
+
+macro_rules! impl_complex {
+    ($ty:ty, $dtype:expr, $field:ident) => {
+        impl TensorProtoElement for Complex<$ty> {
+            const DATA_TYPE: DataType = $dtype;
+
+            fn to_bytes(&self) -> Vec<u8> {
+                self.re
+                    .to_le_bytes()
+                    .iter()
+                    .chain(self.im.to_le_bytes().iter())
+                    .cloned()
+                    .collect()
+            }
+        }
+
+        impl TensorProtoDecode for Complex<$ty> {
+            fn from_bytes(bytes: &[u8]) -> Self {
+                let (re, im) = bytes.split_at(bytes.len() / 2);
+                Complex::new(
+                    <$ty>::from_le_bytes(re.try_into().unwrap()),
+                    <$ty>::from_le_bytes(im.try_into().unwrap()),
+                )
+            }
+
+            fn typed_values(tensor: &TensorProto) -> Vec<Self> {
+                // the real and imaginary parts are interleaved
+                tensor
+                    .$field
+                    .chunks_exact(2)
+                    .map(|pair| Complex::new(pair[0], pair[1]))
+                    .collect()
+            }
+        }
     };
 }
 
-impl_to_le_bytes!(u8, DataType::DtUint8);
-impl_to_le_bytes!(u16, DataType::DtUint16);
-impl_to_le_bytes!(u32, DataType::DtUint32);
-impl_to_le_bytes!(u64, DataType::DtUint64);
-impl_to_le_bytes!(i8, DataType::DtInt8);
-impl_to_le_bytes!(i16, DataType::DtInt16);
-impl_to_le_bytes!(i32, DataType::DtInt32);
-impl_to_le_bytes!(i64, DataType::DtInt64);
-impl_to_le_bytes!(f32, DataType::DtFloat);
-impl_to_le_bytes!(f64, DataType::DtDouble);
+impl_complex!(f32, DataType::DtComplex64, scomplex_val);
+impl_complex!(f64, DataType::DtComplex128, dcomplex_val);
 
 /// A trait marking types that can be converted to elements of [HistogramProto](crate::protos::HistogramProto).
+///
+/// The complex values are converted to their magnitudes.
 pub trait HistogramProtoElement
 where
     Self: Copy,
@@ -168,9 +345,211 @@ impl HistogramProtoElement for f64 {
     }
 }
 
+impl HistogramProtoElement for f16 {
+    fn to_f64(&self) -> f64 {
+        f64::from(*self)
+    }
+}
+
+impl HistogramProtoElement for bf16 {
+    fn to_f64(&self) -> f64 {
+        f64::from(*self)
+    }
+}
+
+impl HistogramProtoElement for bool {
+    fn to_f64(&self) -> f64 {
+        *self as u8 as f64
+    }
+}
+
+impl HistogramProtoElement for Complex<f32> {
+    fn to_f64(&self) -> f64 {
+        self.norm() as f64
+    }
+}
+
+impl HistogramProtoElement for Complex<f64> {
+    fn to_f64(&self) -> f64 {
+        self.norm()
+    }
+}
+
 /// A trait marking if the type can be converted to a list of imgaes.
 pub trait TryInfoImageList {
     type Error;
 
     fn try_into_image_list(self) -> Result<Vec<Image>, Self::Error>;
 }
+
+/// A trait for types that can be converted from and to [Example].
+///
+/// It is usually implemented by `#[derive(TfExample)]` with the `derive` feature.
+/// The features are looked up with the keys prefixed by `prefix`, which allows
+/// the types to be nested in other types.
+pub trait TfExample
+where
+    Self: Sized,
+{
+    fn from_example_with_prefix(example: &Example, prefix: &str) -> Result<Self, Error>;
+    fn write_example_with_prefix(self, example: &mut Example, prefix: &str) -> Result<(), Error>;
+
+    fn from_example(example: &Example) -> Result<Self, Error> {
+        Self::from_example_with_prefix(example, "")
+    }
+
+    fn into_example(self) -> Result<Example, Error> {
+        let mut example = Example::new();
+        self.write_example_with_prefix(&mut example, "")?;
+        Ok(example)
+    }
+}
+
+/// A trait for field types that can be converted from and to a [Feature].
+///
+/// Single values are stored in lists of one value. The `u8` vectors and strings
+/// are stored as bytes.
+pub trait FeatureValue
+where
+    Self: Sized,
+{
+    fn from_feature(name: &str, feature: &Feature) -> Result<Self, Error>;
+
+    /// Convert to a feature. The feature is omitted if it returns `None`.
+    fn into_feature(self) -> Option<Feature>;
+
+    /// Get the value of a missing feature. It returns error by default.
+    fn from_missing(name: &str) -> Result<Self, Error> {
+        Err(Error::MissingFeatureError {
+            name: name.to_owned(),
+        })
+    }
+}
+
+impl FeatureValue for i64 {
+    fn from_feature(name: &str, feature: &Feature) -> Result<Self, Error> {
+        Ok(*single_value(name, "int64", int64_list(name, feature)?)?)
+    }
+
+    fn into_feature(self) -> Option<Feature> {
+        Some(Feature::Int64List(vec![self]))
+    }
+}
+
+impl FeatureValue for i32 {
+    fn from_feature(name: &str, feature: &Feature) -> Result<Self, Error> {
+        let value = i64::from_feature(name, feature)?;
+        i32::try_from(value).map_err(|_| Error::ConversionError {
+            desc: format!(r#"the value {} of "{}" does not fit in i32"#, value, name),
+        })
+    }
+
+    fn into_feature(self) -> Option<Feature> {
+        Some(Feature::Int64List(vec![self as i64]))
+    }
+}
+
+impl FeatureValue for bool {
+    fn from_feature(name: &str, feature: &Feature) -> Result<Self, Error> {
+        Ok(i64::from_feature(name, feature)? != 0)
+    }
+
+    fn into_feature(self) -> Option<Feature> {
+        Some(Feature::Int64List(vec![self as i64]))
+    }
+}
+
+impl FeatureValue for f32 {
+    fn from_feature(name: &str, feature: &Feature) -> Result<Self, Error> {
+        Ok(*single_value(name, "float", float_list(name, feature)?)?)
+    }
+
+    fn into_feature(self) -> Option<Feature> {
+        Some(Feature::FloatList(vec![self]))
+    }
+}
+
+impl FeatureValue for String {
+    fn from_feature(name: &str, feature: &Feature) -> Result<Self, Error> {
+        let bytes = single_value(name, "bytes", bytes_list(name, feature)?)?;
+        Ok(bytes_to_str(name, bytes)?.to_owned())
+    }
+
+    fn into_feature(self) -> Option<Feature> {
+        Some(Feature::BytesList(vec![self.into_bytes()]))
+    }
+}
+
+impl FeatureValue for Vec<u8> {
+    fn from_feature(name: &str, feature: &Feature) -> Result<Self, Error> {
+        Ok(single_value(name, "bytes", bytes_list(name, feature)?)?.clone())
+    }
+
+    fn into_feature(self) -> Option<Feature> {
+        Some(Feature::BytesList(vec![self]))
+    }
+}
+
+impl FeatureValue for Vec<i64> {
+    fn from_feature(name: &str, feature: &Feature) -> Result<Self, Error> {
+        Ok(int64_list(name, feature)?.to_vec())
+    }
+
+    fn into_feature(self) -> Option<Feature> {
+        Some(Feature::Int64List(self))
+    }
+}
+
+impl FeatureValue for Vec<f32> {
+    fn from_feature(name: &str, feature: &Feature) -> Result<Self, Error> {
+        Ok(float_list(name, feature)?.to_vec())
+    }
+
+    fn into_feature(self) -> Option<Feature> {
+        Some(Feature::FloatList(self))
+    }
+}
+
+impl FeatureValue for Vec<Vec<u8>> {
+    fn from_feature(name: &str, feature: &Feature) -> Result<Self, Error> {
+        Ok(bytes_list(name, feature)?.to_vec())
+    }
+
+    fn into_feature(self) -> Option<Feature> {
+        Some(Feature::BytesList(self))
+    }
+}
+
+impl FeatureValue for Vec<String> {
+    fn from_feature(name: &str, feature: &Feature) -> Result<Self, Error> {
+        bytes_list(name, feature)?
+            .iter()
+            .map(|bytes| Ok(bytes_to_str(name, bytes)?.to_owned()))
+            .collect()
+    }
+
+    fn into_feature(self) -> Option<Feature> {
+        let values = self.into_iter().map(String::into_bytes).collect();
+        Some(Feature::BytesList(values))
+    }
+}
+
+impl<T> FeatureValue for Option<T>
+where
+    T: FeatureValue,
+{
+    fn from_feature(name: &str, feature: &Feature) -> Result<Self, Error> {
+        match feature {
+            Feature::None => Ok(None),
+            feature => Ok(Some(T::from_feature(name, feature)?)),
+        }
+    }
+
+    fn into_feature(self) -> Option<Feature> {
+        self.and_then(T::into_feature)
+    }
+
+    fn from_missing(_name: &str) -> Result<Self, Error> {
+        Ok(None)
+    }
+}
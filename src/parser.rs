@@ -0,0 +1,510 @@
+//! Schema-driven example parsing, analogous to `tf.io.parse_single_example`.
+//!
+//! The [ExampleParser] is configured by a [FeatureSpec] per output. It validates
+//! each example against the specs and produces dense and sparse outputs.
+
+use crate::{
+    error::Error,
+    projection::Projection,
+    protos::{
+        feature_configuration::Config, DataType, ExampleParserConfiguration, FixedLenFeatureProto,
+        TensorProto, VarLenFeatureProto,
+    },
+    types::{feature_mismatch, Example, Feature},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+};
+
+/// The value type of a feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeatureType {
+    /// The int64 list, or `tf.int64`.
+    Int64,
+    /// The float list, or `tf.float32`.
+    Float,
+    /// The bytes list, or `tf.string`.
+    Bytes,
+}
+
+impl FeatureType {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Int64 => "int64",
+            Self::Float => "float",
+            Self::Bytes => "bytes",
+        }
+    }
+
+    fn empty(&self) -> Feature {
+        match self {
+            Self::Int64 => Feature::Int64List(vec![]),
+            Self::Float => Feature::FloatList(vec![]),
+            Self::Bytes => Feature::BytesList(vec![]),
+        }
+    }
+
+    /// Get the number of values if the feature has this type.
+    fn len_of(&self, feature: &Feature) -> Option<usize> {
+        match (self, feature) {
+            (Self::Int64, Feature::Int64List(values)) => Some(values.len()),
+            (Self::Float, Feature::FloatList(values)) => Some(values.len()),
+            (Self::Bytes, Feature::BytesList(values)) => Some(values.len()),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<DataType> for FeatureType {
+    type Error = Error;
+
+    fn try_from(dtype: DataType) -> Result<Self, Self::Error> {
+        let feature_type = match dtype {
+            DataType::DtInt64 => Self::Int64,
+            DataType::DtFloat => Self::Float,
+            DataType::DtString => Self::Bytes,
+            _ => {
+                return Err(Error::ConversionError {
+                    desc: format!("the data type {:?} cannot be stored in examples", dtype),
+                })
+            }
+        };
+        Ok(feature_type)
+    }
+}
+
+/// The specification of a parsed output.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeatureSpec {
+    /// The feature has a fixed number of values, analogous to `tf.io.FixedLenFeature`.
+    ///
+    /// The feature must have exactly as many values as the product of `shape`,
+    /// which is 1 for an empty shape. The `default` is used if the feature is
+    /// missing, otherwise the missing feature is an error.
+    FixedLen {
+        dtype: FeatureType,
+        shape: Vec<usize>,
+        default: Option<Feature>,
+    },
+    /// The feature has any number of values, analogous to `tf.io.VarLenFeature`.
+    ///
+    /// It produces a 1-D sparse output. The missing feature produces an empty output.
+    VarLen { dtype: FeatureType },
+    /// The sparse output assembled from index and value features, analogous to `tf.io.SparseFeature`.
+    Sparse(SparseSpec),
+}
+
+/// The specification of a sparse output built from several features.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SparseSpec {
+    /// The int64 features storing the indices, one per dimension.
+    pub index_keys: Vec<String>,
+    /// The feature storing the values.
+    pub value_key: String,
+    pub dtype: FeatureType,
+    /// The dense shape of the output.
+    pub size: Vec<usize>,
+    /// Skip sorting the indices if they are known to be in order.
+    pub already_sorted: bool,
+}
+
+/// The dense output of a parsed feature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DenseTensor {
+    pub shape: Vec<usize>,
+    /// The values in row-major order.
+    pub values: Feature,
+}
+
+/// The sparse output of a parsed feature in coordinate format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseTensor {
+    /// The indices of values, each having one coordinate per dimension.
+    pub indices: Vec<Vec<i64>>,
+    pub values: Feature,
+    pub dense_shape: Vec<usize>,
+}
+
+/// The outputs of [ExampleParser::parse].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ParsedExample {
+    pub dense: HashMap<String, DenseTensor>,
+    pub sparse: HashMap<String, SparseTensor>,
+}
+
+/// The example parser configured by feature specs.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExampleParser {
+    /// The specs by output name. The output name is the feature key
+    /// except for [FeatureSpec::Sparse].
+    pub specs: BTreeMap<String, FeatureSpec>,
+}
+
+impl ExampleParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a spec.
+    pub fn feature<S>(mut self, name: S, spec: FeatureSpec) -> Self
+    where
+        S: Into<String>,
+    {
+        self.specs.insert(name.into(), spec);
+        self
+    }
+
+    /// Add a [FeatureSpec::FixedLen] spec.
+    pub fn fixed_len<S>(
+        self,
+        name: S,
+        dtype: FeatureType,
+        shape: Vec<usize>,
+        default: Option<Feature>,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        self.feature(
+            name,
+            FeatureSpec::FixedLen {
+                dtype,
+                shape,
+                default,
+            },
+        )
+    }
+
+    /// Add a [FeatureSpec::VarLen] spec.
+    pub fn var_len<S>(self, name: S, dtype: FeatureType) -> Self
+    where
+        S: Into<String>,
+    {
+        self.feature(name, FeatureSpec::VarLen { dtype })
+    }
+
+    /// Add a [FeatureSpec::Sparse] spec.
+    pub fn sparse<S>(self, name: S, spec: SparseSpec) -> Self
+    where
+        S: Into<String>,
+    {
+        self.feature(name, FeatureSpec::Sparse(spec))
+    }
+
+    /// Get the names of features read by the parser.
+    pub fn feature_keys(&self) -> Vec<&str> {
+        let mut keys = vec![];
+        for (name, spec) in self.specs.iter() {
+            match spec {
+                FeatureSpec::FixedLen { .. } | FeatureSpec::VarLen { .. } => {
+                    keys.push(name.as_str())
+                }
+                FeatureSpec::Sparse(spec) => {
+                    keys.extend(spec.index_keys.iter().map(|key| key.as_str()));
+                    keys.push(spec.value_key.as_str());
+                }
+            }
+        }
+        keys.sort_unstable();
+        keys.dedup();
+        keys
+    }
+
+    /// Validate the example against the specs and build the outputs.
+    ///
+    /// The features not in the specs are ignored. An empty feature without
+    /// a value type is regarded as missing.
+    pub fn parse(&self, example: &Example) -> Result<ParsedExample, Error> {
+        let mut parsed = ParsedExample::default();
+        let get = |name: &str| match example.get(name) {
+            Some(Feature::None) | None => None,
+            Some(feature) => Some(feature),
+        };
+
+        for (name, spec) in self.specs.iter() {
+            match spec {
+                FeatureSpec::FixedLen {
+                    dtype,
+                    shape,
+                    default,
+                } => {
+                    let tensor = parse_fixed_len(name, get(name), *dtype, shape, default.as_ref())?;
+                    parsed.dense.insert(name.clone(), tensor);
+                }
+                FeatureSpec::VarLen { dtype } => {
+                    let tensor = parse_var_len(name, get(name), *dtype)?;
+                    parsed.sparse.insert(name.clone(), tensor);
+                }
+                FeatureSpec::Sparse(spec) => {
+                    let tensor = parse_sparse(name, spec, get)?;
+                    parsed.sparse.insert(name.clone(), tensor);
+                }
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Parse the serialized example, decoding only the features read by the parser.
+    pub fn parse_bytes(&self, bytes: &[u8]) -> Result<ParsedExample, Error> {
+        let example = Projection::names(self.feature_keys()).decode_example(bytes)?;
+        self.parse(&example)
+    }
+}
+
+impl TryFrom<&ExampleParserConfiguration> for ExampleParser {
+    type Error = Error;
+
+    fn try_from(config: &ExampleParserConfiguration) -> Result<Self, Self::Error> {
+        let specs = config
+            .feature_map
+            .iter()
+            .map(|(name, feature_config)| {
+                let spec = match &feature_config.config {
+                    Some(Config::FixedLenFeature(proto)) => FeatureSpec::try_from(proto)?,
+                    Some(Config::VarLenFeature(proto)) => FeatureSpec::try_from(proto)?,
+                    None => {
+                        return Err(Error::InvalidArgumentsError {
+                            desc: format!(r#"the feature "{}" has no configuration"#, name),
+                        })
+                    }
+                };
+                Ok((name.clone(), spec))
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self { specs })
+    }
+}
+
+impl TryFrom<ExampleParserConfiguration> for ExampleParser {
+    type Error = Error;
+
+    fn try_from(config: ExampleParserConfiguration) -> Result<Self, Self::Error> {
+        Self::try_from(&config)
+    }
+}
+
+impl TryFrom<&FixedLenFeatureProto> for FeatureSpec {
+    type Error = Error;
+
+    fn try_from(proto: &FixedLenFeatureProto) -> Result<Self, Self::Error> {
+        let dtype = feature_type_from_i32(proto.dtype)?;
+        let shape = match &proto.shape {
+            Some(shape) => shape
+                .dim
+                .iter()
+                .map(|dim| {
+                    usize::try_from(dim.size).map_err(|_| Error::InvalidArgumentsError {
+                        desc: format!("the dimension size {} is not supported", dim.size),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => vec![],
+        };
+        let default = proto
+            .default_value
+            .as_ref()
+            .map(|tensor| default_from_tensor(tensor, dtype))
+            .transpose()?;
+        Ok(Self::FixedLen {
+            dtype,
+            shape,
+            default,
+        })
+    }
+}
+
+impl TryFrom<&VarLenFeatureProto> for FeatureSpec {
+    type Error = Error;
+
+    fn try_from(proto: &VarLenFeatureProto) -> Result<Self, Self::Error> {
+        Ok(Self::VarLen {
+            dtype: feature_type_from_i32(proto.dtype)?,
+        })
+    }
+}
+
+fn feature_type_from_i32(dtype: i32) -> Result<FeatureType, Error> {
+    let dtype = DataType::from_i32(dtype).ok_or_else(|| Error::ConversionError {
+        desc: format!("invalid data type {}", dtype),
+    })?;
+    FeatureType::try_from(dtype)
+}
+
+fn default_from_tensor(tensor: &TensorProto, dtype: FeatureType) -> Result<Feature, Error> {
+    let feature = match dtype {
+        FeatureType::Int64 => Feature::Int64List(tensor.to_vec()?),
+        FeatureType::Float => Feature::FloatList(tensor.to_vec()?),
+        FeatureType::Bytes => Feature::BytesList(tensor.to_bytes_list()?),
+    };
+    Ok(feature)
+}
+
+fn parse_fixed_len(
+    name: &str,
+    feature: Option<&Feature>,
+    dtype: FeatureType,
+    shape: &[usize],
+    default: Option<&Feature>,
+) -> Result<DenseTensor, Error> {
+    let num_values: usize = shape.iter().product();
+
+    let values = match (feature, default) {
+        (Some(feature), _) => {
+            let expect = format!("{} {} values", num_values, dtype.name());
+            match dtype.len_of(feature) {
+                Some(len) if len == num_values => feature.clone(),
+                _ => return Err(feature_mismatch(name, &expect, feature)),
+            }
+        }
+        (None, Some(default)) => {
+            if dtype.len_of(default) != Some(num_values) {
+                return Err(Error::InvalidArgumentsError {
+                    desc: format!(
+                        r#"the default value of "{}" must have {} {} values"#,
+                        name,
+                        num_values,
+                        dtype.name()
+                    ),
+                });
+            }
+            default.clone()
+        }
+        (None, None) => {
+            return Err(Error::MissingFeatureError {
+                name: name.to_owned(),
+            })
+        }
+    };
+
+    Ok(DenseTensor {
+        shape: shape.to_vec(),
+        values,
+    })
+}
+
+fn parse_var_len(
+    name: &str,
+    feature: Option<&Feature>,
+    dtype: FeatureType,
+) -> Result<SparseTensor, Error> {
+    let values = match feature {
+        Some(feature) => {
+            if dtype.len_of(feature).is_none() {
+                return Err(feature_mismatch(
+                    name,
+                    &format!("a {} list", dtype.name()),
+                    feature,
+                ));
+            }
+            feature.clone()
+        }
+        None => dtype.empty(),
+    };
+    let len = dtype.len_of(&values).unwrap();
+
+    Ok(SparseTensor {
+        indices: (0..len as i64).map(|index| vec![index]).collect(),
+        values,
+        dense_shape: vec![len],
+    })
+}
+
+fn parse_sparse<'a, F>(name: &str, spec: &SparseSpec, get: F) -> Result<SparseTensor, Error>
+where
+    F: Fn(&str) -> Option<&'a Feature>,
+{
+    let SparseSpec {
+        index_keys,
+        value_key,
+        dtype,
+        size,
+        already_sorted,
+    } = spec;
+
+    if index_keys.len() != size.len() || index_keys.is_empty() {
+        return Err(Error::InvalidArgumentsError {
+            desc: format!(
+                r#"the sparse feature "{}" must have one index key per dimension"#,
+                name
+            ),
+        });
+    }
+
+    // values
+    let values = match get(value_key) {
+        Some(feature) => {
+            if dtype.len_of(feature).is_none() {
+                return Err(feature_mismatch(
+                    value_key,
+                    &format!("a {} list", dtype.name()),
+                    feature,
+                ));
+            }
+            feature.clone()
+        }
+        None => dtype.empty(),
+    };
+    let num_values = dtype.len_of(&values).unwrap();
+
+    // indices per dimension
+    let columns = index_keys
+        .iter()
+        .zip(size.iter())
+        .map(|(key, &dim)| {
+            let indices: &[i64] = match get(key) {
+                Some(Feature::Int64List(indices)) => indices,
+                Some(feature) => return Err(feature_mismatch(key, "an int64 list", feature)),
+                None => &[],
+            };
+            if indices.len() != num_values {
+                return Err(Error::FeatureMismatchError {
+                    name: key.clone(),
+                    expect: format!("{} indices to match {}", num_values, value_key),
+                    found: format!("{} indices", indices.len()),
+                });
+            }
+            if let Some(&index) = indices
+                .iter()
+                .find(|&&index| index < 0 || index as u64 >= dim as u64)
+            {
+                return Err(Error::FeatureMismatchError {
+                    name: key.clone(),
+                    expect: format!("indices in range 0..{}", dim),
+                    found: format!("index {}", index),
+                });
+            }
+            Ok(indices)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut entries: Vec<(Vec<i64>, usize)> = (0..num_values)
+        .map(|position| {
+            let index = columns.iter().map(|column| column[position]).collect();
+            (index, position)
+        })
+        .collect();
+    if !already_sorted {
+        entries.sort();
+    }
+
+    let values = match &values {
+        Feature::Int64List(list) => {
+            Feature::Int64List(entries.iter().map(|(_, pos)| list[*pos]).collect())
+        }
+        Feature::FloatList(list) => {
+            Feature::FloatList(entries.iter().map(|(_, pos)| list[*pos]).collect())
+        }
+        Feature::BytesList(list) => {
+            Feature::BytesList(entries.iter().map(|(_, pos)| list[*pos].clone()).collect())
+        }
+        Feature::None => unreachable!(),
+    };
+
+    Ok(SparseTensor {
+        indices: entries.into_iter().map(|(index, _)| index).collect(),
+        values,
+        dense_shape: size.clone(),
+    })
+}
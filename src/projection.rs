@@ -0,0 +1,97 @@
+//! Selecting the features to decode.
+
+use crate::{error::Error, example_ref::ExampleRef, types::Example};
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Debug, Formatter},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// The set of features to be decoded from examples.
+///
+/// The features not selected are skipped at the ProtocolBuffer level without being copied.
+/// The projection applies to the record types with named features, such as [Example].
+/// The other record types, such as [Vec\<u8\>](Vec), ignore it.
+#[derive(Clone)]
+pub struct Projection(ProjectionKind);
+
+#[derive(Clone)]
+enum ProjectionKind {
+    Names(Arc<BTreeSet<String>>),
+    Predicate(Arc<dyn Fn(&str) -> bool + Send + Sync>),
+}
+
+impl Projection {
+    /// Select the features by names.
+    pub fn names<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let names = names.into_iter().map(Into::into).collect();
+        Self(ProjectionKind::Names(Arc::new(names)))
+    }
+
+    /// Select the features whose names satisfy the predicate.
+    pub fn predicate<F>(predicate: F) -> Self
+    where
+        F: 'static + Fn(&str) -> bool + Send + Sync,
+    {
+        Self(ProjectionKind::Predicate(Arc::new(predicate)))
+    }
+
+    /// Check whether the feature is selected.
+    pub fn contains(&self, name: &str) -> bool {
+        match &self.0 {
+            ProjectionKind::Names(names) => names.contains(name),
+            ProjectionKind::Predicate(predicate) => predicate(name),
+        }
+    }
+
+    /// Decode the selected features from the bytes of a serialized example.
+    pub fn decode_example(&self, bytes: &[u8]) -> Result<Example, Error> {
+        let mut example = Example::new();
+        for entry in ExampleRef::from_bytes(bytes)?.iter() {
+            let (name, feature) = entry?;
+            if self.contains(name) {
+                example.insert(name.to_owned(), feature.to_feature()?);
+            }
+        }
+        Ok(example)
+    }
+}
+
+impl Debug for Projection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            ProjectionKind::Names(names) => f.debug_tuple("Projection").field(names).finish(),
+            ProjectionKind::Predicate(_) => write!(f, "Projection(<predicate>)"),
+        }
+    }
+}
+
+impl PartialEq for Projection {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (ProjectionKind::Names(lhs), ProjectionKind::Names(rhs)) => lhs == rhs,
+            (ProjectionKind::Predicate(lhs), ProjectionKind::Predicate(rhs)) => {
+                Arc::ptr_eq(lhs, rhs)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Projection {}
+
+impl Hash for Projection {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match &self.0 {
+            ProjectionKind::Names(names) => names.hash(state),
+            ProjectionKind::Predicate(predicate) => {
+                (Arc::as_ptr(predicate) as *const u8).hash(state)
+            }
+        }
+    }
+}
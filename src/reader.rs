@@ -10,7 +10,13 @@
 //! The [RecordStreamInit] initializer constructs streams from types with [AsyncRead](AsyncRead) trait.
 //! The streams can integrated with [futures::stream] API.
 
-use crate::{error::Error, markers::GenericRecord, protos::Example as RawExample, types::Example};
+use crate::{
+    error::Error,
+    markers::GenericRecord,
+    projection::Projection,
+    protos::{Example as RawExample, SequenceExample as RawSequenceExample},
+    types::{Example, SequenceExample},
+};
 #[cfg(feature = "async_")]
 use futures::{io::AsyncRead, stream::Stream};
 use std::{io::prelude::*, marker::PhantomData, path::Path};
@@ -21,46 +27,83 @@ pub type BytesReader<R> = RecordReader<Vec<u8>, R>;
 pub type RawExampleReader<R> = RecordReader<RawExample, R>;
 /// Alias to [RecordReader] which output record type is [Example](Example).
 pub type ExampleReader<R> = RecordReader<Example, R>;
+/// Alias to [RecordReader] which output record type is [RawSequenceExample](RawSequenceExample).
+pub type RawSequenceExampleReader<R> = RecordReader<RawSequenceExample, R>;
+/// Alias to [RecordReader] which output record type is [SequenceExample](SequenceExample).
+pub type SequenceExampleReader<R> = RecordReader<SequenceExample, R>;
 
 #[cfg(feature = "async_")]
 pub use async_::*;
 pub use blocking::*;
 
+fn decode_record<T>(bytes: Vec<u8>, projection: Option<&Projection>) -> Result<T, Error>
+where
+    T: GenericRecord,
+{
+    match projection {
+        Some(projection) => T::from_bytes_projected(bytes, projection),
+        None => T::from_bytes(bytes),
+    }
+}
+
 mod blocking {
     use super::*;
 
     /// The reader initializer.
     #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    #[non_exhaustive]
     pub struct RecordReaderInit {
         pub check_integrity: bool,
+        /// Decode only the selected features of examples.
+        pub projection: Option<Projection>,
     }
 
     impl Default for RecordReaderInit {
         fn default() -> Self {
             Self {
                 check_integrity: true,
+                projection: None,
             }
         }
     }
 
     impl RecordReaderInit {
+        /// Set whether to verify the checksums.
+        pub fn with_check_integrity(mut self, check_integrity: bool) -> Self {
+            self.check_integrity = check_integrity;
+            self
+        }
+
+        /// Decode only the selected features of examples.
+        pub fn with_projection(mut self, projection: Projection) -> Self {
+            self.projection = Some(projection);
+            self
+        }
+
         /// Construct a [RecordReader] from a type implementing [Read](std::io::Read).
         pub fn from_reader<T, R>(self, reader: R) -> Result<RecordReader<T, R>, Error>
         where
             T: GenericRecord,
             R: Read,
         {
-            let RecordReaderInit { check_integrity } = self;
+            let RecordReaderInit {
+                check_integrity,
+                projection,
+            } = self;
 
             let record_reader = RecordReader {
                 reader_opt: Some(reader),
                 check_integrity,
+                projection,
                 _phantom: PhantomData,
             };
             Ok(record_reader)
         }
 
         /// Construct a [RecordReader] from a path.
+        ///
+        /// It reads local files only. The files on a storage backend are read by
+        /// `RecordStreamInit::open_storage` with the `dataset` feature.
         pub fn open<T, P>(
             self,
             path: P,
@@ -88,6 +131,7 @@ mod blocking {
         R: Read,
     {
         check_integrity: bool,
+        projection: Option<Projection>,
         reader_opt: Option<R>,
         _phantom: PhantomData<T>,
     }
@@ -110,7 +154,7 @@ mod blocking {
 
             let bytes_result = bytes_opt?;
             let record_result = match bytes_result {
-                Ok(bytes) => T::from_bytes(bytes),
+                Ok(bytes) => decode_record(bytes, self.projection.as_ref()),
                 Err(err) => Err(err),
             };
             Some(record_result)
@@ -121,28 +165,51 @@ mod blocking {
 #[cfg(feature = "async_")]
 mod async_ {
     use super::*;
+    #[cfg(feature = "dataset")]
+    use crate::dataset::Storage;
+    #[cfg(feature = "dataset")]
+    use futures::stream::TryStreamExt;
+    #[cfg(feature = "dataset")]
+    use std::{io, sync::Arc};
 
     /// The stream initializer.
     #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    #[non_exhaustive]
     pub struct RecordStreamInit {
         pub check_integrity: bool,
+        /// Decode only the selected features of examples.
+        pub projection: Option<Projection>,
     }
 
     impl Default for RecordStreamInit {
         fn default() -> Self {
             Self {
                 check_integrity: true,
+                projection: None,
             }
         }
     }
 
     impl RecordStreamInit {
+        /// Set whether to verify the checksums.
+        pub fn with_check_integrity(mut self, check_integrity: bool) -> Self {
+            self.check_integrity = check_integrity;
+            self
+        }
+
+        /// Decode only the selected features of examples.
+        pub fn with_projection(mut self, projection: Projection) -> Self {
+            self.projection = Some(projection);
+            self
+        }
+
         /// Build a stream from a reader type with [AsyncRead] trait.
         ///
         /// Specify the output type while calling this method. For example,
         /// `from_reader<Example, _>()`, or you can use [bytes_from_reader](RecordStreamInit::bytes_from_reader),
-        /// [raw_examples_from_reader](RecordStreamInit::raw_examples_from_reader) and
-        /// [examples_from_reader](RecordStreamInit::examples_from_reader) aliases.
+        /// [raw_examples_from_reader](RecordStreamInit::raw_examples_from_reader),
+        /// [examples_from_reader](RecordStreamInit::examples_from_reader) and
+        /// [sequence_examples_from_reader](RecordStreamInit::sequence_examples_from_reader) aliases.
         pub async fn from_reader<T, R>(
             self,
             reader: R,
@@ -151,24 +218,29 @@ mod async_ {
             T: GenericRecord,
             R: 'static + AsyncRead + Unpin + Send,
         {
-            let RecordStreamInit { check_integrity } = self;
+            let RecordStreamInit {
+                check_integrity,
+                projection,
+            } = self;
 
-            let stream =
-                futures::stream::unfold(Some((reader, check_integrity)), |state_opt| async move {
-                    let (mut reader, check_integrity) = state_opt?;
+            let stream = futures::stream::unfold(
+                Some((reader, check_integrity, projection)),
+                |state_opt| async move {
+                    let (mut reader, check_integrity, projection) = state_opt?;
                     let result = crate::io::async_::try_read_record(&mut reader, check_integrity)
                         .await
                         .transpose()?;
                     let result = match result {
-                        Ok(bytes) => T::from_bytes(bytes),
+                        Ok(bytes) => decode_record(bytes, projection.as_ref()),
                         Err(err) => Err(err),
                     };
 
                     match result {
-                        Ok(bytes) => Some((Ok(bytes), Some((reader, check_integrity)))),
+                        Ok(bytes) => Some((Ok(bytes), Some((reader, check_integrity, projection)))),
                         Err(err) => Some((Err(err), None)),
                     }
-                });
+                },
+            );
 
             Ok(stream)
         }
@@ -177,8 +249,9 @@ mod async_ {
         ///
         /// Specify the output type while calling this method. For example,
         /// `open<Example, _>()`, or you can use [bytes_open](RecordStreamInit::bytes_open),
-        /// [raw_examples_open](RecordStreamInit::raw_examples_open) and
-        /// [examples_open](RecordStreamInit::examples_open) aliases.
+        /// [raw_examples_open](RecordStreamInit::raw_examples_open),
+        /// [examples_open](RecordStreamInit::examples_open) and
+        /// [sequence_examples_open](RecordStreamInit::sequence_examples_open) aliases.
         pub async fn open<T, P>(
             self,
             path: P,
@@ -192,6 +265,43 @@ mod async_ {
             Self::from_reader(self, reader).await
         }
 
+        /// Build a stream from a file on a storage backend.
+        ///
+        /// The file is read sequentially by ranged reads of 1MiB.
+        #[cfg(feature = "dataset")]
+        pub async fn open_storage<T>(
+            self,
+            storage: Arc<dyn Storage>,
+            path: &str,
+        ) -> Result<impl Stream<Item = Result<T, Error>>, Error>
+        where
+            T: GenericRecord,
+        {
+            const CHUNK_SIZE: usize = 1024 * 1024;
+
+            let path = path.to_owned();
+            let chunks = futures::stream::try_unfold(0, move |offset| {
+                let storage = storage.clone();
+                let path = path.clone();
+                async move {
+                    let chunk = storage
+                        .read_range(&path, offset, CHUNK_SIZE)
+                        .await
+                        .map_err(|err| match err {
+                            Error::IoError { error } => error,
+                            err => io::Error::other(err),
+                        })?;
+                    if chunk.is_empty() {
+                        return Ok(None);
+                    }
+                    let offset = offset + chunk.len() as u64;
+                    Ok(Some((chunk, offset)))
+                }
+            });
+            let reader = Box::pin(chunks).into_async_read();
+            self.from_reader(reader).await
+        }
+
         /// Alias to [from_reader<Vec<u8>, R>](RecordStreamInit::from_reader).
         pub async fn bytes_from_reader<R>(
             self,
@@ -225,6 +335,28 @@ mod async_ {
             self.from_reader::<Example, _>(reader).await
         }
 
+        /// Alias to [from_reader<RawSequenceExample, R>](RecordStreamInit::from_reader).
+        pub async fn raw_sequence_examples_from_reader<R>(
+            self,
+            reader: R,
+        ) -> Result<impl Stream<Item = Result<RawSequenceExample, Error>>, Error>
+        where
+            R: 'static + AsyncRead + Unpin + Send,
+        {
+            self.from_reader::<RawSequenceExample, _>(reader).await
+        }
+
+        /// Alias to [from_reader<SequenceExample, R>](RecordStreamInit::from_reader).
+        pub async fn sequence_examples_from_reader<R>(
+            self,
+            reader: R,
+        ) -> Result<impl Stream<Item = Result<SequenceExample, Error>>, Error>
+        where
+            R: 'static + AsyncRead + Unpin + Send,
+        {
+            self.from_reader::<SequenceExample, _>(reader).await
+        }
+
         /// Alias to [open<Vec<u8>, R>](RecordStreamInit::open).
         pub async fn bytes_open<P>(
             self,
@@ -257,5 +389,27 @@ mod async_ {
         {
             Self::open::<Example, _>(self, path).await
         }
+
+        /// Alias to [open<RawSequenceExample, R>](RecordStreamInit::open).
+        pub async fn raw_sequence_examples_open<P>(
+            self,
+            path: P,
+        ) -> Result<impl Stream<Item = Result<RawSequenceExample, Error>>, Error>
+        where
+            P: AsRef<async_std::path::Path>,
+        {
+            Self::open::<RawSequenceExample, _>(self, path).await
+        }
+
+        /// Alias to [open<SequenceExample, R>](RecordStreamInit::open).
+        pub async fn sequence_examples_open<P>(
+            self,
+            path: P,
+        ) -> Result<impl Stream<Item = Result<SequenceExample, Error>>, Error>
+        where
+            P: AsRef<async_std::path::Path>,
+        {
+            Self::open::<SequenceExample, _>(self, path).await
+        }
     }
 }
@@ -0,0 +1,358 @@
+//! Inferring the layout of examples.
+//!
+//! The [Schema] accumulates the kinds, presence and value counts of features
+//! over examples. It can be turned into an [ExampleParser], a TensorFlow feature
+//! spec or a Rust struct definition.
+
+#[cfg(feature = "dataset")]
+use crate::dataset::Dataset;
+use crate::{
+    error::Error,
+    parser::{ExampleParser, FeatureSpec, FeatureType},
+    statistics::{merge_features, update_features, FeatureAccumulator},
+    types::{Example, Feature},
+};
+#[cfg(feature = "dataset")]
+use futures::stream::StreamExt;
+#[cfg(feature = "async_")]
+use futures::stream::{Stream, TryStreamExt};
+use std::{collections::BTreeMap, fmt::Write, str};
+
+/// The inferred layout of a feature.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FeatureSchema {
+    /// The value type. It is `None` if the feature is only seen without a value type.
+    pub dtype: Option<FeatureType>,
+    /// Whether the feature is seen with distinct value types.
+    pub conflicting_types: bool,
+    /// Number of examples having the feature.
+    pub num_present: usize,
+    /// Number of examples without the feature.
+    pub num_missing: usize,
+    /// Minimum number of values.
+    pub min_len: usize,
+    /// Maximum number of values.
+    pub max_len: usize,
+    /// Whether all bytes values are valid UTF-8.
+    pub utf8: bool,
+}
+
+impl FeatureSchema {
+    /// The ratio of examples having the feature.
+    pub fn presence_rate(&self) -> f64 {
+        let total = self.num_present + self.num_missing;
+        if total == 0 {
+            return 0.0;
+        }
+        self.num_present as f64 / total as f64
+    }
+
+    /// Return true if the feature appears in every example.
+    pub fn is_required(&self) -> bool {
+        self.num_present > 0 && self.num_missing == 0
+    }
+
+    /// Get the number of values if it is the same in every occurrence.
+    pub fn fixed_len(&self) -> Option<usize> {
+        if self.num_present > 0 && self.min_len == self.max_len {
+            Some(self.min_len)
+        } else {
+            None
+        }
+    }
+}
+
+impl FeatureAccumulator for FeatureSchema {
+    fn new(num_missing: usize) -> Self {
+        Self {
+            dtype: None,
+            conflicting_types: false,
+            num_present: 0,
+            num_missing,
+            min_len: usize::MAX,
+            max_len: 0,
+            utf8: true,
+        }
+    }
+
+    fn update(&mut self, feature: &Feature) {
+        let (dtype, len) = match feature {
+            Feature::Int64List(values) => (Some(FeatureType::Int64), values.len()),
+            Feature::FloatList(values) => (Some(FeatureType::Float), values.len()),
+            Feature::BytesList(values) => {
+                if self.utf8 {
+                    self.utf8 = values.iter().all(|bytes| str::from_utf8(bytes).is_ok());
+                }
+                (Some(FeatureType::Bytes), values.len())
+            }
+            Feature::None => (None, 0),
+        };
+
+        match (self.dtype, dtype) {
+            (None, Some(dtype)) => self.dtype = Some(dtype),
+            (Some(prev), Some(dtype)) if prev != dtype => self.conflicting_types = true,
+            _ => (),
+        }
+        self.num_present += 1;
+        self.min_len = self.min_len.min(len);
+        self.max_len = self.max_len.max(len);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        match (self.dtype, other.dtype) {
+            (None, dtype) => self.dtype = dtype,
+            (Some(lhs), Some(rhs)) if lhs != rhs => self.conflicting_types = true,
+            _ => (),
+        }
+        self.conflicting_types |= other.conflicting_types;
+        self.num_present += other.num_present;
+        self.num_missing += other.num_missing;
+        self.min_len = self.min_len.min(other.min_len);
+        self.max_len = self.max_len.max(other.max_len);
+        self.utf8 &= other.utf8;
+    }
+
+    fn add_missing(&mut self, num_missing: usize) {
+        self.num_missing += num_missing;
+    }
+}
+
+/// The inferred layout of examples.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Schema {
+    /// Number of scanned examples.
+    pub num_examples: usize,
+    /// The feature layouts by name.
+    pub features: BTreeMap<String, FeatureSchema>,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Infer the schema from examples, such as those from an [ExampleReader](crate::ExampleReader).
+    pub fn from_examples<I>(examples: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = Result<Example, Error>>,
+    {
+        let mut schema = Self::new();
+        for example in examples {
+            schema.update(&example?);
+        }
+        Ok(schema)
+    }
+
+    /// Infer the schema from a stream of examples.
+    #[cfg(feature = "async_")]
+    pub async fn from_stream<S>(stream: S) -> Result<Self, Error>
+    where
+        S: Stream<Item = Result<Example, Error>>,
+    {
+        stream
+            .try_fold(Self::new(), |mut schema, example| async move {
+                schema.update(&example);
+                Ok(schema)
+            })
+            .await
+    }
+
+    /// Infer the schema from the records of a dataset.
+    ///
+    /// It scans at most `limit` records in index order if `limit` is set.
+    #[cfg(feature = "dataset")]
+    pub async fn from_dataset(dataset: &Dataset, limit: Option<usize>) -> Result<Self, Error> {
+        let limit = limit.unwrap_or(usize::MAX);
+        Self::from_stream(dataset.stream::<Example>().take(limit)).await
+    }
+
+    /// Add an example to the inferred layout.
+    ///
+    /// A feature first seen in a later example is counted as missing in the earlier ones.
+    pub fn update(&mut self, example: &Example) {
+        update_features(&mut self.features, self.num_examples, example);
+        self.num_examples += 1;
+    }
+
+    /// Merge the layout inferred from another shard of examples.
+    pub fn merge(&mut self, other: &Schema) {
+        merge_features(
+            &mut self.features,
+            self.num_examples,
+            &other.features,
+            other.num_examples,
+        );
+        self.num_examples += other.num_examples;
+    }
+
+    /// Build the parse specs.
+    ///
+    /// The features present in every example with a fixed number of values become
+    /// [FeatureSpec::FixedLen], while the others become [FeatureSpec::VarLen].
+    /// It fails if a feature has conflicting or unknown value types.
+    pub fn to_parser(&self) -> Result<ExampleParser, Error> {
+        let mut parser = ExampleParser::new();
+        for (name, schema) in self.features.iter() {
+            let dtype = self.checked_dtype(name, schema)?;
+            let spec = match schema.fixed_len() {
+                Some(len) if schema.is_required() => FeatureSpec::FixedLen {
+                    dtype,
+                    shape: if len == 1 { vec![] } else { vec![len] },
+                    default: None,
+                },
+                _ => FeatureSpec::VarLen { dtype },
+            };
+            parser = parser.feature(name.as_str(), spec);
+        }
+        Ok(parser)
+    }
+
+    /// Format the features as a Python dict of `tf.io` feature specs.
+    ///
+    /// The rules follow [to_parser](Schema::to_parser).
+    pub fn to_python_spec(&self) -> Result<String, Error> {
+        let mut code = String::from("{\n");
+        for (name, spec) in self.to_parser()?.specs.iter() {
+            let (ctor, dtype, shape) = match spec {
+                FeatureSpec::FixedLen { dtype, shape, .. } => ("FixedLenFeature", dtype, shape),
+                FeatureSpec::VarLen { dtype } => ("VarLenFeature", dtype, &vec![]),
+                FeatureSpec::Sparse(_) => unreachable!(),
+            };
+            let dtype = match dtype {
+                FeatureType::Int64 => "tf.int64",
+                FeatureType::Float => "tf.float32",
+                FeatureType::Bytes => "tf.string",
+            };
+            let name = python_str(name);
+            match spec {
+                FeatureSpec::FixedLen { .. } => {
+                    writeln!(
+                        code,
+                        "    {}: tf.io.{}({:?}, {}),",
+                        name, ctor, shape, dtype
+                    )
+                }
+                _ => writeln!(code, "    {}: tf.io.{}({}),", name, ctor, dtype),
+            }
+            .unwrap();
+        }
+        code.push('}');
+        Ok(code)
+    }
+
+    /// Generate a Rust struct definition with `#[derive(TfExample)]`.
+    ///
+    /// The optional features become `Option` fields. The bytes features are
+    /// `String` if all values are valid UTF-8.
+    pub fn to_rust_struct(&self, struct_name: &str) -> Result<String, Error> {
+        let mut code = String::new();
+        writeln!(code, "#[derive(Debug, Clone, PartialEq, TfExample)]").unwrap();
+        writeln!(code, "pub struct {} {{", struct_name).unwrap();
+
+        let mut used_idents = vec![];
+        for (name, schema) in self.features.iter() {
+            let dtype = self.checked_dtype(name, schema)?;
+            let single = schema.fixed_len() == Some(1);
+            let ty = match (dtype, single) {
+                (FeatureType::Int64, true) => "i64",
+                (FeatureType::Int64, false) => "Vec<i64>",
+                (FeatureType::Float, true) => "f32",
+                (FeatureType::Float, false) => "Vec<f32>",
+                (FeatureType::Bytes, true) if schema.utf8 => "String",
+                (FeatureType::Bytes, false) if schema.utf8 => "Vec<String>",
+                (FeatureType::Bytes, true) => "Vec<u8>",
+                (FeatureType::Bytes, false) => "Vec<Vec<u8>>",
+            };
+            let ty = if schema.is_required() {
+                ty.to_owned()
+            } else {
+                format!("Option<{}>", ty)
+            };
+
+            let ident = unique_ident(rust_ident(name), &used_idents);
+            if ident != *name {
+                writeln!(code, "    #[tfrecord(key = {:?})]", name).unwrap();
+            }
+            writeln!(code, "    pub {}: {},", ident, ty).unwrap();
+            used_idents.push(ident);
+        }
+
+        code.push('}');
+        Ok(code)
+    }
+
+    fn checked_dtype(&self, name: &str, schema: &FeatureSchema) -> Result<FeatureType, Error> {
+        match (schema.dtype, schema.conflicting_types) {
+            (Some(dtype), false) => Ok(dtype),
+            (_, true) => Err(Error::InvalidArgumentsError {
+                desc: format!(r#"the feature "{}" has conflicting value types"#, name),
+            }),
+            (None, false) => Err(Error::InvalidArgumentsError {
+                desc: format!(r#"the value type of feature "{}" is unknown"#, name),
+            }),
+        }
+    }
+}
+
+/// Quote the text as a Python string literal.
+fn python_str(text: &str) -> String {
+    let mut literal = String::from('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => literal.push_str(r#"\""#),
+            '\\' => literal.push_str(r"\\"),
+            '\n' => literal.push_str(r"\n"),
+            '\r' => literal.push_str(r"\r"),
+            '\t' => literal.push_str(r"\t"),
+            ch if ch.is_control() => write!(literal, r"\x{:02x}", ch as u32).unwrap(),
+            ch => literal.push(ch),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Convert a feature name to a snake case identifier.
+fn rust_ident(name: &str) -> String {
+    let mut ident = String::new();
+    let mut prev_lower = false;
+    for ch in name.chars() {
+        if ch.is_ascii_alphanumeric() {
+            if ch.is_ascii_uppercase() && prev_lower {
+                ident.push('_');
+            }
+            prev_lower = ch.is_ascii_lowercase() || ch.is_ascii_digit();
+            ident.push(ch.to_ascii_lowercase());
+        } else {
+            if !ident.ends_with('_') {
+                ident.push('_');
+            }
+            prev_lower = false;
+        }
+    }
+    let ident = ident.trim_matches('_').to_owned();
+
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else",
+        "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+        "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait",
+        "true", "try", "type", "unsafe", "use", "where", "while", "yield",
+    ];
+    if ident.is_empty() || ident.starts_with(|ch: char| ch.is_ascii_digit()) {
+        format!("field_{}", ident)
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        format!("{}_", ident)
+    } else {
+        ident
+    }
+}
+
+fn unique_ident(ident: String, used: &[String]) -> String {
+    if !used.contains(&ident) {
+        return ident;
+    }
+    (2..)
+        .map(|suffix| format!("{}_{}", ident, suffix))
+        .find(|candidate| !used.contains(candidate))
+        .unwrap()
+}
@@ -0,0 +1,1376 @@
+//! The serde data format that maps Rust values onto [Example].
+//!
+//! The module is available when the `with-serde` feature is enabled.
+//! The top-level value must be a struct or a map. The fields are mapped to features as follows.
+//!
+//! - Booleans and integers are stored in int64 lists of one value.
+//! - Floating point numbers are stored in float lists of one value.
+//! - Strings, characters, unit enum variants and byte buffers are stored in bytes lists of one value.
+//!   Note that `Vec<u8>` is a sequence of integers unless it is annotated with
+//!   [serde_bytes](https://crates.io/crates/serde_bytes).
+//! - Sequences and tuples of the types above are stored in lists of the corresponding type.
+//! - `None` and unit values are omitted.
+//! - Nested structs and maps are flattened to dotted keys, such as `image.width`.
+
+use crate::{error::Error, types::Example, types::Feature};
+use serde::{
+    de::{
+        self, value::StringDeserializer, DeserializeSeed, Deserializer, IntoDeserializer,
+        MapAccess, SeqAccess, Visitor,
+    },
+    ser::{self, Impossible, Serialize, Serializer},
+    Deserialize,
+};
+use std::{collections::BTreeSet, str};
+
+/// Serialize a value to an example.
+pub fn to_example<T>(value: &T) -> Result<Example, Error>
+where
+    T: ?Sized + Serialize,
+{
+    let mut example = Example::new();
+    value.serialize(ExampleSerializer::new(&mut example))?;
+    Ok(example)
+}
+
+/// Deserialize a value from an example.
+pub fn from_example<'de, T>(example: &'de Example) -> Result<T, Error>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(ExampleDeserializer::new(example))
+}
+
+/// The serializer that writes features to an example.
+#[derive(Debug)]
+pub struct ExampleSerializer<'a> {
+    example: &'a mut Example,
+    key: Option<String>,
+}
+
+impl<'a> ExampleSerializer<'a> {
+    /// Create a serializer for the top-level value.
+    pub fn new(example: &'a mut Example) -> Self {
+        Self { example, key: None }
+    }
+
+    fn insert(self, feature: Feature) -> Result<(), Error> {
+        match self.key {
+            Some(key) => {
+                self.example.insert(key, feature);
+                Ok(())
+            }
+            None => Err(top_level_error()),
+        }
+    }
+
+    fn prefix(&self) -> String {
+        self.key
+            .as_ref()
+            .map(|key| format!("{}.", key))
+            .unwrap_or_default()
+    }
+}
+
+impl<'a> Serializer for ExampleSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = ListSerializer<'a>;
+    type SerializeTuple = ListSerializer<'a>;
+    type SerializeTupleStruct = ListSerializer<'a>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = MapSerializer<'a>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_bool(self, value: bool) -> Result<(), Error> {
+        self.serialize_i64(value as i64)
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), Error> {
+        self.serialize_i64(value as i64)
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), Error> {
+        self.serialize_i64(value as i64)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), Error> {
+        self.serialize_i64(value as i64)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), Error> {
+        self.insert(Feature::Int64List(vec![value]))
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), Error> {
+        self.serialize_i64(value as i64)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), Error> {
+        self.serialize_i64(value as i64)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), Error> {
+        self.serialize_i64(value as i64)
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), Error> {
+        self.serialize_i64(u64_to_i64(value)?)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), Error> {
+        self.insert(Feature::FloatList(vec![value]))
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), Error> {
+        self.serialize_f32(value as f32)
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), Error> {
+        self.serialize_str(value.encode_utf8(&mut [0u8; 4]))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), Error> {
+        self.serialize_bytes(value.as_bytes())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), Error> {
+        self.insert(Feature::BytesList(vec![value.to_vec()]))
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(unsupported(&format!(
+            "the enum variant {}::{}",
+            name, variant
+        )))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ListSerializer<'a>, Error> {
+        if self.key.is_none() {
+            return Err(top_level_error());
+        }
+        Ok(ListSerializer {
+            serializer: self,
+            values: ListValues::Empty,
+            capacity: len.unwrap_or(0),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ListSerializer<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<ListSerializer<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(unsupported(&format!(
+            "the enum variant {}::{}",
+            name, variant
+        )))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer<'a>, Error> {
+        Ok(MapSerializer {
+            prefix: self.prefix(),
+            example: self.example,
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer<'a>, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(unsupported(&format!(
+            "the enum variant {}::{}",
+            name, variant
+        )))
+    }
+}
+
+/// The serializer for the fields of structs and maps.
+#[derive(Debug)]
+pub struct MapSerializer<'a> {
+    example: &'a mut Example,
+    prefix: String,
+    next_key: Option<String>,
+}
+
+impl<'a> MapSerializer<'a> {
+    fn serialize_entry_value<T>(&mut self, key: &str, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(ExampleSerializer {
+            example: self.example,
+            key: Some(format!("{}{}", self.prefix, key)),
+        })
+    }
+}
+
+impl<'a> ser::SerializeMap for MapSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.next_key = Some(key.serialize(MapKeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = self.next_key.take().ok_or_else(|| {
+            <Error as ser::Error>::custom("serialize_value is called before serialize_key")
+        })?;
+        self.serialize_entry_value(&key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeStruct for MapSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.serialize_entry_value(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// The serializer for sequences of values.
+#[derive(Debug)]
+pub struct ListSerializer<'a> {
+    serializer: ExampleSerializer<'a>,
+    values: ListValues,
+    capacity: usize,
+}
+
+#[derive(Debug)]
+enum ListValues {
+    Empty,
+    Int64(Vec<i64>),
+    Float(Vec<f32>),
+    Bytes(Vec<Vec<u8>>),
+}
+
+impl<'a> ListSerializer<'a> {
+    fn push<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let element = value.serialize(ElementSerializer)?;
+        let capacity = self.capacity;
+        let values = &mut self.values;
+
+        match (&mut *values, element) {
+            (ListValues::Empty, Element::Int64(value)) => {
+                let mut list = Vec::with_capacity(capacity);
+                list.push(value);
+                *values = ListValues::Int64(list);
+            }
+            (ListValues::Empty, Element::Float(value)) => {
+                let mut list = Vec::with_capacity(capacity);
+                list.push(value);
+                *values = ListValues::Float(list);
+            }
+            (ListValues::Empty, Element::Bytes(value)) => {
+                let mut list = Vec::with_capacity(capacity);
+                list.push(value);
+                *values = ListValues::Bytes(list);
+            }
+            (ListValues::Int64(list), Element::Int64(value)) => list.push(value),
+            (ListValues::Float(list), Element::Float(value)) => list.push(value),
+            (ListValues::Bytes(list), Element::Bytes(value)) => list.push(value),
+            _ => {
+                return Err(<Error as ser::Error>::custom(format!(
+                    r#"the sequence "{}" mixes values of distinct types"#,
+                    self.serializer.key.as_deref().unwrap_or("")
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        let feature = match self.values {
+            // the element type is unknown, but it must be distinct from a missing value
+            ListValues::Empty => Feature::Int64List(vec![]),
+            ListValues::Int64(list) => Feature::Int64List(list),
+            ListValues::Float(list) => Feature::FloatList(list),
+            ListValues::Bytes(list) => Feature::BytesList(list),
+        };
+        self.serializer.insert(feature)
+    }
+}
+
+impl<'a> ser::SerializeSeq for ListSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTuple for ListSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for ListSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+enum Element {
+    Int64(i64),
+    Float(f32),
+    Bytes(Vec<u8>),
+}
+
+/// Serializes the elements of sequences.
+struct ElementSerializer;
+
+impl Serializer for ElementSerializer {
+    type Ok = Element;
+    type Error = Error;
+    type SerializeSeq = Impossible<Element, Error>;
+    type SerializeTuple = Impossible<Element, Error>;
+    type SerializeTupleStruct = Impossible<Element, Error>;
+    type SerializeTupleVariant = Impossible<Element, Error>;
+    type SerializeMap = Impossible<Element, Error>;
+    type SerializeStruct = Impossible<Element, Error>;
+    type SerializeStructVariant = Impossible<Element, Error>;
+
+    fn serialize_bool(self, value: bool) -> Result<Element, Error> {
+        Ok(Element::Int64(value as i64))
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<Element, Error> {
+        Ok(Element::Int64(value as i64))
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<Element, Error> {
+        Ok(Element::Int64(value as i64))
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<Element, Error> {
+        Ok(Element::Int64(value as i64))
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Element, Error> {
+        Ok(Element::Int64(value))
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<Element, Error> {
+        Ok(Element::Int64(value as i64))
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<Element, Error> {
+        Ok(Element::Int64(value as i64))
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<Element, Error> {
+        Ok(Element::Int64(value as i64))
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<Element, Error> {
+        Ok(Element::Int64(u64_to_i64(value)?))
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<Element, Error> {
+        Ok(Element::Float(value))
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<Element, Error> {
+        Ok(Element::Float(value as f32))
+    }
+
+    fn serialize_char(self, value: char) -> Result<Element, Error> {
+        self.serialize_str(value.encode_utf8(&mut [0u8; 4]))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Element, Error> {
+        Ok(Element::Bytes(value.as_bytes().to_vec()))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Element, Error> {
+        Ok(Element::Bytes(value.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Element, Error> {
+        Err(unsupported("None in sequences"))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Element, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Element, Error> {
+        Err(unsupported("unit values in sequences"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Element, Error> {
+        Err(unsupported(&format!(
+            "the unit struct {} in sequences",
+            name
+        )))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Element, Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Element, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<Element, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(unsupported(&format!(
+            "the enum variant {}::{}",
+            name, variant
+        )))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(unsupported("nested sequences"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(unsupported("nested sequences"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(unsupported("nested sequences"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(unsupported(&format!(
+            "the enum variant {}::{}",
+            name, variant
+        )))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(unsupported("maps in sequences"))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(unsupported(&format!("the struct {} in sequences", name)))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(unsupported(&format!(
+            "the enum variant {}::{}",
+            name, variant
+        )))
+    }
+}
+
+/// Serializes the map keys to strings.
+struct MapKeySerializer;
+
+impl Serializer for MapKeySerializer {
+    type Ok = String;
+    type Error = Error;
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    fn serialize_bool(self, value: bool) -> Result<String, Error> {
+        Ok(value.to_string())
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<String, Error> {
+        Ok(value.to_string())
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<String, Error> {
+        Ok(value.to_string())
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<String, Error> {
+        Ok(value.to_string())
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<String, Error> {
+        Ok(value.to_string())
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<String, Error> {
+        Ok(value.to_string())
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<String, Error> {
+        Ok(value.to_string())
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<String, Error> {
+        Ok(value.to_string())
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<String, Error> {
+        Ok(value.to_string())
+    }
+
+    fn serialize_f32(self, _value: f32) -> Result<String, Error> {
+        Err(unsupported("floating point map keys"))
+    }
+
+    fn serialize_f64(self, _value: f64) -> Result<String, Error> {
+        Err(unsupported("floating point map keys"))
+    }
+
+    fn serialize_char(self, value: char) -> Result<String, Error> {
+        Ok(value.to_string())
+    }
+
+    fn serialize_str(self, value: &str) -> Result<String, Error> {
+        Ok(value.to_owned())
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<String, Error> {
+        Err(unsupported("byte buffer map keys"))
+    }
+
+    fn serialize_none(self) -> Result<String, Error> {
+        Err(unsupported("None map keys"))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<String, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String, Error> {
+        Err(unsupported("unit map keys"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<String, Error> {
+        Err(unsupported(&format!(
+            "the unit struct {} as map keys",
+            name
+        )))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, Error> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<String, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<String, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(unsupported(&format!(
+            "the enum variant {}::{}",
+            name, variant
+        )))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(unsupported("sequence map keys"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(unsupported("tuple map keys"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(unsupported(&format!(
+            "the tuple struct {} as map keys",
+            name
+        )))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(unsupported(&format!(
+            "the enum variant {}::{}",
+            name, variant
+        )))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(unsupported("map keys of maps"))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(unsupported(&format!("the struct {} as map keys", name)))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(unsupported(&format!(
+            "the enum variant {}::{}",
+            name, variant
+        )))
+    }
+}
+
+/// The deserializer that reads features from an example.
+#[derive(Debug, Clone)]
+pub struct ExampleDeserializer<'de> {
+    example: &'de Example,
+    key: Option<String>,
+}
+
+impl<'de> ExampleDeserializer<'de> {
+    /// Create a deserializer for the top-level value.
+    pub fn new(example: &'de Example) -> Self {
+        Self { example, key: None }
+    }
+
+    fn prefix(&self) -> String {
+        self.key
+            .as_ref()
+            .map(|key| format!("{}.", key))
+            .unwrap_or_default()
+    }
+
+    fn feature(&self) -> Result<&'de Feature, Error> {
+        let key = self.key.as_ref().ok_or_else(top_level_error)?;
+        self.example
+            .get(key)
+            .ok_or_else(|| Error::MissingFeatureError { name: key.clone() })
+    }
+
+    fn has_children(&self) -> bool {
+        let prefix = self.prefix();
+        self.example.keys().any(|key| key.starts_with(&prefix))
+    }
+
+    fn key(&self) -> &str {
+        self.key.as_deref().unwrap_or("")
+    }
+
+    fn single_int64(&self) -> Result<i64, Error> {
+        let key = self.key();
+        let values = crate::types::int64_list(key, self.feature()?)?;
+        Ok(*crate::types::single_value(key, "int64", values)?)
+    }
+
+    fn single_float(&self) -> Result<f32, Error> {
+        let key = self.key();
+        let values = crate::types::float_list(key, self.feature()?)?;
+        Ok(*crate::types::single_value(key, "float", values)?)
+    }
+
+    fn single_bytes(&self) -> Result<&'de [u8], Error> {
+        let key = self.key();
+        let values = crate::types::bytes_list(key, self.feature()?)?;
+        Ok(crate::types::single_value(key, "bytes", values)?)
+    }
+
+    fn single_str(&self) -> Result<&'de str, Error> {
+        let bytes = self.single_bytes()?;
+        crate::types::bytes_to_str(self.key(), bytes)
+    }
+
+    fn entries(&self, fields: Option<&'static [&'static str]>) -> Vec<(String, String)> {
+        let prefix = self.prefix();
+
+        match fields {
+            // the struct fields that have features
+            Some(fields) => fields
+                .iter()
+                .filter_map(|field| {
+                    let key = format!("{}{}", prefix, field);
+                    let child_prefix = format!("{}.", key);
+                    let exists = self.example.contains_key(&key)
+                        || self
+                            .example
+                            .keys()
+                            .any(|other| other.starts_with(&child_prefix));
+                    if exists {
+                        Some((field.to_string(), key))
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+            // the first segments of keys under the prefix
+            None => self
+                .example
+                .keys()
+                .filter_map(|key| key.strip_prefix(prefix.as_str()))
+                .map(|suffix| suffix.split('.').next().unwrap())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .map(|name| (name.to_owned(), format!("{}{}", prefix, name)))
+                .collect(),
+        }
+    }
+}
+
+impl<'de> Deserializer<'de> for ExampleDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let key = match &self.key {
+            Some(key) => key,
+            None => return self.deserialize_map(visitor),
+        };
+
+        match self.example.get(key) {
+            Some(Feature::Int64List(values)) if values.len() == 1 => visitor.visit_i64(values[0]),
+            Some(Feature::FloatList(values)) if values.len() == 1 => visitor.visit_f32(values[0]),
+            Some(Feature::BytesList(values)) if values.len() == 1 => {
+                match str::from_utf8(&values[0]) {
+                    Ok(text) => visitor.visit_borrowed_str(text),
+                    Err(_) => visitor.visit_borrowed_bytes(&values[0]),
+                }
+            }
+            Some(Feature::None) => visitor.visit_unit(),
+            Some(_) => self.deserialize_seq(visitor),
+            None if self.has_children() => self.deserialize_map(visitor),
+            None => Err(Error::MissingFeatureError { name: key.clone() }),
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_bool(self.single_int64()? != 0)
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.single_int64()?)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.single_int64()?)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.single_int64()?)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.single_int64()?)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.single_int64()?)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.single_int64()?)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.single_int64()?)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.single_int64()?)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_f32(self.single_float()?)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_f64(self.single_float()? as f64)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.single_str()?)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.single_str()?)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.single_str()?)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_bytes(self.single_bytes()?)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_bytes(self.single_bytes()?)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let key = match &self.key {
+            Some(key) => key,
+            None => return visitor.visit_some(self),
+        };
+
+        match self.example.get(key) {
+            Some(Feature::None) => visitor.visit_none(),
+            Some(_) => visitor.visit_some(self),
+            None if self.has_children() => visitor.visit_some(self),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let key = self.key().to_owned();
+        let values = match self.feature()? {
+            Feature::Int64List(values) => ListRef::Int64(values),
+            Feature::FloatList(values) => ListRef::Float(values),
+            Feature::BytesList(values) => ListRef::Bytes(values),
+            Feature::None => ListRef::Int64(&[]),
+        };
+        visitor.visit_seq(ListAccess {
+            key,
+            values,
+            index: 0,
+        })
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(EntryAccess {
+            example: self.example,
+            entries: self.entries(None).into_iter(),
+            next_key: None,
+        })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(EntryAccess {
+            example: self.example,
+            entries: self.entries(Some(fields)).into_iter(),
+            next_key: None,
+        })
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(self.single_str()?.into_deserializer())
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+/// Visits the fields of structs and maps.
+struct EntryAccess<'de> {
+    example: &'de Example,
+    entries: std::vec::IntoIter<(String, String)>,
+    next_key: Option<String>,
+}
+
+impl<'de> MapAccess<'de> for EntryAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.entries.next() {
+            Some((name, key)) => {
+                self.next_key = Some(key);
+                let name: StringDeserializer<Error> = name.into_deserializer();
+                seed.deserialize(name).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| <Error as de::Error>::custom("next_value is called before next_key"))?;
+        seed.deserialize(ExampleDeserializer {
+            example: self.example,
+            key: Some(key),
+        })
+    }
+}
+
+enum ListRef<'de> {
+    Int64(&'de [i64]),
+    Float(&'de [f32]),
+    Bytes(&'de [Vec<u8>]),
+}
+
+/// Visits the values of lists.
+struct ListAccess<'de> {
+    key: String,
+    values: ListRef<'de>,
+    index: usize,
+}
+
+impl<'de> SeqAccess<'de> for ListAccess<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let index = self.index;
+        self.index += 1;
+
+        match self.values {
+            ListRef::Int64(values) => values
+                .get(index)
+                .map(|&value| seed.deserialize(value.into_deserializer()))
+                .transpose(),
+            ListRef::Float(values) => values
+                .get(index)
+                .map(|&value| seed.deserialize(value.into_deserializer()))
+                .transpose(),
+            ListRef::Bytes(values) => values
+                .get(index)
+                .map(|bytes| {
+                    seed.deserialize(BytesDeserializer {
+                        key: &self.key,
+                        bytes,
+                    })
+                })
+                .transpose(),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        let len = match self.values {
+            ListRef::Int64(values) => values.len(),
+            ListRef::Float(values) => values.len(),
+            ListRef::Bytes(values) => values.len(),
+        };
+        Some(len.saturating_sub(self.index))
+    }
+}
+
+/// Deserializes a value of bytes lists.
+struct BytesDeserializer<'a, 'de> {
+    key: &'a str,
+    bytes: &'de [u8],
+}
+
+impl<'a, 'de> Deserializer<'de> for BytesDeserializer<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match str::from_utf8(self.bytes) {
+            Ok(text) => visitor.visit_borrowed_str(text),
+            Err(_) => visitor.visit_borrowed_bytes(self.bytes),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(crate::types::bytes_to_str(self.key, self.bytes)?)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let text = crate::types::bytes_to_str(self.key, self.bytes)?;
+        visitor.visit_enum(text.into_deserializer())
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_bytes(self.bytes)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_bytes(self.bytes)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let values = self.bytes.iter().cloned();
+        visitor.visit_seq(de::value::SeqDeserializer::new(values))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 unit unit_struct
+        tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+fn u64_to_i64(value: u64) -> Result<i64, Error> {
+    if value > i64::MAX as u64 {
+        return Err(<Error as ser::Error>::custom(format!(
+            "the value {} does not fit in int64",
+            value
+        )));
+    }
+    Ok(value as i64)
+}
+
+fn top_level_error() -> Error {
+    <Error as ser::Error>::custom("the top-level value must be a struct or a map")
+}
+
+fn unsupported(what: &str) -> Error {
+    <Error as ser::Error>::custom(format!("{} are not supported", what))
+}
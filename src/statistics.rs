@@ -0,0 +1,630 @@
+//! Computing the statistics of features.
+//!
+//! The [Statistics] accumulates the value counts, numeric distributions and
+//! bytes frequencies of features over examples, in the style of TensorFlow Data
+//! Validation. The statistics computed on shards or threads can be merged and
+//! exported to JSON.
+
+#[cfg(feature = "dataset")]
+use crate::dataset::Dataset;
+use crate::{
+    error::Error,
+    types::{Example, Feature, Histogram},
+};
+#[cfg(feature = "async_")]
+use futures::stream::{Stream, StreamExt, TryStreamExt};
+use noisy_float::types::R64;
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap},
+    fmt::Write,
+    hash::{Hash, Hasher},
+    sync::atomic::Ordering,
+};
+
+/// Number of distinct values tracked for the frequent bytes values.
+const MAX_TRACKED_VALUES: usize = 1000;
+/// The bytes values are truncated to the length before they are counted.
+const MAX_TRACKED_VALUE_LEN: usize = 100;
+/// Number of the smallest value hashes kept to estimate the distinct count.
+const NUM_UNIQUE_HASHES: usize = 1024;
+
+/// The statistics of a feature.
+#[derive(Debug, Clone)]
+pub struct FeatureStatistics {
+    /// Number of examples having the feature.
+    pub num_present: usize,
+    /// Number of examples without the feature.
+    pub num_missing: usize,
+    /// Total number of values.
+    pub num_values: usize,
+    /// Minimum number of values in an example.
+    pub min_num_values: usize,
+    /// Maximum number of values in an example.
+    pub max_num_values: usize,
+    /// The statistics of int64 and float values.
+    pub numeric: Option<NumericStatistics>,
+    /// The statistics of bytes values.
+    pub bytes: Option<BytesStatistics>,
+}
+
+impl FeatureStatistics {
+    /// The ratio of examples without the feature.
+    pub fn missing_rate(&self) -> f64 {
+        let total = self.num_present + self.num_missing;
+        if total == 0 {
+            return 0.0;
+        }
+        self.num_missing as f64 / total as f64
+    }
+
+    /// The average number of values in examples having the feature.
+    pub fn avg_num_values(&self) -> Option<f64> {
+        if self.num_present == 0 {
+            return None;
+        }
+        Some(self.num_values as f64 / self.num_present as f64)
+    }
+
+    fn write_json(&self, json: &mut String, top_k: usize, num_quantiles: usize) {
+        write!(
+            json,
+            r#"{{"num_present":{},"num_missing":{},"missing_rate":{},"num_values":{},"min_num_values":{},"max_num_values":{},"avg_num_values":{}"#,
+            self.num_present,
+            self.num_missing,
+            json_f64(Some(self.missing_rate())),
+            self.num_values,
+            self.min_num_values.min(self.max_num_values),
+            self.max_num_values,
+            json_f64(self.avg_num_values()),
+        )
+        .unwrap();
+        if let Some(numeric) = &self.numeric {
+            json.push_str(r#","numeric":"#);
+            numeric.write_json(json, num_quantiles);
+        }
+        if let Some(bytes) = &self.bytes {
+            json.push_str(r#","bytes":"#);
+            bytes.write_json(json, top_k);
+        }
+        json.push('}');
+    }
+}
+
+impl FeatureAccumulator for FeatureStatistics {
+    fn new(num_missing: usize) -> Self {
+        Self {
+            num_present: 0,
+            num_missing,
+            num_values: 0,
+            min_num_values: usize::MAX,
+            max_num_values: 0,
+            numeric: None,
+            bytes: None,
+        }
+    }
+
+    fn update(&mut self, feature: &Feature) {
+        let len = match feature {
+            Feature::Int64List(values) => {
+                let numeric = self.numeric.get_or_insert_with(NumericStatistics::new);
+                values
+                    .iter()
+                    .for_each(|value| numeric.update(*value as f64));
+                values.len()
+            }
+            Feature::FloatList(values) => {
+                let numeric = self.numeric.get_or_insert_with(NumericStatistics::new);
+                values
+                    .iter()
+                    .for_each(|value| numeric.update(*value as f64));
+                values.len()
+            }
+            Feature::BytesList(values) => {
+                let bytes = self.bytes.get_or_insert_with(BytesStatistics::new);
+                values.iter().for_each(|value| bytes.update(value));
+                values.len()
+            }
+            Feature::None => 0,
+        };
+
+        self.num_present += 1;
+        self.num_values += len;
+        self.min_num_values = self.min_num_values.min(len);
+        self.max_num_values = self.max_num_values.max(len);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.num_present += other.num_present;
+        self.num_missing += other.num_missing;
+        self.num_values += other.num_values;
+        self.min_num_values = self.min_num_values.min(other.min_num_values);
+        self.max_num_values = self.max_num_values.max(other.max_num_values);
+
+        match (&mut self.numeric, &other.numeric) {
+            (Some(lhs), Some(rhs)) => lhs.merge(rhs),
+            (None, Some(rhs)) => self.numeric = Some(rhs.clone()),
+            _ => (),
+        }
+        match (&mut self.bytes, &other.bytes) {
+            (Some(lhs), Some(rhs)) => lhs.merge(rhs),
+            (None, Some(rhs)) => self.bytes = Some(rhs.clone()),
+            _ => (),
+        }
+    }
+
+    fn add_missing(&mut self, num_missing: usize) {
+        self.num_missing += num_missing;
+    }
+}
+
+/// The statistics of int64 and float values.
+///
+/// The int64 values are converted to `f64`. The quantiles are estimated from
+/// the buckets of the [Histogram].
+#[derive(Debug, Clone)]
+pub struct NumericStatistics {
+    histogram: Histogram,
+    num_zeros: usize,
+    num_non_finite: usize,
+}
+
+impl NumericStatistics {
+    /// Get the histogram of finite values.
+    pub fn histogram(&self) -> &Histogram {
+        &self.histogram
+    }
+
+    /// Get the number of finite values.
+    pub fn count(&self) -> usize {
+        self.histogram.len()
+    }
+
+    /// Get the number of zero values.
+    pub fn num_zeros(&self) -> usize {
+        self.num_zeros
+    }
+
+    /// Get the number of NaN and infinite values, which are excluded from the other statistics.
+    pub fn num_non_finite(&self) -> usize {
+        self.num_non_finite
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.histogram.min()
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.histogram.max()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        match self.count() {
+            0 => None,
+            count => Some(self.histogram.sum() / count as f64),
+        }
+    }
+
+    /// Get the population standard deviation.
+    pub fn std_dev(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        let variance = self.histogram.sum_squares() / self.count() as f64 - mean.powi(2);
+        Some(variance.max(0.0).sqrt())
+    }
+
+    /// Estimate the `q`-th quantile.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        self.histogram.quantile(q)
+    }
+
+    /// Estimate the boundaries that split the values into `num_quantiles` equal parts.
+    ///
+    /// It returns `num_quantiles + 1` values starting from the minimum and ending at
+    /// the maximum, or an empty vector if there are no values.
+    pub fn quantiles(&self, num_quantiles: usize) -> Vec<f64> {
+        if num_quantiles == 0 {
+            return vec![];
+        }
+        (0..=num_quantiles)
+            .filter_map(|index| self.quantile(index as f64 / num_quantiles as f64))
+            .collect()
+    }
+
+    fn new() -> Self {
+        Self {
+            histogram: Histogram::default(),
+            num_zeros: 0,
+            num_non_finite: 0,
+        }
+    }
+
+    fn update(&mut self, value: f64) {
+        // the histogram requires finite sums of squares
+        if !value.powi(2).is_finite() {
+            self.num_non_finite += 1;
+            return;
+        }
+        if value == 0.0 {
+            self.num_zeros += 1;
+        }
+        self.histogram.add(R64::new(value));
+    }
+
+    fn merge(&mut self, other: &Self) {
+        // the histograms are built with the same default limits
+        self.histogram.merge(&other.histogram).unwrap();
+        self.num_zeros += other.num_zeros;
+        self.num_non_finite += other.num_non_finite;
+    }
+
+    fn write_json(&self, json: &mut String, num_quantiles: usize) {
+        let quantiles = self
+            .quantiles(num_quantiles)
+            .into_iter()
+            .map(|value| json_f64(Some(value)))
+            .collect::<Vec<_>>()
+            .join(",");
+        write!(
+            json,
+            r#"{{"count":{},"num_zeros":{},"num_non_finite":{},"mean":{},"std_dev":{},"min":{},"max":{},"quantiles":[{}],"histogram":["#,
+            self.count(),
+            self.num_zeros,
+            self.num_non_finite,
+            json_f64(self.mean()),
+            json_f64(self.std_dev()),
+            json_f64(self.min()),
+            json_f64(self.max()),
+            quantiles,
+        )
+        .unwrap();
+
+        // write the non-empty buckets clamped to the observed range
+        if let (Some(min), Some(max)) = (self.min(), self.max()) {
+            let mut low = min;
+            let mut is_first = true;
+            for bucket in self.histogram.buckets.iter() {
+                let high = bucket.limit.raw().min(max);
+                let count = bucket.count.load(Ordering::SeqCst);
+                if count > 0 {
+                    if !is_first {
+                        json.push(',');
+                    }
+                    is_first = false;
+                    write!(
+                        json,
+                        r#"{{"low":{},"high":{},"count":{}}}"#,
+                        json_f64(Some(low)),
+                        json_f64(Some(high)),
+                        count
+                    )
+                    .unwrap();
+                }
+                low = high.max(min);
+            }
+        }
+        json.push_str("]}");
+    }
+}
+
+/// The statistics of bytes values.
+///
+/// The memory use is bounded regardless of the number of values, so the frequencies are
+/// approximate like TensorFlow Data Validation. The frequent values are counted by a
+/// Misra-Gries summary that reports up to 1000 values truncated to their first 100 bytes.
+/// A count is never overestimated, and is underestimated by at most `count() / 1001`.
+/// The number of distinct values is estimated from the 1024 smallest hashes of the values,
+/// and is exact if fewer distinct values are seen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BytesStatistics {
+    counts: HashMap<Vec<u8>, usize>,
+    hashes: BTreeSet<u64>,
+    num_values: usize,
+    total_len: usize,
+}
+
+impl BytesStatistics {
+    /// Get the number of values.
+    pub fn count(&self) -> usize {
+        self.num_values
+    }
+
+    /// Get the estimated number of distinct values.
+    pub fn unique_count(&self) -> usize {
+        match self.hashes.iter().next_back() {
+            Some(&max_hash) if self.hashes.len() >= NUM_UNIQUE_HASHES => {
+                // the k-th smallest of uniform hashes is about k / (unique + 1) of the range
+                let fraction = (max_hash as f64 + 1.0) / (u64::MAX as f64 + 1.0);
+                ((NUM_UNIQUE_HASHES - 1) as f64 / fraction).round() as usize
+            }
+            _ => self.hashes.len(),
+        }
+    }
+
+    /// Get the average length of values in bytes.
+    pub fn avg_len(&self) -> Option<f64> {
+        match self.num_values {
+            0 => None,
+            count => Some(self.total_len as f64 / count as f64),
+        }
+    }
+
+    /// Get the approximate number of occurrences of a value.
+    ///
+    /// The values sharing the same first 100 bytes are counted together.
+    pub fn value_count(&self, value: &[u8]) -> usize {
+        self.counts.get(truncate_value(value)).cloned().unwrap_or(0)
+    }
+
+    /// Get the `k` most frequent values with their approximate counts.
+    ///
+    /// The values are truncated to their first 100 bytes, and at most 1000 values are
+    /// tracked. The values with equal counts are ordered by their bytes.
+    pub fn top_k(&self, k: usize) -> Vec<(&[u8], usize)> {
+        let mut values = self
+            .counts
+            .iter()
+            .map(|(value, count)| (value.as_slice(), *count))
+            .collect::<Vec<_>>();
+        values.sort_by(|(lhs_value, lhs_count), (rhs_value, rhs_count)| {
+            rhs_count.cmp(lhs_count).then(lhs_value.cmp(rhs_value))
+        });
+        values.truncate(k.min(MAX_TRACKED_VALUES));
+        values
+    }
+
+    fn new() -> Self {
+        Self {
+            counts: HashMap::new(),
+            hashes: BTreeSet::new(),
+            num_values: 0,
+            total_len: 0,
+        }
+    }
+
+    fn update(&mut self, value: &[u8]) {
+        let key = truncate_value(value);
+        match self.counts.get_mut(key) {
+            Some(count) => *count += 1,
+            None => {
+                self.counts.insert(key.to_owned(), 1);
+                // prune in batches to amortize the cost
+                if self.counts.len() > MAX_TRACKED_VALUES * 2 {
+                    self.prune_counts();
+                }
+            }
+        }
+
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        self.insert_hash(hasher.finish());
+
+        self.num_values += 1;
+        self.total_len += value.len();
+    }
+
+    fn merge(&mut self, other: &Self) {
+        for (value, count) in other.counts.iter() {
+            *self.counts.entry(value.to_owned()).or_insert(0) += count;
+        }
+        self.prune_counts();
+        other.hashes.iter().for_each(|&hash| self.insert_hash(hash));
+        self.num_values += other.num_values;
+        self.total_len += other.total_len;
+    }
+
+    /// Subtract the count of the (MAX_TRACKED_VALUES + 1)-th frequent value from all counts,
+    /// so that at most MAX_TRACKED_VALUES values remain.
+    fn prune_counts(&mut self) {
+        if self.counts.len() <= MAX_TRACKED_VALUES {
+            return;
+        }
+        let mut counts = self.counts.values().cloned().collect::<Vec<_>>();
+        let (_, &mut threshold, _) =
+            counts.select_nth_unstable_by(MAX_TRACKED_VALUES, |lhs, rhs| rhs.cmp(lhs));
+        self.counts.retain(|_, count| {
+            *count -= threshold.min(*count);
+            *count > 0
+        });
+    }
+
+    /// Keep the hash if it is among the NUM_UNIQUE_HASHES smallest ones.
+    fn insert_hash(&mut self, hash: u64) {
+        if self.hashes.len() >= NUM_UNIQUE_HASHES {
+            let max_hash = *self.hashes.iter().next_back().unwrap();
+            if hash >= max_hash || self.hashes.contains(&hash) {
+                return;
+            }
+            self.hashes.remove(&max_hash);
+        }
+        self.hashes.insert(hash);
+    }
+
+    fn write_json(&self, json: &mut String, top_k: usize) {
+        write!(
+            json,
+            r#"{{"count":{},"unique":{},"avg_len":{},"top_values":["#,
+            self.num_values,
+            self.unique_count(),
+            json_f64(self.avg_len()),
+        )
+        .unwrap();
+        for (index, (value, count)) in self.top_k(top_k).into_iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            json.push_str(r#"{"value":"#);
+            json_str(json, &String::from_utf8_lossy(value));
+            write!(json, r#","count":{}}}"#, count).unwrap();
+        }
+        json.push_str("]}");
+    }
+}
+
+/// The statistics of examples.
+#[derive(Debug, Clone, Default)]
+pub struct Statistics {
+    /// Number of scanned examples.
+    pub num_examples: usize,
+    /// The feature statistics by name.
+    pub features: BTreeMap<String, FeatureStatistics>,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compute the statistics of examples, such as those from an [ExampleReader](crate::ExampleReader).
+    pub fn from_examples<I>(examples: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = Result<Example, Error>>,
+    {
+        let mut statistics = Self::new();
+        for example in examples {
+            statistics.update(&example?);
+        }
+        Ok(statistics)
+    }
+
+    /// Compute the statistics of a stream of examples.
+    #[cfg(feature = "async_")]
+    pub async fn from_stream<S>(stream: S) -> Result<Self, Error>
+    where
+        S: Stream<Item = Result<Example, Error>>,
+    {
+        stream
+            .try_fold(Self::new(), |mut statistics, example| async move {
+                statistics.update(&example);
+                Ok(statistics)
+            })
+            .await
+    }
+
+    /// Compute the statistics of the records of a dataset.
+    ///
+    /// It scans at most `limit` records in index order if `limit` is set.
+    #[cfg(feature = "dataset")]
+    pub async fn from_dataset(dataset: &Dataset, limit: Option<usize>) -> Result<Self, Error> {
+        let limit = limit.unwrap_or(usize::MAX);
+        Self::from_stream(dataset.stream::<Example>().take(limit)).await
+    }
+
+    /// Add an example to the statistics.
+    pub fn update(&mut self, example: &Example) {
+        update_features(&mut self.features, self.num_examples, example);
+        self.num_examples += 1;
+    }
+
+    /// Merge the statistics computed on another shard or thread.
+    pub fn merge(&mut self, other: &Statistics) {
+        merge_features(
+            &mut self.features,
+            self.num_examples,
+            &other.features,
+            other.num_examples,
+        );
+        self.num_examples += other.num_examples;
+    }
+
+    /// Export the statistics to a JSON string.
+    ///
+    /// It includes `top_k` most frequent values of bytes features and `num_quantiles + 1`
+    /// quantile boundaries of numeric features. The non-finite numbers are written as `null`.
+    pub fn to_json(&self, top_k: usize, num_quantiles: usize) -> String {
+        let mut json = String::new();
+        write!(
+            json,
+            r#"{{"num_examples":{},"features":{{"#,
+            self.num_examples
+        )
+        .unwrap();
+        for (index, (name, statistics)) in self.features.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            json_str(&mut json, name);
+            json.push(':');
+            statistics.write_json(&mut json, top_k, num_quantiles);
+        }
+        json.push_str("}}");
+        json
+    }
+}
+
+/// The per-feature accumulator over examples, shared with [Schema](crate::Schema).
+pub(crate) trait FeatureAccumulator {
+    /// Create an accumulator for a feature first seen after `num_missing` examples.
+    fn new(num_missing: usize) -> Self;
+    fn add_missing(&mut self, num_missing: usize);
+    fn update(&mut self, feature: &Feature);
+    fn merge(&mut self, other: &Self);
+}
+
+/// Add an example to the accumulators, given the number of examples added before.
+pub(crate) fn update_features<A>(
+    features: &mut BTreeMap<String, A>,
+    num_examples: usize,
+    example: &Example,
+) where
+    A: FeatureAccumulator,
+{
+    for (name, accumulator) in features.iter_mut() {
+        if !example.contains_key(name) {
+            accumulator.add_missing(1);
+        }
+    }
+    for (name, feature) in example.iter() {
+        features
+            .entry(name.to_owned())
+            .or_insert_with(|| A::new(num_examples))
+            .update(feature);
+    }
+}
+
+/// Merge the accumulators of another set of examples.
+pub(crate) fn merge_features<A>(
+    features: &mut BTreeMap<String, A>,
+    num_examples: usize,
+    other_features: &BTreeMap<String, A>,
+    other_num_examples: usize,
+) where
+    A: FeatureAccumulator,
+{
+    for (name, accumulator) in features.iter_mut() {
+        if !other_features.contains_key(name) {
+            accumulator.add_missing(other_num_examples);
+        }
+    }
+    for (name, other_accumulator) in other_features.iter() {
+        features
+            .entry(name.to_owned())
+            .or_insert_with(|| A::new(num_examples))
+            .merge(other_accumulator);
+    }
+}
+
+fn truncate_value(value: &[u8]) -> &[u8] {
+    &value[..value.len().min(MAX_TRACKED_VALUE_LEN)]
+}
+
+fn json_f64(value: Option<f64>) -> String {
+    match value {
+        Some(value) if value.is_finite() => format!("{}", value),
+        _ => "null".into(),
+    }
+}
+
+fn json_str(json: &mut String, text: &str) {
+    json.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => json.push_str(r#"\""#),
+            '\\' => json.push_str(r"\\"),
+            '\n' => json.push_str(r"\n"),
+            '\r' => json.push_str(r"\r"),
+            '\t' => json.push_str(r"\t"),
+            ch if (ch as u32) < 0x20 => write!(json, r"\u{:04x}", ch as u32).unwrap(),
+            ch => json.push(ch),
+        }
+    }
+    json.push('"');
+}
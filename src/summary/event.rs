@@ -2,6 +2,7 @@ use super::*;
 
 /// A [Event] initializer.
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct EventInit {
     /// The wall clock time in microseconds.
     ///
@@ -92,6 +93,7 @@ impl From<(i64, SystemTime)> for EventInit {
 
 /// A [Summary] initializer.
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct SummaryInit<T>
 where
     T: ToString,
@@ -2,6 +2,7 @@ use super::*;
 
 /// The event writer initializer.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub struct EventWriterInit {
     /// If set, the writer flushes the buffer after writing a event.
     pub auto_flush: bool,
@@ -14,6 +15,12 @@ impl Default for EventWriterInit {
 }
 
 impl EventWriterInit {
+    /// Set whether to flush the buffer after writing a event.
+    pub fn with_auto_flush(mut self, auto_flush: bool) -> Self {
+        self.auto_flush = auto_flush;
+        self
+    }
+
     /// Construct an [EventWriter] from a type with [Write] trait.
     pub fn from_writer<W>(self, writer: W) -> Result<EventWriter<W>, Error>
     where
@@ -1,5 +1,6 @@
 //! High level example, feature and many other types.
 
+use crate::error::Error;
 use atomig::Atomic;
 use noisy_float::types::R64;
 #[cfg(feature = "serde")]
@@ -8,6 +9,7 @@ use std::{
     collections::HashMap,
     iter,
     ops::Neg,
+    str,
     sync::atomic::{AtomicUsize, Ordering},
 };
 
@@ -24,6 +26,275 @@ pub enum Feature {
     None,
 }
 
+/// The high level sequence example, which has context features and named feature lists.
+///
+/// The context features apply to the entire sequence, while each feature list stores
+/// one feature per step, such as per video frame or per time step.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SequenceExample {
+    pub context: Example,
+    pub feature_lists: HashMap<String, Vec<Feature>>,
+}
+
+impl SequenceExample {
+    /// Create an empty sequence example.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the feature list by name.
+    pub fn feature_list(&self, name: &str) -> Result<&[Feature], Error> {
+        self.feature_lists
+            .get(name)
+            .map(|features| features.as_slice())
+            .ok_or_else(|| Error::MissingFeatureError {
+                name: name.to_owned(),
+            })
+    }
+
+    /// Get the number of steps in the longest feature list.
+    pub fn max_len(&self) -> usize {
+        self.feature_lists
+            .values()
+            .map(|features| features.len())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+mod example {
+    use super::*;
+
+    /// Typed accessors for [Example].
+    ///
+    /// The getters return [MissingFeatureError](Error::MissingFeatureError) if the feature
+    /// does not exist, and [FeatureMismatchError](Error::FeatureMismatchError) if the feature
+    /// has another type or the scalar getters find zero or multiple values.
+    pub trait ExampleExt {
+        /// Get a feature by name.
+        fn get_feature(&self, name: &str) -> Result<&Feature, Error>;
+
+        /// Get the single value of an int64 list.
+        fn get_i64(&self, name: &str) -> Result<i64, Error> {
+            let values = self.get_i64s(name)?;
+            Ok(*single_value(name, "int64", values)?)
+        }
+
+        /// Get the values of an int64 list.
+        fn get_i64s(&self, name: &str) -> Result<&[i64], Error> {
+            int64_list(name, self.get_feature(name)?)
+        }
+
+        /// Get the single value of a float list.
+        fn get_f32(&self, name: &str) -> Result<f32, Error> {
+            let values = self.get_f32s(name)?;
+            Ok(*single_value(name, "float", values)?)
+        }
+
+        /// Get the values of a float list.
+        fn get_f32s(&self, name: &str) -> Result<&[f32], Error> {
+            float_list(name, self.get_feature(name)?)
+        }
+
+        /// Get the single value of a bytes list.
+        fn get_bytes(&self, name: &str) -> Result<&[u8], Error> {
+            let values = self.get_bytes_list(name)?;
+            single_value(name, "bytes", values).map(|value| value.as_slice())
+        }
+
+        /// Get the values of a bytes list.
+        fn get_bytes_list(&self, name: &str) -> Result<&[Vec<u8>], Error> {
+            bytes_list(name, self.get_feature(name)?)
+        }
+
+        /// Get the single value of a bytes list as a UTF-8 string.
+        fn get_str(&self, name: &str) -> Result<&str, Error> {
+            let bytes = self.get_bytes(name)?;
+            bytes_to_str(name, bytes)
+        }
+
+        /// Get the values of a bytes list as UTF-8 strings.
+        fn get_strs(&self, name: &str) -> Result<Vec<&str>, Error> {
+            self.get_bytes_list(name)?
+                .iter()
+                .map(|bytes| bytes_to_str(name, bytes))
+                .collect()
+        }
+    }
+
+    impl ExampleExt for Example {
+        fn get_feature(&self, name: &str) -> Result<&Feature, Error> {
+            self.get(name).ok_or_else(|| Error::MissingFeatureError {
+                name: name.to_owned(),
+            })
+        }
+    }
+
+    /// The fluent builder of [Example].
+    #[derive(Debug, Clone, PartialEq, Default)]
+    pub struct ExampleBuilder {
+        example: Example,
+    }
+
+    impl ExampleBuilder {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Add a feature. The feature with the same name is replaced.
+        pub fn feature<N>(mut self, name: N, feature: Feature) -> Self
+        where
+            N: Into<String>,
+        {
+            self.example.insert(name.into(), feature);
+            self
+        }
+
+        /// Add an int64 list with a single value.
+        pub fn i64<N>(self, name: N, value: i64) -> Self
+        where
+            N: Into<String>,
+        {
+            self.feature(name, Feature::Int64List(vec![value]))
+        }
+
+        /// Add an int64 list.
+        pub fn i64s<N, I>(self, name: N, values: I) -> Self
+        where
+            N: Into<String>,
+            I: IntoIterator<Item = i64>,
+        {
+            self.feature(name, Feature::Int64List(values.into_iter().collect()))
+        }
+
+        /// Add a float list with a single value.
+        pub fn f32<N>(self, name: N, value: f32) -> Self
+        where
+            N: Into<String>,
+        {
+            self.feature(name, Feature::FloatList(vec![value]))
+        }
+
+        /// Add a float list.
+        pub fn f32s<N, I>(self, name: N, values: I) -> Self
+        where
+            N: Into<String>,
+            I: IntoIterator<Item = f32>,
+        {
+            self.feature(name, Feature::FloatList(values.into_iter().collect()))
+        }
+
+        /// Add a bytes list with a single value.
+        pub fn bytes<N, B>(self, name: N, value: B) -> Self
+        where
+            N: Into<String>,
+            B: Into<Vec<u8>>,
+        {
+            self.feature(name, Feature::BytesList(vec![value.into()]))
+        }
+
+        /// Add a bytes list.
+        pub fn bytes_list<N, I>(self, name: N, values: I) -> Self
+        where
+            N: Into<String>,
+            I: IntoIterator,
+            I::Item: Into<Vec<u8>>,
+        {
+            let values = values.into_iter().map(Into::into).collect();
+            self.feature(name, Feature::BytesList(values))
+        }
+
+        /// Add a bytes list with a single UTF-8 string.
+        pub fn str<N>(self, name: N, value: &str) -> Self
+        where
+            N: Into<String>,
+        {
+            self.bytes(name, value.as_bytes())
+        }
+
+        /// Add a bytes list of UTF-8 strings.
+        pub fn strs<'a, N, I>(self, name: N, values: I) -> Self
+        where
+            N: Into<String>,
+            I: IntoIterator<Item = &'a str>,
+        {
+            self.bytes_list(name, values.into_iter().map(|value| value.as_bytes()))
+        }
+
+        pub fn build(self) -> Example {
+            self.example
+        }
+    }
+
+    impl From<ExampleBuilder> for Example {
+        fn from(builder: ExampleBuilder) -> Self {
+            builder.build()
+        }
+    }
+
+    pub(crate) fn single_value<'a, T>(
+        name: &str,
+        type_name: &str,
+        values: &'a [T],
+    ) -> Result<&'a T, Error> {
+        match values {
+            [value] => Ok(value),
+            _ => Err(Error::FeatureMismatchError {
+                name: name.to_owned(),
+                expect: format!("a single {} value", type_name),
+                found: format!("{} values", values.len()),
+            }),
+        }
+    }
+
+    pub(crate) fn int64_list<'a>(name: &str, feature: &'a Feature) -> Result<&'a [i64], Error> {
+        match feature {
+            Feature::Int64List(values) => Ok(values),
+            feature => Err(feature_mismatch(name, "an int64 list", feature)),
+        }
+    }
+
+    pub(crate) fn float_list<'a>(name: &str, feature: &'a Feature) -> Result<&'a [f32], Error> {
+        match feature {
+            Feature::FloatList(values) => Ok(values),
+            feature => Err(feature_mismatch(name, "a float list", feature)),
+        }
+    }
+
+    pub(crate) fn bytes_list<'a>(name: &str, feature: &'a Feature) -> Result<&'a [Vec<u8>], Error> {
+        match feature {
+            Feature::BytesList(values) => Ok(values),
+            feature => Err(feature_mismatch(name, "a bytes list", feature)),
+        }
+    }
+
+    pub(crate) fn feature_mismatch(name: &str, expect: &str, feature: &Feature) -> Error {
+        Error::FeatureMismatchError {
+            name: name.to_owned(),
+            expect: expect.to_owned(),
+            found: describe_feature(feature),
+        }
+    }
+
+    pub(crate) fn describe_feature(feature: &Feature) -> String {
+        match feature {
+            Feature::BytesList(values) => format!("a bytes list of {} values", values.len()),
+            Feature::FloatList(values) => format!("a float list of {} values", values.len()),
+            Feature::Int64List(values) => format!("an int64 list of {} values", values.len()),
+            Feature::None => "an empty feature".into(),
+        }
+    }
+
+    pub(crate) fn bytes_to_str<'a>(name: &str, bytes: &'a [u8]) -> Result<&'a str, Error> {
+        str::from_utf8(bytes).map_err(|err| Error::UnicodeError {
+            desc: format!(r#"the feature "{}" is not UTF-8: {}"#, name, err),
+        })
+    }
+}
+
+pub use example::*;
+
 mod histogram {
     use super::*;
 
@@ -190,6 +461,118 @@ mod histogram {
         }
     }
 
+    impl Histogram {
+        /// Add the values of another histogram with the same bucket limits.
+        ///
+        /// It returns error if the bucket limits differ.
+        pub fn merge(&self, other: &Histogram) -> Result<(), Error> {
+            let is_compatible = self.buckets.len() == other.buckets.len()
+                && self
+                    .buckets
+                    .iter()
+                    .zip(other.buckets.iter())
+                    .all(|(lhs, rhs)| lhs.limit == rhs.limit);
+            if !is_compatible {
+                return Err(Error::InvalidArgumentsError {
+                    desc: "cannot merge histograms with distinct bucket limits".into(),
+                });
+            }
+
+            for (lhs, rhs) in self.buckets.iter().zip(other.buckets.iter()) {
+                lhs.count
+                    .fetch_add(rhs.count.load(Ordering::SeqCst), Ordering::SeqCst);
+            }
+            self.len
+                .fetch_add(other.len.load(Ordering::SeqCst), Ordering::SeqCst);
+
+            let other_min = other.min.load(Ordering::SeqCst);
+            let other_max = other.max.load(Ordering::SeqCst);
+            let other_sum = other.sum.load(Ordering::SeqCst);
+            let other_sum_squares = other.sum_squares.load(Ordering::SeqCst);
+            update_atomic(&self.min, |curr| curr.min(other_min));
+            update_atomic(&self.max, |curr| curr.max(other_max));
+            update_atomic(&self.sum, |curr| curr + other_sum);
+            update_atomic(&self.sum_squares, |curr| curr + other_sum_squares);
+
+            Ok(())
+        }
+
+        /// Estimate the `q`-th quantile from the bucket counts.
+        ///
+        /// The value is linearly interpolated within the bucket and clamped to the observed
+        /// minimum and maximum. It returns `None` if the histogram is empty or `q` is
+        /// not within `[0, 1]`.
+        pub fn quantile(&self, q: f64) -> Option<f64> {
+            let min = self.min()?;
+            let max = self.max()?;
+            if !(0.0..=1.0).contains(&q) {
+                return None;
+            }
+
+            let counts = self
+                .buckets
+                .iter()
+                .map(|bucket| bucket.count.load(Ordering::SeqCst))
+                .collect::<Vec<_>>();
+            let total: usize = counts.iter().sum();
+            if total == 0 {
+                return None;
+            }
+            let rank = q * total as f64;
+
+            let mut accumulated = 0;
+            for (index, count) in counts.iter().cloned().enumerate() {
+                if count == 0 || ((accumulated + count) as f64) < rank {
+                    accumulated += count;
+                    continue;
+                }
+
+                let lower = match index {
+                    0 => min,
+                    _ => self.buckets[index - 1].limit.raw().max(min),
+                };
+                let upper = self.buckets[index].limit.raw().min(max);
+                let ratio = ((rank - accumulated as f64) / count as f64).max(0.0);
+                return Some(lower + (upper - lower) * ratio);
+            }
+            Some(max)
+        }
+    }
+
+    impl Clone for Histogram {
+        fn clone(&self) -> Self {
+            Self {
+                buckets: self
+                    .buckets
+                    .iter()
+                    .map(|bucket| Bucket {
+                        limit: bucket.limit,
+                        count: AtomicUsize::new(bucket.count.load(Ordering::SeqCst)),
+                    })
+                    .collect(),
+                len: AtomicUsize::new(self.len.load(Ordering::SeqCst)),
+                min: Atomic::new(self.min.load(Ordering::SeqCst)),
+                max: Atomic::new(self.max.load(Ordering::SeqCst)),
+                sum: Atomic::new(self.sum.load(Ordering::SeqCst)),
+                sum_squares: Atomic::new(self.sum_squares.load(Ordering::SeqCst)),
+            }
+        }
+    }
+
+    fn update_atomic<F>(atomic: &Atomic<f64>, f: F)
+    where
+        F: Fn(f64) -> f64,
+    {
+        loop {
+            let curr = atomic.load(Ordering::Acquire);
+            let new = f(curr);
+            let swapped = atomic.compare_and_swap(curr, new, Ordering::Release);
+            if swapped == curr {
+                break;
+            }
+        }
+    }
+
     impl Default for Histogram {
         fn default() -> Self {
             let pos_limits_iter = iter::successors(Some(R64::new(1e-12)), |prev| {
@@ -271,4 +654,27 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn merge_histogram() -> Result<(), Error> {
+        let lhs = Histogram::default();
+        let rhs = Histogram::default();
+        (1..=50).for_each(|value| lhs.add(R64::new(value as f64)));
+        (51..=100).for_each(|value| rhs.add(R64::new(value as f64)));
+        lhs.merge(&rhs)?;
+
+        assert_eq!(lhs.len(), 100);
+        assert_abs_diff_eq!(lhs.min().unwrap(), 1.0);
+        assert_abs_diff_eq!(lhs.max().unwrap(), 100.0);
+        assert_abs_diff_eq!(lhs.sum(), 5050.0);
+        assert_abs_diff_eq!(lhs.quantile(0.0).unwrap(), 1.0);
+        assert_abs_diff_eq!(lhs.quantile(1.0).unwrap(), 100.0);
+        assert_abs_diff_eq!(lhs.quantile(0.5).unwrap(), 50.0, epsilon = 5.0);
+        assert_eq!(lhs.quantile(1.5), None);
+
+        let other = Histogram::new(vec![R64::new(0.0)]).unwrap();
+        assert!(lhs.merge(&other).is_err());
+
+        Ok(())
+    }
 }
@@ -7,7 +7,12 @@
 //! The type aliases [ExampleWriter], [RawExampleWriter] and [BytesWriter]
 //! are [RecordWriter] writing specific record types.
 
-use crate::{error::Error, markers::GenericRecord, protos::Example as RawExample, types::Example};
+use crate::{
+    error::Error,
+    markers::GenericRecord,
+    protos::{Example as RawExample, SequenceExample as RawSequenceExample},
+    types::{Example, SequenceExample},
+};
 #[cfg(feature = "async_")]
 use futures::io::AsyncWriteExt;
 use std::{io::Write, marker::PhantomData, path::Path};
@@ -18,6 +23,10 @@ pub type BytesWriter<W> = RecordWriter<Vec<u8>, W>;
 pub type RawExampleWriter<W> = RecordWriter<RawExample, W>;
 /// Alias to [RecordWriter] which input record type is [Example].
 pub type ExampleWriter<W> = RecordWriter<Example, W>;
+/// Alias to [RecordWriter] which input record type is [RawSequenceExample].
+pub type RawSequenceExampleWriter<W> = RecordWriter<RawSequenceExample, W>;
+/// Alias to [RecordWriter] which input record type is [SequenceExample].
+pub type SequenceExampleWriter<W> = RecordWriter<SequenceExample, W>;
 
 /// The writer initializer.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
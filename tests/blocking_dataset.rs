@@ -0,0 +1,225 @@
+mod common;
+
+use common::*;
+
+#[cfg(feature = "blocking_dataset")]
+#[test]
+fn blocking_dataset_test() -> Result<()> {
+    let paths = (0..4)
+        .map(|index| DATA_DIR.join(format!("blocking_dataset_test-{}.tfrecord", index)))
+        .collect::<Vec<_>>();
+    for (index, path) in paths.iter().enumerate() {
+        let start = index as u32 * 100;
+        write_numbered_records(path, start..(start + 25))?;
+    }
+    let expect = (0..4)
+        .flat_map(|index| (index * 100)..(index * 100 + 25))
+        .collect::<Vec<u32>>();
+
+    for max_open_files in 1..=3 {
+        let mut dataset = BlockingDatasetInit::default()
+            .with_max_open_files(NonZeroUsize::new(max_open_files).unwrap())
+            .with_max_workers(NonZeroUsize::new(2).unwrap())
+            .from_paths(&paths)?;
+        ensure!(dataset.num_records() == 100, "unexpected number of records");
+
+        let numbers = dataset
+            .iter::<Vec<u8>>()
+            .map(|result| result.map(parse_number))
+            .collect::<Result<Vec<_>, _>>()?;
+        ensure!(numbers == expect, "the records must be in index order");
+
+        #[cfg(feature = "with-rayon")]
+        {
+            use rayon::iter::ParallelIterator;
+
+            let numbers = dataset
+                .par_iter::<Vec<u8>>()
+                .map(|result| result.map(parse_number))
+                .collect::<Result<Vec<_>, _>>()?;
+            ensure!(
+                numbers == expect,
+                "the parallel records must be in index order"
+            );
+        }
+
+        let number = parse_number(dataset.get::<Vec<u8>>(57)?.unwrap());
+        ensure!(number == 207, "unexpected record");
+        ensure!(dataset.get::<Vec<u8>>(100)?.is_none(), "expect no record");
+    }
+
+    let dataset = BlockingDatasetInit::default()
+        .from_prefix(DATA_DIR.join("blocking_dataset_test-").to_str().unwrap())?;
+    ensure!(dataset.num_records() == 100, "unexpected number of records");
+
+    for path in paths {
+        std::fs::remove_file(&path)?;
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "blocking_dataset", feature = "with-rayon"))]
+#[test]
+fn blocking_dataset_par_iter_after_get_test() -> Result<()> {
+    use rayon::iter::ParallelIterator;
+
+    let paths = (0..2)
+        .map(|index| DATA_DIR.join(format!("blocking_dataset_par_iter_test-{}.tfrecord", index)))
+        .collect::<Vec<_>>();
+    for (index, path) in paths.iter().enumerate() {
+        let start = index as u32 * 100;
+        write_numbered_records(path, start..(start + 100))?;
+    }
+
+    let mut dataset = BlockingDatasetInit::default()
+        .with_max_open_files(NonZeroUsize::new(1).unwrap())
+        .from_paths(&paths)?;
+
+    // the dataset keeps the only permitted file open
+    let number = parse_number(dataset.get::<Vec<u8>>(0)?.unwrap());
+    ensure!(number == 0, "unexpected record");
+
+    let numbers = dataset
+        .par_iter::<Vec<u8>>()
+        .map(|result| result.map(parse_number))
+        .collect::<Result<Vec<_>, _>>()?;
+    let expect = (0..100).chain(100..200).collect::<Vec<u32>>();
+    ensure!(
+        numbers == expect,
+        "the parallel records must be in index order"
+    );
+
+    for path in paths {
+        std::fs::remove_file(&path)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "blocking_dataset")]
+#[test]
+fn blocking_dataset_dir_and_file_stats_test() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+
+    let dir = DATA_DIR.join("blocking_dataset_dir_and_file_stats_test");
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(dir.join("sub"))?;
+
+    // every record takes 20 bytes, corrupt the data of record 2 in the nested file
+    let paths = vec![dir.join("part-0.tfrecord"), dir.join("sub/part-1.tfrecord")];
+    for path in &paths {
+        write_numbered_records(path, 0..8)?;
+    }
+    std::fs::write(dir.join("README"), b"not a record file")?;
+    let mut bytes = std::fs::read(&paths[1])?;
+    bytes[20 * 2 + 12] ^= 0xff;
+    std::fs::write(&paths[1], &bytes)?;
+
+    let filter = DirFilter {
+        include: vec!["**/*.tfrecord".into()],
+        ..Default::default()
+    };
+    let result = BlockingDatasetInit::default().from_dir(&dir, &filter);
+    ensure!(result.is_err(), "expect error");
+
+    let num_reports = Arc::new(Mutex::new(0));
+    let dataset = {
+        let num_reports = num_reports.clone();
+        BlockingDatasetInit::default()
+            .with_skip_corrupted(true)
+            .with_on_progress(ProgressCallback::new(move |_| {
+                *num_reports.lock().unwrap() += 1;
+            }))
+            .from_dir(&dir, &filter)?
+    };
+    ensure!(dataset.num_records() == 15, "unexpected number of records");
+    ensure!(
+        *num_reports.lock().unwrap() == 2,
+        "expect one report per file"
+    );
+
+    let stats = dataset.file_stats();
+    ensure!(
+        stats.iter().map(|stats| &stats.path).collect::<Vec<_>>()
+            == paths.iter().collect::<Vec<_>>(),
+        "unexpected file paths"
+    );
+    let corrupted = &stats[1].corrupted_regions;
+    ensure!(
+        corrupted.len() == 1 && corrupted[0] == (40..60),
+        "unexpected corrupted regions"
+    );
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[cfg(feature = "blocking_dataset")]
+#[test]
+fn blocking_dataset_incomplete_and_projection_test() -> Result<()> {
+    use std::io::Write;
+
+    let dir = DATA_DIR.join("blocking_dataset_incomplete_and_projection_test");
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+
+    // the file ends with an incomplete record of 10 bytes
+    let numbers_path = dir.join("numbers.tfrecord");
+    write_numbered_records(&numbers_path, 0..4)?;
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&numbers_path)?
+        .write_all(&[0u8; 10])?;
+
+    ensure!(
+        BlockingDatasetInit::default()
+            .from_paths(&[&numbers_path])
+            .is_err(),
+        "expect incomplete record error"
+    );
+    let dataset = BlockingDatasetInit::default()
+        .with_allow_incomplete(true)
+        .from_paths(&[&numbers_path])?;
+    ensure!(dataset.num_records() == 4, "unexpected number of records");
+    ensure!(
+        dataset.file_stats()[0].incomplete_tail == Some(80..90),
+        "the incomplete record must be reported"
+    );
+
+    // projection
+    let examples_path = dir.join("examples.tfrecord");
+    {
+        let mut writer: ExampleWriter<_> = RecordWriterInit::create(&examples_path)?;
+        for index in 0..4 {
+            let example = ExampleBuilder::new()
+                .i64("id", index)
+                .bytes("image", vec![0u8; 1024])
+                .build();
+            writer.send(example)?;
+        }
+        writer.flush()?;
+    }
+    let mut dataset = BlockingDatasetInit::default()
+        .with_projection(Projection::names(vec!["id"]))
+        .from_paths(&[&examples_path])?;
+
+    let example = dataset.get::<Example>(2)?.unwrap();
+    ensure!(example.len() == 1, "unexpected number of features");
+    ensure!(example.get_i64("id")? == 2, "unexpected value");
+    ensure!(
+        dataset
+            .iter::<Example>()
+            .all(|example| example.map(|example| example.len() == 1).unwrap_or(false)),
+        "expect projected examples"
+    );
+
+    dataset.set_projection(None);
+    let example = dataset.get::<Example>(0)?.unwrap();
+    ensure!(example.len() == 2, "expect the full example");
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
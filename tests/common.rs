@@ -15,14 +15,47 @@ pub use std::{
 };
 #[cfg(feature = "async_")]
 pub use tfrecord::RecordStreamInit;
+#[cfg(feature = "blocking_dataset")]
+pub use tfrecord::{BlockingDataset, BlockingDatasetInit};
+#[cfg(feature = "dataset")]
 pub use tfrecord::{
-    BytesReader, BytesWriter, Example, ExampleReader, ExampleWriter, Feature, RawExample,
-    RawExampleReader, RawExampleWriter, RecordReaderInit, RecordWriterInit,
+    BucketInit, CacheMode, Checkpoint, Dataset, DatasetInit, DatasetStreamExt, InterleaveInit,
+    LocalStorage, SampleInit, ShardBy, StopPolicy, Storage,
 };
-#[cfg(feature = "dataset")]
-pub use tfrecord::{Dataset, DatasetInit};
+pub use tfrecord::{
+    BytesReader, BytesWriter, Example, ExampleBuilder, ExampleExt, ExampleParser, ExampleReader,
+    ExampleRef, ExampleWriter, Feature, FeatureRef, FeatureSpec, FeatureType, Projection,
+    RawExample, RawExampleReader, RawExampleWriter, RawSequenceExample, RecordReaderInit,
+    RecordWriterInit, Schema, SequenceExample, SequenceExampleReader, SequenceExampleWriter,
+    SparseSpec, Statistics,
+};
+#[cfg(any(feature = "dataset", feature = "blocking_dataset"))]
+pub use tfrecord::{DirFilter, FileStats, IndexProgress, ProgressCallback};
 #[cfg(feature = "summary")]
 pub use tfrecord::{EventInit, EventWriterInit};
+#[cfg(feature = "derive")]
+pub use tfrecord::{RecordReader, RecordWriter, TfExample};
+#[cfg(feature = "s3")]
+pub use tfrecord::{S3Storage, S3StorageInit};
+
+/// Write the numbers as little-endian records.
+#[allow(dead_code)]
+pub fn write_numbered_records(path: &std::path::Path, numbers: std::ops::Range<u32>) -> Result<()> {
+    let mut writer: BytesWriter<_> = RecordWriterInit::create(path)?;
+    for number in numbers {
+        writer.send(number.to_le_bytes().to_vec())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Parse the record written by [write_numbered_records].
+#[allow(dead_code)]
+pub fn parse_number(bytes: Vec<u8>) -> u32 {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(&bytes);
+    u32::from_le_bytes(buf)
+}
 
 lazy_static::lazy_static! {
     pub static ref INPUT_TFRECORD_PATH: PathBuf = {
@@ -9,12 +9,9 @@ async fn dataset_stream_test() -> Result<()> {
     let num_workers = num_cpus::get();
 
     for max_open_files in 0..=(num_cpus::get()) {
-        let dataset = DatasetInit {
-            max_open_files: NonZeroUsize::new(max_open_files), // None if zero
-            ..Default::default()
-        }
-        .from_paths(&[&*INPUT_TFRECORD_PATH])
-        .await?;
+        let mut init = DatasetInit::default();
+        init.max_open_files = NonZeroUsize::new(max_open_files); // None if zero
+        let dataset = init.from_paths(&[&*INPUT_TFRECORD_PATH]).await?;
 
         // print header
         println!("worker_no\texample_no\tfeature_no\tname\ttype\tsize");
@@ -74,12 +71,9 @@ async fn dataset_random_access_test() -> Result<()> {
     let num_workers = num_cpus::get();
 
     for max_open_files in 0..=(num_cpus::get()) {
-        let dataset = DatasetInit {
-            max_open_files: NonZeroUsize::new(max_open_files), // None if zero
-            ..Default::default()
-        }
-        .from_paths(&[&*INPUT_TFRECORD_PATH])
-        .await?;
+        let mut init = DatasetInit::default();
+        init.max_open_files = NonZeroUsize::new(max_open_files); // None if zero
+        let dataset = init.from_paths(&[&*INPUT_TFRECORD_PATH]).await?;
 
         // print header
         println!("worker_no\tround\texample_no\tfeature_no\tname\ttype\tsize");
@@ -136,3 +130,917 @@ async fn dataset_random_access_test() -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(feature = "dataset")]
+#[async_std::test]
+async fn dataset_glob_and_dir_test() -> Result<()> {
+    let root_dir = DATA_DIR.join("dataset_glob_and_dir_test");
+    if root_dir.exists() {
+        std::fs::remove_dir_all(&root_dir)?;
+    }
+
+    // write nested date-partitioned files, each file contains one record per split index
+    for date in &["2020-01-01", "2020-01-02"] {
+        let dir = root_dir.join(date).join("part");
+        std::fs::create_dir_all(&dir)?;
+
+        for (split, num_records) in &[("train", 3), ("val", 1)] {
+            let path = dir.join(format!("{}-0.tfrecord", split));
+            let mut writer: BytesWriter<_> = RecordWriterInit::create(&path)?;
+            for index in 0..*num_records {
+                writer.send(vec![index as u8])?;
+            }
+            writer.flush()?;
+        }
+    }
+
+    // glob
+    {
+        let pattern = format!("{}/**/train-*.tfrecord*", root_dir.display());
+        let dataset = DatasetInit::default().from_glob(&pattern).await?;
+        ensure!(dataset.num_records() == 6, "unexpected number of records");
+    }
+
+    // recursive walk with filters
+    {
+        let filter = DirFilter {
+            include: vec!["**/*.tfrecord".into()],
+            exclude: vec!["2020-01-02/**".into()],
+            ..Default::default()
+        };
+        let dataset = DatasetInit::default().from_dir(&root_dir, &filter).await?;
+        ensure!(dataset.num_records() == 4, "unexpected number of records");
+    }
+
+    // depth limit excludes files in nested directories
+    {
+        let filter = DirFilter {
+            max_depth: NonZeroUsize::new(2),
+            ..Default::default()
+        };
+        let dataset = DatasetInit::default().from_dir(&root_dir, &filter).await?;
+        ensure!(dataset.num_records() == 0, "unexpected number of records");
+    }
+
+    // invalid patterns are reported as errors
+    {
+        let result = DatasetInit::default().from_glob("[").await;
+        ensure!(result.is_err(), "expect error");
+    }
+
+    std::fs::remove_dir_all(&root_dir)?;
+    Ok(())
+}
+
+#[cfg(feature = "dataset")]
+#[async_std::test]
+async fn dataset_shuffle_test() -> Result<()> {
+    let path = DATA_DIR.join("dataset_shuffle_test.tfrecord");
+    write_numbered_records(&path, 0..100)?;
+    let dataset = DatasetInit::default().from_paths(&[&path]).await?;
+
+    // global permutation visits every record exactly once
+    let shuffled = |seed, epoch| {
+        dataset
+            .shuffled_stream::<Vec<u8>>(seed, epoch)
+            .map_ok(parse_number)
+            .try_collect::<Vec<_>>()
+    };
+    let first = shuffled(7, 0).await?;
+    ensure!(
+        first == shuffled(7, 0).await?,
+        "the order is not reproducible"
+    );
+    ensure!(
+        first != shuffled(7, 1).await?,
+        "the order does not change across epochs"
+    );
+    ensure!(
+        first != (0..100).collect::<Vec<_>>(),
+        "the records are not shuffled"
+    );
+    let mut sorted = first.clone();
+    sorted.sort();
+    ensure!(
+        sorted == (0..100).collect::<Vec<_>>(),
+        "records are missing"
+    );
+
+    // shuffle buffer
+    let buffered = |seed, epoch| {
+        dataset
+            .stream::<Vec<u8>>()
+            .shuffle(10, seed)
+            .with_epoch(epoch)
+            .map_ok(parse_number)
+            .try_collect::<Vec<_>>()
+    };
+    let first = buffered(7, 0).await?;
+    ensure!(
+        first == buffered(7, 0).await?,
+        "the order is not reproducible"
+    );
+    ensure!(
+        first != buffered(7, 1).await?,
+        "the order does not change across epochs"
+    );
+    let mut sorted = first.clone();
+    sorted.sort();
+    ensure!(
+        sorted == (0..100).collect::<Vec<_>>(),
+        "records are missing"
+    );
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[cfg(feature = "dataset")]
+#[async_std::test]
+async fn dataset_combinator_test() -> Result<()> {
+    let path = DATA_DIR.join("dataset_combinator_test.tfrecord");
+    write_numbered_records(&path, 0..10)?;
+    let dataset = DatasetInit::default().from_paths(&[&path]).await?;
+
+    // batch
+    let batches = dataset
+        .stream::<Vec<u8>>()
+        .map_ok(parse_number)
+        .batch(4, false)
+        .try_collect::<Vec<_>>()
+        .await?;
+    ensure!(
+        batches == vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7], vec![8, 9]],
+        "unexpected batches"
+    );
+
+    let batches = dataset
+        .stream::<Vec<u8>>()
+        .batch(4, true)
+        .try_collect::<Vec<_>>()
+        .await?;
+    ensure!(batches.len() == 2, "the remainder is not dropped");
+
+    // prefetch, parallel map and filter
+    let numbers = dataset
+        .stream::<Vec<u8>>()
+        .prefetch(3)
+        .par_map(4, |bytes| Ok(parse_number(bytes) * 2))
+        .filter_ok(|number| number % 4 == 0)
+        .prefetch_bytes(64, |_| 16)
+        .try_collect::<Vec<_>>()
+        .await?;
+    ensure!(numbers == vec![0, 4, 8, 12, 16], "unexpected records");
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[cfg(feature = "dataset")]
+#[async_std::test]
+async fn dataset_shard_and_split_test() -> Result<()> {
+    let paths = vec![
+        DATA_DIR.join("dataset_shard_and_split_test-0.tfrecord"),
+        DATA_DIR.join("dataset_shard_and_split_test-1.tfrecord"),
+    ];
+    write_numbered_records(&paths[0], 0..50)?;
+    write_numbered_records(&paths[1], 50..100)?;
+    let dataset = DatasetInit::default().from_paths(&paths).await?;
+
+    let collect = |dataset: Dataset| async move {
+        dataset
+            .stream::<Vec<u8>>()
+            .map_ok(parse_number)
+            .try_collect::<Vec<_>>()
+            .await
+    };
+
+    // shard by record
+    {
+        let mut numbers = vec![];
+        for index in 0..3 {
+            let shard = dataset.shard(3, index, ShardBy::Record)?;
+            numbers.extend(collect(shard).await?);
+        }
+        numbers.sort();
+        ensure!(
+            numbers == (0..100).collect::<Vec<_>>(),
+            "shards are not disjoint"
+        );
+    }
+
+    // shard by file
+    {
+        let shard = dataset.shard(2, 1, ShardBy::File)?;
+        ensure!(
+            collect(shard).await? == (50..100).collect::<Vec<_>>(),
+            "unexpected shard"
+        );
+        ensure!(dataset.shard(2, 2, ShardBy::File).is_err(), "expect error");
+
+        // a file appearing again is not counted as a new file
+        let dataset = DatasetInit::default()
+            .from_paths(&[&paths[0], &paths[1], &paths[0]])
+            .await?;
+        let shard = dataset.shard(3, 0, ShardBy::File)?;
+        ensure!(shard.num_records() == 100, "unexpected shard");
+        let shard = dataset.shard(3, 2, ShardBy::File)?;
+        ensure!(shard.num_records() == 0, "unexpected shard");
+    }
+
+    // seeded split
+    {
+        let parts = dataset.split(&[0.8, 0.1, 0.1], 3)?;
+        let num_records = parts.iter().map(|part| part.num_records()).sum::<usize>();
+        ensure!(num_records == 100, "records are missing");
+        ensure!(
+            parts[0].num_records() > parts[1].num_records(),
+            "unexpected ratios"
+        );
+
+        let again = dataset.split(&[0.8, 0.1, 0.1], 3)?;
+        ensure!(
+            collect(parts[1].clone()).await? == collect(again[1].clone()).await?,
+            "the split is not stable"
+        );
+        ensure!(dataset.split(&[-1.0, 1.0], 3).is_err(), "expect error");
+
+        // the split does not depend on the order of records
+        let reversed = DatasetInit::default()
+            .from_paths(&[&paths[1], &paths[0]])
+            .await?;
+        let reversed_parts = reversed.split(&[0.8, 0.1, 0.1], 3)?;
+        for (part, reversed_part) in parts.into_iter().zip(reversed_parts) {
+            let mut numbers = collect(part).await?;
+            let mut reversed_numbers = collect(reversed_part).await?;
+            numbers.sort();
+            reversed_numbers.sort();
+            ensure!(
+                numbers == reversed_numbers,
+                "the split depends on the order"
+            );
+        }
+    }
+
+    for path in paths {
+        std::fs::remove_file(&path)?;
+    }
+
+    // split by feature hash
+    {
+        let path = DATA_DIR.join("dataset_split_by_feature_test.tfrecord");
+        let mut writer: ExampleWriter<_> = RecordWriterInit::create(&path)?;
+        for index in 0..40 {
+            let mut example = Example::new();
+            let user = format!("user-{}", index % 8).into_bytes();
+            example.insert("user".into(), Feature::BytesList(vec![user]));
+            example.insert("index".into(), Feature::Int64List(vec![index]));
+            writer.send(example)?;
+        }
+        writer.flush()?;
+
+        let dataset = DatasetInit::default().from_paths(&[&path]).await?;
+        let parts = dataset.split_by_feature(&[0.5, 0.5], "user").await?;
+
+        // records of the same user fall into the same part
+        for part in parts {
+            let users = part
+                .stream::<Example>()
+                .map_ok(|example| example["index"].clone())
+                .map_ok(|feature| match feature {
+                    Feature::Int64List(list) => list[0] % 8,
+                    _ => unreachable!(),
+                })
+                .try_collect::<std::collections::HashSet<_>>()
+                .await?;
+            ensure!(
+                part.num_records() == users.len() * 5,
+                "records of a user are separated"
+            );
+        }
+
+        ensure!(
+            dataset.split_by_feature(&[1.0], "missing").await.is_err(),
+            "expect error"
+        );
+        std::fs::remove_file(&path)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "dataset")]
+#[async_std::test]
+async fn dataset_interleave_and_sample_test() -> Result<()> {
+    let paths = vec![
+        DATA_DIR.join("dataset_interleave_and_sample_test-0.tfrecord"),
+        DATA_DIR.join("dataset_interleave_and_sample_test-1.tfrecord"),
+        DATA_DIR.join("dataset_interleave_and_sample_test-2.tfrecord"),
+    ];
+    write_numbered_records(&paths[0], 0..3)?;
+    write_numbered_records(&paths[1], 10..12)?;
+    write_numbered_records(&paths[2], 20..24)?;
+
+    let mut datasets = vec![];
+    for path in paths.iter() {
+        datasets.push(DatasetInit::default().from_paths(&[path]).await?);
+    }
+    let streams = || {
+        datasets
+            .iter()
+            .map(|dataset| dataset.stream::<Vec<u8>>().map_ok(parse_number))
+            .collect::<Vec<_>>()
+    };
+
+    // round-robin with cycle length
+    {
+        let numbers = InterleaveInit::default()
+            .with_cycle_length(NonZeroUsize::new(2).unwrap())
+            .from_streams(streams())
+            .try_collect::<Vec<_>>()
+            .await?;
+        ensure!(
+            numbers == vec![0, 10, 1, 11, 2, 20, 21, 22, 23],
+            "unexpected order {:?}",
+            numbers
+        );
+    }
+
+    // stop on the first exhausted stream
+    {
+        let numbers = InterleaveInit::default()
+            .with_stop_policy(StopPolicy::FirstExhausted)
+            .from_streams(streams())
+            .try_collect::<Vec<_>>()
+            .await?;
+        ensure!(
+            numbers == vec![0, 10, 20, 1, 11, 21, 2],
+            "unexpected order {:?}",
+            numbers
+        );
+    }
+
+    // weighted sampling
+    {
+        let sample = |seed| {
+            let streams = streams().into_iter().zip(vec![0.5, 0.3, 0.2]).collect();
+            SampleInit::default().with_seed(seed).from_streams(streams)
+        };
+        let numbers = sample(1)?.try_collect::<Vec<_>>().await?;
+        ensure!(
+            numbers == sample(1)?.try_collect::<Vec<_>>().await?,
+            "the sampling is not reproducible"
+        );
+        let mut sorted = numbers.clone();
+        sorted.sort();
+        ensure!(
+            sorted == vec![0, 1, 2, 10, 11, 20, 21, 22, 23],
+            "records are missing"
+        );
+
+        let streams = streams().into_iter().zip(vec![1.0, -1.0, 1.0]).collect();
+        ensure!(
+            SampleInit::default().from_streams(streams).is_err(),
+            "expect error"
+        );
+    }
+
+    for path in paths {
+        std::fs::remove_file(&path)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "dataset")]
+#[async_std::test]
+async fn dataset_checkpoint_test() -> Result<()> {
+    use tfrecord::dataset::{Interleave, Shuffle};
+
+    let paths = vec![
+        DATA_DIR.join("dataset_checkpoint_test-0.tfrecord"),
+        DATA_DIR.join("dataset_checkpoint_test-1.tfrecord"),
+    ];
+    write_numbered_records(&paths[0], 0..30)?;
+    write_numbered_records(&paths[1], 100..120)?;
+    let mut datasets = vec![];
+    for path in paths.iter() {
+        datasets.push(DatasetInit::default().from_paths(&[path]).await?);
+    }
+
+    let build = || {
+        let streams = datasets
+            .iter()
+            .map(|dataset| dataset.shuffled_stream::<Vec<u8>>(5, 1))
+            .collect();
+        InterleaveInit::default()
+            .from_streams(streams)
+            .shuffle(8, 11)
+            .with_epoch(2)
+    };
+    let expect = build().map_ok(parse_number).try_collect::<Vec<_>>().await?;
+
+    // consume a part of the stream and save the state
+    let mut stream = build();
+    let mut numbers = vec![];
+    for _ in 0..17 {
+        let bytes = stream.try_next().await?.unwrap();
+        numbers.push(parse_number(bytes));
+    }
+    let state = stream.checkpoint();
+    drop(stream);
+
+    // round trip the state through serde
+    #[cfg(feature = "serde")]
+    let state = {
+        use tfrecord::dataset::{DatasetStreamState, InterleaveState, ShuffleState};
+        let state: ShuffleState<InterleaveState<DatasetStreamState>, Vec<u8>> =
+            serde_json::from_str(&serde_json::to_string(&state)?)?;
+        state
+    };
+
+    // resume from the state
+    let interleave = Interleave::from_state(state.stream.clone(), |index, state| {
+        datasets[index].stream_from_state::<Vec<u8>>(&state)
+    });
+    let stream = Shuffle::from_state(interleave, state);
+    numbers.extend(stream.map_ok(parse_number).try_collect::<Vec<_>>().await?);
+
+    ensure!(numbers == expect, "the resumed stream diverges");
+
+    for path in paths {
+        std::fs::remove_file(&path)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "dataset")]
+#[async_std::test]
+async fn dataset_repeat_and_bucket_test() -> Result<()> {
+    use tfrecord::dataset::feature_len;
+
+    // repeat with a reshuffling hook
+    let path = DATA_DIR.join("dataset_repeat_test.tfrecord");
+    write_numbered_records(&path, 0..10)?;
+    let dataset = DatasetInit::default().from_paths(&[&path]).await?;
+
+    let numbers = dataset
+        .repeat::<Vec<u8>>(Some(3))
+        .map_ok(parse_number)
+        .try_collect::<Vec<_>>()
+        .await?;
+    ensure!(numbers.len() == 30, "expect 30 records");
+    ensure!(
+        numbers
+            .chunks(10)
+            .all(|epoch| epoch == (0..10).collect::<Vec<_>>().as_slice()),
+        "each epoch must be in index order"
+    );
+
+    let mut epochs = vec![];
+    let numbers = dataset
+        .repeat_with(Some(2), |dataset, epoch| {
+            epochs.push(epoch);
+            dataset.shuffled_stream::<Vec<u8>>(3, epoch as u64)
+        })
+        .map_ok(parse_number)
+        .try_collect::<Vec<_>>()
+        .await?;
+    ensure!(
+        epochs == vec![0, 1],
+        "the hook must be called once per epoch"
+    );
+    ensure!(
+        numbers[..10] != numbers[10..],
+        "the epochs must be reshuffled"
+    );
+    for epoch in numbers.chunks(10) {
+        let mut sorted = epoch.to_vec();
+        sorted.sort_unstable();
+        ensure!(
+            sorted == (0..10).collect::<Vec<_>>(),
+            "each epoch must be a permutation"
+        );
+    }
+    std::fs::remove_file(&path)?;
+
+    // bucket the examples by the length of a feature
+    let lengths = vec![1usize, 7, 3, 12, 2, 8, 15, 4, 9, 0];
+    let examples = lengths
+        .iter()
+        .map(|&len| {
+            let mut example = Example::new();
+            example.insert("tokens".into(), Feature::Int64List(vec![0; len]));
+            example
+        })
+        .collect::<Vec<_>>();
+    let stream = || futures::stream::iter(examples.clone().into_iter().map(Ok));
+
+    let init = BucketInit::new(vec![5, 10], vec![2, 2, 4]);
+    let batches = init
+        .clone()
+        .from_stream(stream(), feature_len("tokens"))?
+        .map_ok(|batch| {
+            batch
+                .into_iter()
+                .map(|example| match &example["tokens"] {
+                    Feature::Int64List(list) => list.len(),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+    ensure!(
+        batches
+            == vec![
+                vec![1, 3],
+                vec![7, 8],
+                vec![2, 4],
+                vec![0],
+                vec![9],
+                vec![12, 15]
+            ],
+        "unexpected batches {:?}",
+        batches
+    );
+
+    let num_batches = init
+        .clone()
+        .with_drop_remainder(true)
+        .from_stream(stream(), feature_len("tokens"))?
+        .try_collect::<Vec<_>>()
+        .await?
+        .len();
+    ensure!(num_batches == 3, "the incomplete batches must be dropped");
+
+    let result =
+        BucketInit::new(vec![5, 10], vec![2, 2]).from_stream(stream(), feature_len("tokens"));
+    ensure!(result.is_err(), "mismatched batch sizes must be rejected");
+
+    Ok(())
+}
+
+#[cfg(feature = "dataset")]
+#[async_std::test]
+async fn dataset_cache_test() -> Result<()> {
+    let path = DATA_DIR.join("dataset_cache_test.tfrecord");
+    let cache_path = DATA_DIR.join("dataset_cache_test.cache");
+    let cache_index_path = DATA_DIR.join("dataset_cache_test.cache.index");
+    let _ = std::fs::remove_file(&cache_path);
+    let _ = std::fs::remove_file(&cache_index_path);
+    write_numbered_records(&path, 0..20)?;
+    let dataset = DatasetInit::default().from_paths(&[&path]).await?;
+
+    // the memory cache keeps serving the records within the budget
+    let cached = dataset.cache(CacheMode::Memory { max_bytes: 40 }).await?;
+    let numbers = cached
+        .stream::<Vec<u8>>()
+        .map_ok(parse_number)
+        .try_collect::<Vec<_>>()
+        .await?;
+    ensure!(numbers == (0..20).collect::<Vec<_>>(), "unexpected records");
+
+    let mut cached = cached.clone();
+    write_numbered_records(&path, 100..120)?;
+    let number = parse_number(cached.get::<Vec<u8>>(3).await?.unwrap());
+    ensure!(
+        number == 3,
+        "the record must be served from the memory cache"
+    );
+    let number = parse_number(cached.get::<Vec<u8>>(15).await?.unwrap());
+    ensure!(
+        number == 115,
+        "the record beyond the budget must not be cached"
+    );
+
+    // the disk cache is built once and reused
+    let dataset = DatasetInit::default().from_paths(&[&path]).await?;
+    let mode = CacheMode::Disk {
+        path: cache_path.clone().into(),
+    };
+    let numbers = dataset
+        .cache(mode.clone())
+        .await?
+        .stream::<Vec<u8>>()
+        .map_ok(parse_number)
+        .try_collect::<Vec<_>>()
+        .await?;
+    ensure!(
+        numbers == (100..120).collect::<Vec<_>>(),
+        "unexpected records"
+    );
+    let modified = std::fs::metadata(&cache_index_path)?.modified()?;
+
+    let cached = dataset.cache(mode.clone()).await?;
+    ensure!(
+        std::fs::metadata(&cache_index_path)?.modified()? == modified,
+        "the disk cache must be reused"
+    );
+    ensure!(cached.num_records() == 20, "unexpected number of records");
+
+    // the disk cache is rebuilt after the source file changes
+    write_numbered_records(&path, 200..210)?;
+    let dataset = DatasetInit::default().from_paths(&[&path]).await?;
+    let numbers = dataset
+        .cache(mode)
+        .await?
+        .stream::<Vec<u8>>()
+        .map_ok(parse_number)
+        .try_collect::<Vec<_>>()
+        .await?;
+    ensure!(
+        numbers == (200..210).collect::<Vec<_>>(),
+        "the disk cache is stale"
+    );
+
+    std::fs::remove_file(&path)?;
+    std::fs::remove_file(&cache_path)?;
+    std::fs::remove_file(&cache_index_path)?;
+    Ok(())
+}
+
+#[cfg(feature = "dataset")]
+#[async_std::test]
+async fn dataset_refresh_test() -> Result<()> {
+    use std::io::Write;
+
+    let dir = DATA_DIR.join("dataset_refresh_test");
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+    let path_a = dir.join("part-0.tfrecord");
+    let path_b = dir.join("part-1.tfrecord");
+
+    let append_records = |path: &std::path::Path, numbers: std::ops::Range<u32>| -> Result<()> {
+        let file = std::fs::OpenOptions::new().append(true).open(path)?;
+        let mut writer: BytesWriter<_> = RecordWriterInit::from_writer(file)?;
+        for number in numbers {
+            writer.send(number.to_le_bytes().to_vec())?;
+        }
+        writer.flush()?;
+        Ok(())
+    };
+    let collect_numbers = |dataset: &Dataset| {
+        dataset
+            .stream::<Vec<u8>>()
+            .map_ok(parse_number)
+            .try_collect::<Vec<_>>()
+    };
+
+    write_numbered_records(&path_a, 0..5)?;
+    let prefix = format!("{}/part-", dir.display());
+    let init = DatasetInit::default().with_allow_incomplete(true);
+    let mut dataset = init.clone().from_prefix(&prefix).await?;
+    ensure!(dataset.num_records() == 5, "unexpected number of records");
+
+    // nothing changes
+    ensure!(dataset.refresh().await? == 0, "expect no new records");
+
+    // append records along with an incomplete record, and add a new file
+    let partial = {
+        let mut bytes = vec![];
+        let mut writer: BytesWriter<_> = RecordWriterInit::from_writer(&mut bytes)?;
+        writer.send(8u32.to_le_bytes().to_vec())?;
+        writer.flush()?;
+        drop(writer);
+        bytes
+    };
+    append_records(&path_a, 5..8)?;
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path_a)?
+        .write_all(&partial[0..14])?;
+    write_numbered_records(&path_b, 100..103)?;
+
+    // a dataset opened in the middle of a write skips the incomplete record if allowed
+    ensure!(
+        DatasetInit::default().from_prefix(&prefix).await.is_err(),
+        "expect incomplete record error"
+    );
+    let mut opened = init.from_prefix(&prefix).await?;
+    ensure!(opened.num_records() == 11, "unexpected number of records");
+    let tail_offset = opened.file_stats()[0].num_bytes;
+    ensure!(
+        opened.file_stats()[0].incomplete_tail == Some(tail_offset..(tail_offset + 14)),
+        "the incomplete record must be reported"
+    );
+
+    let stale = dataset.clone();
+    ensure!(
+        dataset.refresh().await? == 6,
+        "unexpected number of new records"
+    );
+    ensure!(stale.num_records() == 5, "the clones must not be affected");
+    let numbers = collect_numbers(&dataset).await?;
+    ensure!(
+        numbers == (0..8).chain(100..103).collect::<Vec<_>>(),
+        "unexpected records"
+    );
+
+    // the incomplete record is indexed once it is completed
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path_a)?
+        .write_all(&partial[14..])?;
+    ensure!(
+        dataset.refresh().await? == 1,
+        "unexpected number of new records"
+    );
+    ensure!(
+        opened.refresh().await? == 1,
+        "unexpected number of new records"
+    );
+    ensure!(
+        opened.file_stats()[0].incomplete_tail.is_none(),
+        "the completed record must not be reported"
+    );
+    ensure!(
+        parse_number(dataset.get::<Vec<u8>>(11).await?.unwrap()) == 8,
+        "unexpected record"
+    );
+
+    // derived datasets cannot be refreshed
+    let mut shard = dataset.shard(2, 0, ShardBy::Record)?;
+    ensure!(shard.refresh().await.is_err(), "expect error");
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[cfg(feature = "dataset")]
+#[async_std::test]
+async fn dataset_progress_and_file_stats_test() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+
+    let dir = DATA_DIR.join("dataset_progress_and_file_stats_test");
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+
+    // every record takes 20 bytes, corrupt the data of record 2 and the length of record 4
+    let paths = (0..3)
+        .map(|index| dir.join(format!("part-{}.tfrecord", index)))
+        .collect::<Vec<_>>();
+    for (index, path) in paths.iter().enumerate() {
+        write_numbered_records(path, 0..(index as u32 + 8))?;
+    }
+    let mut bytes = std::fs::read(&paths[1])?;
+    bytes[20 * 2 + 12] ^= 0xff;
+    bytes[20 * 4] ^= 0xff;
+    std::fs::write(&paths[1], &bytes)?;
+
+    // corrupted records are errors by default
+    let result = DatasetInit::default().from_paths(&paths).await;
+    ensure!(result.is_err(), "expect error");
+
+    let reports = Arc::new(Mutex::new(vec![]));
+    let dataset = {
+        let reports = reports.clone();
+        DatasetInit::default()
+            .with_skip_corrupted(true)
+            .with_on_progress(ProgressCallback::new(move |progress| {
+                reports.lock().unwrap().push(progress.clone());
+            }))
+            .from_paths(&paths)
+            .await?
+    };
+
+    let stats = dataset.file_stats();
+    ensure!(stats.len() == 3, "unexpected number of file stats");
+    ensure!(
+        stats
+            .iter()
+            .map(|stats| stats.num_records)
+            .collect::<Vec<_>>()
+            == vec![8, 7, 10],
+        "unexpected numbers of records"
+    );
+    ensure!(
+        stats[1].corrupted_regions == vec![40..60, 80..100],
+        "unexpected corrupted regions"
+    );
+    ensure!(stats[1].num_bytes == 9 * 20, "unexpected number of bytes");
+    ensure!(dataset.num_records() == 25, "unexpected number of records");
+
+    let reports = reports.lock().unwrap();
+    ensure!(reports.len() == 3, "expect one report per file");
+    ensure!(
+        reports
+            .iter()
+            .map(|progress| progress.num_files_done)
+            .collect::<Vec<_>>()
+            == vec![1, 2, 3],
+        "unexpected progress"
+    );
+    let last = reports.last().unwrap();
+    ensure!(
+        last.num_files == 3 && last.num_records == 25 && last.num_bytes == 27 * 20,
+        "unexpected progress"
+    );
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[cfg(feature = "dataset")]
+#[async_std::test]
+async fn dataset_projection_test() -> Result<()> {
+    let dir = DATA_DIR.join("dataset_projection_test");
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("part-0.tfrecord");
+
+    {
+        let mut writer: ExampleWriter<_> = RecordWriterInit::create(&path)?;
+        for index in 0..4 {
+            let example = ExampleBuilder::new()
+                .i64("id", index)
+                .f32s("embedding", vec![index as f32; 16])
+                .bytes("image", vec![0u8; 1024])
+                .build();
+            writer.send(example)?;
+        }
+        writer.flush()?;
+    }
+
+    let mut dataset = DatasetInit::default()
+        .with_projection(Projection::names(vec!["id"]))
+        .from_paths(&[&path])
+        .await?;
+
+    // get
+    let example = dataset.get::<Example>(2).await?.unwrap();
+    ensure!(example.len() == 1, "unexpected number of features");
+    ensure!(example.get_i64("id")? == 2, "unexpected value");
+
+    // stream
+    let examples: Vec<Example> = dataset.stream().try_collect().await?;
+    ensure!(
+        examples.iter().all(|example| example.len() == 1),
+        "expect projected examples"
+    );
+
+    // change the projection
+    dataset.set_projection(Some(Projection::predicate(|name| name != "image")));
+    let example = dataset.get::<Example>(3).await?.unwrap();
+    ensure!(example.len() == 2, "unexpected number of features");
+    ensure!(
+        example.get_f32s("embedding")? == vec![3.0; 16].as_slice(),
+        "unexpected value"
+    );
+
+    dataset.set_projection(None);
+    let example = dataset.get::<Example>(0).await?.unwrap();
+    ensure!(example.len() == 3, "expect the full example");
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[cfg(feature = "dataset")]
+#[async_std::test]
+async fn dataset_schema_test() -> Result<()> {
+    let dir = DATA_DIR.join("dataset_schema_test");
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("part-0.tfrecord");
+
+    {
+        let mut writer: ExampleWriter<_> = RecordWriterInit::create(&path)?;
+        for index in 0..10 {
+            let mut builder = ExampleBuilder::new().i64("id", index);
+            if index % 2 == 0 {
+                builder = builder.f32s("score", vec![index as f32; index as usize + 1]);
+            }
+            writer.send(builder.build())?;
+        }
+        writer.flush()?;
+    }
+
+    let dataset = DatasetInit::default().from_paths(&[&path]).await?;
+
+    let schema = Schema::from_dataset(&dataset, None).await?;
+    ensure!(schema.num_examples == 10, "unexpected number of examples");
+    ensure!(
+        schema.features["id"].is_required(),
+        "expect a required feature"
+    );
+    let score = &schema.features["score"];
+    ensure!(score.presence_rate() == 0.5, "unexpected presence rate");
+    ensure!(
+        (score.min_len, score.max_len) == (1, 9),
+        "unexpected lengths"
+    );
+
+    let schema = Schema::from_dataset(&dataset, Some(3)).await?;
+    ensure!(schema.num_examples == 3, "unexpected number of examples");
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
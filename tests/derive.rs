@@ -0,0 +1,175 @@
+mod common;
+
+use common::*;
+
+#[cfg(feature = "derive")]
+#[derive(Debug, Clone, PartialEq, TfExample)]
+struct BoundingBox {
+    #[tfrecord(len = 4)]
+    coords: Vec<f32>,
+    label: String,
+}
+
+#[cfg(feature = "derive")]
+#[derive(Debug, Clone, PartialEq, TfExample)]
+struct Record {
+    #[tfrecord(key = "image/encoded")]
+    image: Vec<u8>,
+    id: i64,
+    width: i32,
+    is_train: bool,
+    tags: Vec<String>,
+    weight: Option<f32>,
+    #[tfrecord(default = "default_version")]
+    version: i64,
+    #[tfrecord(default)]
+    scores: Vec<f32>,
+    #[tfrecord(prefix = "bbox/")]
+    bbox: BoundingBox,
+    #[tfrecord(skip)]
+    cached: Option<String>,
+}
+
+#[cfg(feature = "derive")]
+#[derive(Debug, Clone, PartialEq, TfExample)]
+struct Shadowing {
+    prefix: String,
+    example: i64,
+    #[tfrecord(prefix = "inner/")]
+    inner: BoundingBox,
+    after: i64,
+}
+
+#[cfg(feature = "derive")]
+fn default_version() -> i64 {
+    2
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_test() -> Result<()> {
+    use std::convert::TryFrom;
+
+    let record = Record {
+        image: vec![1, 2, 3],
+        id: 7,
+        width: 640,
+        is_train: true,
+        tags: vec!["cat".into(), "dog".into()],
+        weight: None,
+        version: 1,
+        scores: vec![0.5],
+        bbox: BoundingBox {
+            coords: vec![0.0, 0.0, 1.0, 1.0],
+            label: "cat".into(),
+        },
+        cached: None,
+    };
+
+    // encode to examples
+    let example = Example::try_from(record.clone())?;
+    ensure!(
+        example.get_bytes("image/encoded")? == [1, 2, 3],
+        "unexpected feature"
+    );
+    ensure!(example.get_i64("width")? == 640, "unexpected feature");
+    ensure!(example.get_i64("is_train")? == 1, "unexpected feature");
+    ensure!(
+        example.get_str("bbox/label")? == "cat",
+        "unexpected feature"
+    );
+    ensure!(
+        !example.contains_key("weight"),
+        "the None feature must be omitted"
+    );
+    ensure!(
+        !example.contains_key("cached"),
+        "the skipped field must be omitted"
+    );
+    ensure!(
+        Record::try_from(&example)? == record,
+        "the record is not preserved"
+    );
+
+    // optional and default fields
+    let mut example = example;
+    example.remove("version");
+    example.remove("scores");
+    example.insert("weight".into(), Feature::FloatList(vec![0.25]));
+    let decoded = Record::try_from(RawExample::from(example.clone()))?;
+    ensure!(decoded.version == 2, "unexpected default value");
+    ensure!(decoded.scores.is_empty(), "unexpected default value");
+    ensure!(decoded.weight == Some(0.25), "unexpected optional value");
+
+    // missing, mismatched and wrong length features
+    {
+        let mut example = example.clone();
+        example.remove("id");
+        ensure!(
+            Record::try_from(&example).is_err(),
+            "expect missing feature error"
+        );
+    }
+    {
+        let mut example = example.clone();
+        example.insert("id".into(), Feature::FloatList(vec![1.0]));
+        ensure!(Record::try_from(&example).is_err(), "expect mismatch error");
+    }
+    {
+        let mut example = example.clone();
+        example.insert("bbox/coords".into(), Feature::FloatList(vec![1.0]));
+        ensure!(Record::try_from(&example).is_err(), "expect length error");
+    }
+    {
+        let mut record = record.clone();
+        record.bbox.coords.pop();
+        ensure!(Example::try_from(record).is_err(), "expect length error");
+    }
+
+    // read and write typed records
+    let path = DATA_DIR.join("derive_test.tfrecord");
+    {
+        let mut writer: RecordWriter<Record, _> = RecordWriterInit::create(&path)?;
+        writer.send(record.clone())?;
+        writer.send(decoded.clone())?;
+        writer.flush()?;
+    }
+    let reader: RecordReader<Record, _> = RecordReaderInit::default().open(&path)?;
+    let records = reader.collect::<Result<Vec<_>, _>>()?;
+    ensure!(records == vec![record, decoded], "unexpected records");
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_field_names_test() -> Result<()> {
+    use std::convert::TryFrom;
+
+    // the fields named like the generated parameters must not change the keys
+    let record = Shadowing {
+        prefix: "oops/".into(),
+        example: 3,
+        inner: BoundingBox {
+            coords: vec![0.0, 0.5, 1.0, 1.0],
+            label: "dog".into(),
+        },
+        after: 5,
+    };
+    let example = Example::try_from(record.clone())?;
+    ensure!(example.get_str("prefix")? == "oops/", "unexpected feature");
+    ensure!(example.get_i64("example")? == 3, "unexpected feature");
+    ensure!(
+        example.get_str("inner/label")? == "dog",
+        "unexpected feature"
+    );
+    ensure!(example.get_i64("after")? == 5, "unexpected feature");
+    ensure!(example.len() == 5, "unexpected keys");
+    ensure!(
+        Shadowing::try_from(&example)? == record,
+        "the record is not preserved"
+    );
+
+    Ok(())
+}
@@ -186,3 +186,963 @@ fn serde_test() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn example_accessor_and_builder_test() -> Result<()> {
+    let example = ExampleBuilder::new()
+        .i64("label", 3)
+        .i64s("ids", vec![1, 2, 3])
+        .f32("score", 0.5)
+        .f32s("embedding", vec![0.25, 0.75])
+        .bytes("image", vec![0u8, 1, 2])
+        .str("name", "cat")
+        .strs("tags", vec!["a", "b"])
+        .feature("empty", Feature::None)
+        .build();
+
+    ensure!(example.len() == 8, "unexpected number of features");
+    ensure!(example.get_i64("label")? == 3, "unexpected value");
+    ensure!(example.get_i64s("ids")? == [1, 2, 3], "unexpected values");
+    ensure!(example.get_f32("score")? == 0.5, "unexpected value");
+    ensure!(
+        example.get_f32s("embedding")? == [0.25, 0.75],
+        "unexpected values"
+    );
+    ensure!(example.get_bytes("image")? == [0, 1, 2], "unexpected value");
+    ensure!(example.get_str("name")? == "cat", "unexpected value");
+    ensure!(
+        example.get_strs("tags")? == vec!["a", "b"],
+        "unexpected values"
+    );
+    ensure!(
+        example.get_bytes_list("tags")? == [b"a".to_vec(), b"b".to_vec()],
+        "unexpected values"
+    );
+
+    // missing and mismatched features
+    ensure!(
+        matches!(
+            example.get_i64("missing"),
+            Err(tfrecord::Error::MissingFeatureError { .. })
+        ),
+        "expect missing feature error"
+    );
+    ensure!(
+        matches!(
+            example.get_f32s("label"),
+            Err(tfrecord::Error::FeatureMismatchError { .. })
+        ),
+        "expect mismatch error"
+    );
+    ensure!(
+        matches!(
+            example.get_i64("ids"),
+            Err(tfrecord::Error::FeatureMismatchError { .. })
+        ),
+        "expect mismatch error for multiple values"
+    );
+    ensure!(example.get_str("empty").is_err(), "expect mismatch error");
+
+    let invalid = ExampleBuilder::new().bytes("name", vec![0xffu8]).build();
+    ensure!(invalid.get_str("name").is_err(), "expect unicode error");
+
+    Ok(())
+}
+
+fn sample_sequence_examples() -> Vec<SequenceExample> {
+    (0..3)
+        .map(|index| {
+            let mut example = SequenceExample::new();
+            example.context = ExampleBuilder::new()
+                .i64("video_id", index)
+                .str("label", "walking")
+                .build();
+            example.feature_lists.insert(
+                "frame/timestamp".into(),
+                (0..4).map(|step| Feature::Int64List(vec![step])).collect(),
+            );
+            example.feature_lists.insert(
+                "frame/embedding".into(),
+                (0..4)
+                    .map(|step| Feature::FloatList(vec![step as f32, index as f32]))
+                    .collect(),
+            );
+            example
+        })
+        .collect()
+}
+
+#[test]
+fn sequence_example_test() -> Result<()> {
+    let examples = sample_sequence_examples();
+
+    // round trip through the raw proto type
+    for example in examples.iter() {
+        let raw = RawSequenceExample::from(example);
+        ensure!(raw.context.is_some(), "expect context features");
+        ensure!(
+            SequenceExample::from(raw) == *example,
+            "the conversion is not lossless"
+        );
+    }
+    let empty = RawSequenceExample::from(SequenceExample::new());
+    ensure!(
+        empty.context.is_none() && empty.feature_lists.is_none(),
+        "expect empty proto"
+    );
+
+    // write and read
+    let mut bytes = vec![];
+    {
+        let mut writer: SequenceExampleWriter<_> = RecordWriterInit::from_writer(&mut bytes)?;
+        for example in examples.iter().cloned() {
+            writer.send(example)?;
+        }
+        writer.flush()?;
+    }
+    let reader: SequenceExampleReader<_> =
+        RecordReaderInit::default().from_reader(Cursor::new(bytes))?;
+    let decoded = reader.collect::<Result<Vec<_>, _>>()?;
+    ensure!(decoded == examples, "the read examples do not match");
+
+    let example = &decoded[1];
+    ensure!(
+        example.context.get_i64("video_id")? == 1,
+        "unexpected context"
+    );
+    ensure!(example.max_len() == 4, "unexpected number of steps");
+    ensure!(
+        example.feature_list("frame/embedding")?[2] == Feature::FloatList(vec![2.0, 1.0]),
+        "unexpected feature"
+    );
+    ensure!(
+        example.feature_list("missing").is_err(),
+        "expect missing feature error"
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "async_")]
+#[async_std::test]
+async fn sequence_example_stream_test() -> Result<()> {
+    let output_path = DATA_DIR.join("sequence_example_output.tfrecord");
+    let examples = sample_sequence_examples();
+
+    {
+        let mut writer: SequenceExampleWriter<_> =
+            RecordWriterInit::create_async(&output_path).await?;
+        for example in examples.iter().cloned() {
+            writer.send_async(example).await?;
+        }
+        writer.flush_async().await?;
+    }
+
+    let decoded: Vec<_> = RecordStreamInit::default()
+        .sequence_examples_open(&output_path)
+        .await?
+        .try_collect()
+        .await?;
+    ensure!(decoded == examples, "the streamed examples do not match");
+
+    let raw_examples: Vec<_> = RecordStreamInit::default()
+        .raw_sequence_examples_open(&output_path)
+        .await?
+        .try_collect()
+        .await?;
+    ensure!(raw_examples.len() == 3, "unexpected number of examples");
+
+    async_std::fs::remove_file(&output_path).await?;
+    Ok(())
+}
+
+#[test]
+fn example_ref_test() -> Result<()> {
+    use tfrecord::GenericRecord;
+
+    let example = ExampleBuilder::new()
+        .bytes("image", vec![7u8; 1024])
+        .str("name", "cat")
+        .strs("tags", vec!["a", "b"])
+        .f32s("bbox", vec![0.25, 0.5, 0.75, 1.0])
+        .i64s("ids", vec![-1, 0, 1 << 40])
+        .feature("empty", Feature::None)
+        .build();
+    let bytes = Example::to_bytes(example.clone())?;
+    let view = ExampleRef::from_bytes(&bytes)?;
+
+    // the values are borrowed from the buffer
+    let image = view.get_bytes("image")?;
+    ensure!(image.len() == 1024, "unexpected value");
+    let range = bytes.as_ptr_range();
+    ensure!(
+        range.contains(&image.as_ptr()),
+        "the value is not borrowed from the buffer"
+    );
+    ensure!(view.get_str("name")? == "cat", "unexpected value");
+    let tags = view.get_bytes_list("tags")?.to_vec()?;
+    ensure!(tags == [b"a", b"b"], "unexpected values");
+    ensure!(
+        view.get_float_list("bbox")?.to_vec()? == [0.25, 0.5, 0.75, 1.0],
+        "unexpected values"
+    );
+    let ids = view.get_int64_list("ids")?;
+    ensure!(ids.len()? == 3, "unexpected length");
+    ensure!(
+        ids.iter().collect::<Result<Vec<_>, _>>()? == [-1, 0, 1 << 40],
+        "unexpected values"
+    );
+    ensure!(
+        view.get("empty")? == Some(FeatureRef::None),
+        "expect empty feature"
+    );
+    ensure!(view.get("missing")?.is_none(), "expect missing feature");
+    ensure!(
+        matches!(
+            view.get_float_list("ids"),
+            Err(tfrecord::Error::FeatureMismatchError { .. })
+        ),
+        "expect mismatch error"
+    );
+    ensure!(view.keys().count() == 6, "unexpected number of features");
+    ensure!(
+        view.to_example()? == example,
+        "the copied example does not match"
+    );
+
+    // concatenated messages are merged, and the later features take effect
+    let other = ExampleBuilder::new()
+        .str("name", "dog")
+        .i64("label", 3)
+        .build();
+    let mut merged_bytes = bytes.clone();
+    merged_bytes.extend(Example::to_bytes(other)?);
+    let merged = ExampleRef::from_bytes(&merged_bytes)?;
+    ensure!(merged.get_str("name")? == "dog", "unexpected value");
+    ensure!(
+        merged.get_int64_list("label")?.to_vec()? == [3],
+        "unexpected value"
+    );
+    ensure!(
+        merged.to_example()? == Example::from_bytes(merged_bytes.clone())?,
+        "the merged example does not match prost decoding"
+    );
+
+    // repeated lists of the same case are concatenated, and a distinct case replaces them
+    let field = |tag: u8, body: &[u8]| -> Vec<u8> {
+        let mut bytes = vec![tag << 3 | 2, body.len() as u8];
+        bytes.extend_from_slice(body);
+        bytes
+    };
+    let bytes_list = |value: &[u8]| field(1, &field(1, value));
+    let mixed_feature = [
+        bytes_list(b"a"),
+        field(3, &field(1, &[5])),
+        bytes_list(b"b"),
+        bytes_list(b"c"),
+    ]
+    .concat();
+    let mixed_entry = [field(1, b"mixed"), field(2, &mixed_feature)].concat();
+    let split_entry = [
+        field(1, b"split"),
+        field(2, &bytes_list(b"d")),
+        field(2, &bytes_list(b"e")),
+    ]
+    .concat();
+    let repeated_bytes = field(
+        1,
+        &[field(1, &mixed_entry), field(1, &split_entry)].concat(),
+    );
+    let repeated = ExampleRef::from_bytes(&repeated_bytes)?;
+    ensure!(
+        repeated.get_bytes_list("mixed")?.to_vec()? == [b"b", b"c"],
+        "unexpected value"
+    );
+    ensure!(
+        repeated.get_bytes_list("split")?.to_vec()? == [b"d", b"e"],
+        "unexpected value"
+    );
+    ensure!(
+        repeated.to_example()? == Example::from_bytes(repeated_bytes.clone())?,
+        "the repeated lists do not match prost decoding"
+    );
+
+    // truncated buffers are rejected
+    ensure!(
+        ExampleRef::from_bytes(&bytes[..bytes.len() - 1]).is_err(),
+        "expect decode error"
+    );
+
+    Ok(())
+}
+
+fn wide_example_bytes() -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut writer: ExampleWriter<_> = RecordWriterInit::from_writer(&mut bytes)?;
+    for index in 0..3 {
+        let example = (0..50)
+            .fold(ExampleBuilder::new(), |builder, column| {
+                builder.i64(format!("column/{}", column), index * 100 + column)
+            })
+            .bytes("image", vec![0u8; 256])
+            .str("label", "cat")
+            .build();
+        writer.send(example)?;
+    }
+    writer.flush()?;
+    Ok(bytes)
+}
+
+#[test]
+fn projection_reader_test() -> Result<()> {
+    use tfrecord::GenericRecord;
+
+    let bytes = wide_example_bytes()?;
+
+    // select by names
+    let reader: ExampleReader<_> = RecordReaderInit::default()
+        .with_projection(Projection::names(vec!["column/3", "label", "missing"]))
+        .from_reader(Cursor::new(bytes.clone()))?;
+    let examples = reader.collect::<Result<Vec<_>, _>>()?;
+    ensure!(examples.len() == 3, "unexpected number of examples");
+    for (index, example) in examples.iter().enumerate() {
+        ensure!(example.len() == 2, "unexpected number of features");
+        ensure!(
+            example.get_i64("column/3")? == index as i64 * 100 + 3,
+            "unexpected value"
+        );
+        ensure!(example.get_str("label")? == "cat", "unexpected value");
+    }
+
+    // select by a predicate
+    let reader: ExampleReader<_> = RecordReaderInit::default()
+        .with_projection(Projection::predicate(|name| name.starts_with("column/")))
+        .from_reader(Cursor::new(bytes.clone()))?;
+    for example in reader {
+        let example = example?;
+        ensure!(example.len() == 50, "unexpected number of features");
+        ensure!(!example.contains_key("image"), "unexpected feature");
+    }
+
+    // the projection does not affect raw bytes
+    let reader: BytesReader<_> = RecordReaderInit::default()
+        .with_projection(Projection::names(vec!["label"]))
+        .from_reader(Cursor::new(bytes.clone()))?;
+    let full: ExampleReader<_> = RecordReaderInit::default().from_reader(Cursor::new(bytes))?;
+    for (record, example) in reader.zip(full) {
+        ensure!(
+            Example::from_bytes(record?)? == example?,
+            "the bytes are projected"
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "async_")]
+#[async_std::test]
+async fn projection_stream_test() -> Result<()> {
+    let bytes = wide_example_bytes()?;
+    let examples: Vec<Example> = RecordStreamInit::default()
+        .with_projection(Projection::names(vec!["column/7"]))
+        .examples_from_reader(futures::io::Cursor::new(bytes))
+        .await?
+        .try_collect()
+        .await?;
+
+    ensure!(examples.len() == 3, "unexpected number of examples");
+    for (index, example) in examples.iter().enumerate() {
+        ensure!(example.len() == 1, "unexpected number of features");
+        ensure!(
+            example.get_i64("column/7")? == index as i64 * 100 + 7,
+            "unexpected value"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn example_parser_test() -> Result<()> {
+    use std::convert::TryFrom;
+    use tfrecord::{
+        parser::{DenseTensor, SparseTensor},
+        protos::{
+            feature_configuration::Config, tensor_shape_proto::Dim, DataType,
+            ExampleParserConfiguration, FeatureConfiguration, FixedLenFeatureProto, TensorProto,
+            TensorShapeProto, VarLenFeatureProto,
+        },
+        GenericRecord,
+    };
+
+    let parser = ExampleParser::new()
+        .fixed_len("label", FeatureType::Int64, vec![], None)
+        .fixed_len("bbox", FeatureType::Float, vec![2, 2], None)
+        .fixed_len(
+            "weight",
+            FeatureType::Float,
+            vec![],
+            Some(Feature::FloatList(vec![1.0])),
+        )
+        .var_len("tags", FeatureType::Bytes)
+        .var_len("ids", FeatureType::Int64)
+        .sparse(
+            "mask",
+            SparseSpec {
+                index_keys: vec!["mask/row".into(), "mask/col".into()],
+                value_key: "mask/value".into(),
+                dtype: FeatureType::Float,
+                size: vec![4, 4],
+                already_sorted: false,
+            },
+        );
+
+    let example = ExampleBuilder::new()
+        .i64("label", 3)
+        .f32s("bbox", vec![0.0, 0.1, 0.8, 0.9])
+        .strs("tags", vec!["a", "b"])
+        .i64s("mask/row", vec![2, 0])
+        .i64s("mask/col", vec![1, 3])
+        .f32s("mask/value", vec![0.5, 0.25])
+        .str("unused", "ignored")
+        .build();
+    let parsed = parser.parse(&example)?;
+
+    ensure!(
+        parsed.dense["label"]
+            == DenseTensor {
+                shape: vec![],
+                values: Feature::Int64List(vec![3]),
+            },
+        "unexpected dense output"
+    );
+    ensure!(parsed.dense["bbox"].shape == [2, 2], "unexpected shape");
+    ensure!(
+        parsed.dense["weight"].values == Feature::FloatList(vec![1.0]),
+        "expect the default value"
+    );
+    ensure!(
+        parsed.sparse["tags"]
+            == SparseTensor {
+                indices: vec![vec![0], vec![1]],
+                values: Feature::BytesList(vec![b"a".to_vec(), b"b".to_vec()]),
+                dense_shape: vec![2],
+            },
+        "unexpected var-len output"
+    );
+    ensure!(
+        parsed.sparse["ids"].values == Feature::Int64List(vec![]),
+        "expect empty var-len output"
+    );
+    ensure!(
+        parsed.sparse["mask"]
+            == SparseTensor {
+                indices: vec![vec![0, 3], vec![2, 1]],
+                values: Feature::FloatList(vec![0.25, 0.5]),
+                dense_shape: vec![4, 4],
+            },
+        "unexpected sparse output"
+    );
+
+    // parse from bytes
+    let bytes = Example::to_bytes(example.clone())?;
+    ensure!(parser.parse_bytes(&bytes)? == parsed, "unexpected outputs");
+
+    // per-feature errors
+    let check_error = |example: &Example, name: &str| match parser.parse(example) {
+        Err(tfrecord::Error::MissingFeatureError { name: found })
+        | Err(tfrecord::Error::FeatureMismatchError { name: found, .. }) => found == name,
+        _ => false,
+    };
+    let mut missing = example.clone();
+    missing.remove("label");
+    ensure!(check_error(&missing, "label"), "expect missing label");
+
+    let mut wrong_len = example.clone();
+    wrong_len.insert("bbox".into(), Feature::FloatList(vec![0.0; 3]));
+    ensure!(check_error(&wrong_len, "bbox"), "expect wrong length");
+
+    let mut wrong_type = example.clone();
+    wrong_type.insert("tags".into(), Feature::FloatList(vec![0.0]));
+    ensure!(check_error(&wrong_type, "tags"), "expect wrong type");
+
+    let mut out_of_range = example.clone();
+    out_of_range.insert("mask/col".into(), Feature::Int64List(vec![1, 4]));
+    ensure!(
+        check_error(&out_of_range, "mask/col"),
+        "expect out of range"
+    );
+
+    // build from the parser configuration proto
+    let config = ExampleParserConfiguration {
+        feature_map: vec![
+            (
+                "label".to_owned(),
+                FeatureConfiguration {
+                    config: Some(Config::FixedLenFeature(FixedLenFeatureProto {
+                        dtype: DataType::DtInt64 as i32,
+                        shape: Some(TensorShapeProto {
+                            dim: vec![Dim {
+                                size: 1,
+                                name: "".into(),
+                            }],
+                            unknown_rank: false,
+                        }),
+                        default_value: Some(TensorProto {
+                            dtype: DataType::DtInt64 as i32,
+                            int64_val: vec![-1],
+                            ..Default::default()
+                        }),
+                        values_output_tensor_name: "".into(),
+                    })),
+                },
+            ),
+            (
+                "tags".to_owned(),
+                FeatureConfiguration {
+                    config: Some(Config::VarLenFeature(VarLenFeatureProto {
+                        dtype: DataType::DtString as i32,
+                        ..Default::default()
+                    })),
+                },
+            ),
+        ]
+        .into_iter()
+        .collect(),
+    };
+    let parser = ExampleParser::try_from(&config)?;
+    let parsed = parser.parse(&missing)?;
+    ensure!(
+        parsed.dense["label"]
+            == DenseTensor {
+                shape: vec![1],
+                values: Feature::Int64List(vec![-1]),
+            },
+        "expect the default value"
+    );
+    ensure!(parsed.sparse["tags"].dense_shape == [2], "unexpected shape");
+
+    Ok(())
+}
+
+#[test]
+fn schema_inference_test() -> Result<()> {
+    let examples = vec![
+        ExampleBuilder::new()
+            .i64("label", 1)
+            .f32s("bbox", vec![0.0, 0.0, 1.0, 1.0])
+            .strs("tags", vec!["cat", "dog"])
+            .bytes("image/raw", vec![0xff, 0x00])
+            .build(),
+        ExampleBuilder::new()
+            .i64("label", 0)
+            .f32s("bbox", vec![0.5, 0.5, 1.0, 1.0])
+            .strs("tags", vec!["bird"])
+            .build(),
+    ];
+
+    let mut bytes = vec![];
+    {
+        let mut writer: ExampleWriter<_> = RecordWriterInit::from_writer(&mut bytes)?;
+        for example in examples.iter().cloned() {
+            writer.send(example)?;
+        }
+        writer.flush()?;
+    }
+    let reader: ExampleReader<_> = RecordReaderInit::default().from_reader(Cursor::new(bytes))?;
+    let schema = Schema::from_examples(reader)?;
+
+    ensure!(schema.num_examples == 2, "unexpected number of examples");
+    let label = &schema.features["label"];
+    ensure!(label.dtype == Some(FeatureType::Int64), "unexpected type");
+    ensure!(
+        label.is_required() && label.fixed_len() == Some(1),
+        "unexpected layout"
+    );
+    let tags = &schema.features["tags"];
+    ensure!(tags.fixed_len().is_none(), "expect variable length");
+    ensure!((tags.min_len, tags.max_len) == (1, 2), "unexpected lengths");
+    let image = &schema.features["image/raw"];
+    ensure!(image.presence_rate() == 0.5, "unexpected presence rate");
+    ensure!(!image.utf8, "expect non-UTF-8 bytes");
+
+    // parse spec
+    let parser = schema.to_parser()?;
+    ensure!(
+        parser.specs["bbox"]
+            == FeatureSpec::FixedLen {
+                dtype: FeatureType::Float,
+                shape: vec![4],
+                default: None,
+            },
+        "unexpected spec"
+    );
+    ensure!(
+        parser.specs["image/raw"]
+            == FeatureSpec::VarLen {
+                dtype: FeatureType::Bytes
+            },
+        "unexpected spec"
+    );
+    let parsed = parser.parse(&examples[1])?;
+    ensure!(
+        parsed.dense["label"].values == Feature::Int64List(vec![0]),
+        "unexpected value"
+    );
+
+    // code emission
+    let python = schema.to_python_spec()?;
+    ensure!(
+        python.contains(r#""bbox": tf.io.FixedLenFeature([4], tf.float32),"#),
+        "unexpected python spec"
+    );
+    ensure!(
+        python.contains(r#""tags": tf.io.VarLenFeature(tf.string),"#),
+        "unexpected python spec"
+    );
+    let code = schema.to_rust_struct("Record")?;
+    ensure!(code.contains("pub struct Record {"), "unexpected struct");
+    ensure!(code.contains("pub label: i64,"), "unexpected field");
+    ensure!(code.contains("pub tags: Vec<String>,"), "unexpected field");
+    ensure!(
+        code.contains("    #[tfrecord(key = \"image/raw\")]\n    pub image_raw: Option<Vec<u8>>,"),
+        "unexpected field"
+    );
+
+    // merge schemas from shards
+    let mut merged = Schema::from_examples(examples[..1].iter().cloned().map(Ok))?;
+    merged.merge(&Schema::from_examples(
+        examples[1..].iter().cloned().map(Ok),
+    )?);
+    ensure!(merged == schema, "merged schema differs");
+
+    // conflicting types
+    let mut conflicting = schema.clone();
+    conflicting.update(&ExampleBuilder::new().f32("label", 1.0).build());
+    ensure!(conflicting.to_parser().is_err(), "expect conflicting types");
+
+    // feature names are quoted as Python string literals
+    let odd = Schema::from_examples(vec![Ok(ExampleBuilder::new()
+        .i64("a\"b\\c\n\u{7f}é", 1)
+        .build())])?;
+    ensure!(
+        odd.to_python_spec()?
+            .contains(r#""a\"b\\c\n\x7fé": tf.io.FixedLenFeature([], tf.int64),"#),
+        "unexpected python spec"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn statistics_test() -> Result<()> {
+    let examples = (0..100)
+        .map(|index| {
+            let mut builder = ExampleBuilder::new()
+                .i64("id", index)
+                .f32s("score", vec![index as f32 / 10.0, 0.0]);
+            if index % 4 != 0 {
+                let label = if index % 2 == 0 { "cat" } else { "dog" };
+                builder = builder.strs("label", vec![label]);
+            }
+            builder.build()
+        })
+        .collect::<Vec<_>>();
+
+    let statistics = Statistics::from_examples(examples.iter().cloned().map(Ok))?;
+    ensure!(
+        statistics.num_examples == 100,
+        "unexpected number of examples"
+    );
+
+    // numeric features
+    let id = &statistics.features["id"];
+    let numeric = id.numeric.as_ref().unwrap();
+    ensure!(id.missing_rate() == 0.0, "unexpected missing rate");
+    ensure!(numeric.count() == 100, "unexpected count");
+    ensure!(numeric.num_zeros() == 1, "unexpected number of zeros");
+    ensure!(numeric.min() == Some(0.0), "unexpected min");
+    ensure!(numeric.max() == Some(99.0), "unexpected max");
+    ensure!(
+        (numeric.mean().unwrap() - 49.5).abs() < 1e-9,
+        "unexpected mean"
+    );
+    ensure!(
+        (numeric.std_dev().unwrap() - 28.866).abs() < 1e-3,
+        "unexpected std"
+    );
+    let quantiles = numeric.quantiles(4);
+    ensure!(quantiles.len() == 5, "unexpected number of quantiles");
+    ensure!(
+        quantiles[0] == 0.0 && quantiles[4] == 99.0,
+        "unexpected quantile bounds"
+    );
+    ensure!((quantiles[2] - 49.5).abs() < 5.0, "unexpected median");
+
+    let score = &statistics.features["score"];
+    ensure!(
+        score.avg_num_values() == Some(2.0),
+        "unexpected value count"
+    );
+    ensure!(
+        score.numeric.as_ref().unwrap().num_zeros() == 101,
+        "unexpected number of zeros"
+    );
+
+    // bytes features
+    let label = &statistics.features["label"];
+    let bytes = label.bytes.as_ref().unwrap();
+    ensure!(label.missing_rate() == 0.25, "unexpected missing rate");
+    ensure!(bytes.unique_count() == 2, "unexpected unique count");
+    ensure!(bytes.avg_len() == Some(3.0), "unexpected average length");
+    ensure!(
+        bytes.top_k(1) == vec![(b"dog".as_ref(), 50)],
+        "unexpected top values"
+    );
+
+    // merge the statistics computed by threads
+    let merged = examples
+        .chunks(30)
+        .map(|chunk| {
+            let chunk = chunk.to_vec();
+            thread::spawn(move || Statistics::from_examples(chunk.into_iter().map(Ok)))
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .try_fold(Statistics::new(), |mut merged, handle| -> Result<_> {
+            merged.merge(&handle.join().unwrap()?);
+            Ok(merged)
+        })?;
+    ensure!(
+        merged.to_json(10, 4) == statistics.to_json(10, 4),
+        "merged statistics differ"
+    );
+
+    // JSON export
+    let json: serde_json::Value = serde_json::from_str(&statistics.to_json(1, 4))?;
+    ensure!(json["num_examples"] == 100, "unexpected JSON");
+    ensure!(
+        json["features"]["label"]["bytes"]["top_values"][0]["value"] == "dog",
+        "unexpected JSON"
+    );
+    ensure!(
+        json["features"]["id"]["numeric"]["quantiles"]
+            .as_array()
+            .unwrap()
+            .len()
+            == 5,
+        "unexpected JSON"
+    );
+
+    // the bytes statistics are approximate and bounded
+    {
+        let mut examples = (0..20000)
+            .map(|index| {
+                let blob = format!("{:<128}", index % 10000).into_bytes();
+                ExampleBuilder::new().bytes("blob", blob).build()
+            })
+            .collect::<Vec<_>>();
+        let frequent = vec![b'x'; 200];
+        examples.extend((0..5000).map(|_| {
+            ExampleBuilder::new()
+                .bytes("blob", frequent.clone())
+                .build()
+        }));
+        let statistics = Statistics::from_examples(examples.into_iter().map(Ok))?;
+        let bytes = statistics.features["blob"].bytes.as_ref().unwrap();
+
+        let unique_count = bytes.unique_count() as f64;
+        ensure!(
+            (unique_count - 10001.0).abs() < 1000.0,
+            "unexpected unique count {}",
+            unique_count
+        );
+        let top = bytes.top_k(1);
+        ensure!(
+            top[0].0 == &frequent[..100] && top[0].1 <= 5000 && top[0].1 >= 5000 - 25000 / 1001,
+            "unexpected top values"
+        );
+        ensure!(
+            bytes.value_count(&frequent) == top[0].1,
+            "unexpected value count"
+        );
+        ensure!(bytes.top_k(2000).len() <= 1000, "too many tracked values");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn deterministic_encoding_test() -> Result<()> {
+    use prost::Message as _;
+    use tfrecord::{encoding, GenericRecord};
+
+    // the bytes given by tf.train.Example.SerializeToString(deterministic=True)
+    let example = ExampleBuilder::new()
+        .i64("b", 1)
+        .bytes("a", b"x".to_vec())
+        .build();
+    let expect = vec![
+        0x0a, 0x18, 0x0a, 0x0a, 0x0a, 0x01, b'a', 0x12, 0x05, 0x0a, 0x03, 0x0a, 0x01, b'x', 0x0a,
+        0x0a, 0x0a, 0x01, b'b', 0x12, 0x05, 0x1a, 0x03, 0x0a, 0x01, 0x01,
+    ];
+    ensure!(
+        Example::to_bytes(example.clone())? == expect,
+        "unexpected bytes"
+    );
+    ensure!(
+        RawExample::to_bytes(RawExample::from(example))? == expect,
+        "unexpected bytes"
+    );
+
+    // equal examples with distinct insertion orders
+    let names = (0..64)
+        .map(|index| format!("feature/{}", index))
+        .collect::<Vec<_>>();
+    let forward = names
+        .iter()
+        .fold(ExampleBuilder::new(), |builder, name| {
+            builder.f32s(name.as_str(), vec![1.0, 2.0])
+        })
+        .build();
+    let backward = names
+        .iter()
+        .rev()
+        .fold(ExampleBuilder::new(), |builder, name| {
+            builder.f32s(name.as_str(), vec![1.0, 2.0])
+        })
+        .build();
+    let bytes = Example::to_bytes(forward.clone())?;
+    ensure!(
+        bytes == Example::to_bytes(backward)?,
+        "expect identical bytes"
+    );
+    ensure!(
+        Example::from_bytes(bytes)? == forward,
+        "the bytes do not decode to the example"
+    );
+
+    // a single feature is encoded the same as prost does
+    let raw_example = RawExample::from(ExampleBuilder::new().strs("tags", vec!["a", "b"]).build());
+    let mut bytes = vec![];
+    raw_example.encode(&mut bytes)?;
+    ensure!(
+        encoding::encode_example(&raw_example) == bytes,
+        "unexpected bytes"
+    );
+
+    // sequence examples
+    let mut sequence_example = SequenceExample::new();
+    sequence_example.context = forward;
+    for name in names.iter() {
+        sequence_example
+            .feature_lists
+            .insert(name.to_owned(), vec![Feature::Int64List(vec![1, 2, 3])]);
+    }
+    let bytes = SequenceExample::to_bytes(sequence_example.clone())?;
+    ensure!(
+        bytes == SequenceExample::to_bytes(sequence_example.clone())?,
+        "expect identical bytes"
+    );
+    ensure!(
+        SequenceExample::from_bytes(bytes)? == sequence_example,
+        "the bytes do not decode to the sequence example"
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "with-ndarray")]
+#[test]
+fn ndarray_feature_test() -> Result<()> {
+    use ndarray::{arr2, Array2};
+    use tfrecord::batch::stack_array;
+
+    // array to feature and back
+    let array: Array2<f32> = arr2(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+    let feature = Feature::from(&array);
+    ensure!(
+        feature == Feature::FloatList(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]),
+        "unexpected feature"
+    );
+    ensure!(
+        feature.to_array::<f32>(&[2, 3])? == array.clone().into_dyn(),
+        "unexpected array"
+    );
+    ensure!(
+        feature.to_array::<f32>(&[4, 2]).is_err(),
+        "expect shape mismatch"
+    );
+    ensure!(
+        feature.to_array::<i64>(&[2, 3]).is_err(),
+        "expect type mismatch"
+    );
+
+    // stack a feature across examples
+    let examples = (0..4)
+        .map(|index| {
+            ExampleBuilder::new()
+                .i64s("mask", vec![index; 6])
+                .f32("score", index as f32)
+                .build()
+        })
+        .collect::<Vec<_>>();
+    let batch = stack_array::<i64, _>(&examples, "mask", &[2, 3])?;
+    ensure!(batch.shape() == [4, 2, 3], "unexpected shape");
+    ensure!(batch[[3, 1, 2]] == 3, "unexpected value");
+    ensure!(
+        stack_array::<f32, _>(&examples, "score", &[])?.shape() == [4],
+        "unexpected shape"
+    );
+    ensure!(
+        stack_array::<i64, _>(&examples, "mask", &[4]).is_err(),
+        "expect length mismatch"
+    );
+    ensure!(
+        stack_array::<i64, _>(&examples, "missing", &[1]).is_err(),
+        "expect missing feature"
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "with-tch")]
+#[test]
+fn tch_feature_test() -> Result<()> {
+    use std::convert::TryFrom;
+    use tch::{Kind, Tensor};
+    use tfrecord::batch::stack_tensor;
+
+    // tensor to feature and back
+    let tensor = Tensor::of_slice(&[1i64, 2, 3, 4, 5, 6]).view(&[2, 3]);
+    let feature = Feature::try_from(&tensor)?;
+    ensure!(
+        feature == Feature::Int64List(vec![1, 2, 3, 4, 5, 6]),
+        "unexpected feature"
+    );
+    let restored = feature.to_tensor::<i64>(&[3, 2])?;
+    ensure!(restored.size() == [3, 2], "unexpected shape");
+    ensure!(restored.kind() == Kind::Int64, "unexpected kind");
+    ensure!(
+        feature.to_tensor::<f32>(&[3, 2]).is_err(),
+        "expect type mismatch"
+    );
+    ensure!(
+        Feature::try_from(Tensor::of_slice(&[1u8])).is_err(),
+        "expect unsupported kind"
+    );
+
+    // stack a feature across examples
+    let examples = (0..4)
+        .map(|index| {
+            ExampleBuilder::new()
+                .f32s("embedding", vec![index as f32; 8])
+                .build()
+        })
+        .collect::<Vec<_>>();
+    let batch = stack_tensor::<f32, _>(&examples, "embedding", &[2, 4])?;
+    ensure!(batch.size() == [4, 2, 4], "unexpected shape");
+    ensure!(batch.kind() == Kind::Float, "unexpected kind");
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use common::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use tfrecord::serde_example::{from_example, to_example};
+
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Split {
+    Train,
+    Test,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Size {
+    width: u32,
+    height: u32,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Record {
+    id: u64,
+    is_train: bool,
+    score: f32,
+    label: String,
+    split: Split,
+    tags: Vec<String>,
+    coords: [f32; 4],
+    weight: Option<f32>,
+    comment: Option<String>,
+    size: Size,
+    crop: Option<Size>,
+    #[serde(default)]
+    extra: Vec<i64>,
+    notes: Option<Vec<String>>,
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_example_test() -> Result<()> {
+    let record = Record {
+        id: 7,
+        is_train: true,
+        score: 0.5,
+        label: "cat".into(),
+        split: Split::Test,
+        tags: vec!["a".into(), "b".into()],
+        coords: [0.0, 0.1, 0.2, 0.3],
+        weight: Some(2.0),
+        comment: None,
+        size: Size {
+            width: 32,
+            height: 24,
+        },
+        crop: None,
+        extra: vec![],
+        notes: Some(vec![]),
+    };
+
+    // serialize
+    let example = to_example(&record)?;
+    ensure!(example.get_i64("id")? == 7);
+    ensure!(example.get_i64("is_train")? == 1);
+    ensure!(example.get_str("label")? == "cat");
+    ensure!(example.get_str("split")? == "Test");
+    ensure!(example.get_strs("tags")? == vec!["a", "b"]);
+    ensure!(example.get_f32s("coords")? == [0.0, 0.1, 0.2, 0.3]);
+    ensure!(example.get_i64("size.width")? == 32);
+    ensure!(example.get_i64("size.height")? == 24);
+    ensure!(!example.contains_key("comment"));
+    ensure!(!example.contains_key("crop"));
+    ensure!(example.get("extra") == Some(&Feature::Int64List(vec![])));
+    ensure!(example.get("notes") == Some(&Feature::Int64List(vec![])));
+
+    // round trip
+    let decoded: Record = from_example(&example)?;
+    ensure!(decoded == record);
+    ensure!(
+        decoded.notes == Some(vec![]),
+        "empty sequences must not become None"
+    );
+
+    // missing optional and defaulted features
+    let mut example = example;
+    example.remove("extra");
+    example.remove("weight");
+    example.insert("crop.width".into(), Feature::Int64List(vec![8]));
+    example.insert("crop.height".into(), Feature::Int64List(vec![4]));
+    let decoded: Record = from_example(&example)?;
+    ensure!(decoded.weight.is_none());
+    ensure!(decoded.extra.is_empty());
+    ensure!(
+        decoded.crop
+            == Some(Size {
+                width: 8,
+                height: 4
+            })
+    );
+
+    // missing and mismatched features are errors
+    let mut missing = example.clone();
+    missing.remove("label");
+    ensure!(from_example::<Record>(&missing).is_err());
+
+    let mut mismatched = example;
+    mismatched.insert("id".into(), Feature::FloatList(vec![1.0]));
+    ensure!(from_example::<Record>(&mismatched).is_err());
+
+    // maps flatten to dotted keys
+    let mut map = std::collections::BTreeMap::new();
+    map.insert("a", vec![1i64, 2]);
+    map.insert("b", vec![3]);
+    let example: Example = to_example(&map)?;
+    ensure!(example.get_i64s("a")? == [1, 2]);
+    let decoded: std::collections::BTreeMap<String, Vec<i64>> = from_example(&example)?;
+    ensure!(decoded.len() == 2 && decoded["b"] == [3]);
+
+    // top-level scalars are rejected
+    ensure!(to_example(&1i64).is_err());
+
+    Ok(())
+}
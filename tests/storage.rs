@@ -0,0 +1,316 @@
+mod common;
+
+use common::*;
+
+/// A minimal S3-compatible server that serves the buckets under a local directory.
+///
+/// It accepts path-style HEAD, ranged GET and ListObjectsV2 requests, and rejects
+/// unsigned requests. The listing is paginated by two keys per page.
+#[cfg(feature = "s3")]
+fn spawn_s3_stand_in(root: PathBuf) -> Result<u16> {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+    };
+
+    fn percent_decode(text: &str) -> String {
+        let bytes = text.as_bytes();
+        let mut decoded = vec![];
+        let mut index = 0;
+        while index < bytes.len() {
+            if bytes[index] == b'%' && index + 2 < bytes.len() {
+                let hex = std::str::from_utf8(&bytes[(index + 1)..(index + 3)]).unwrap();
+                decoded.push(u8::from_str_radix(hex, 16).unwrap());
+                index += 3;
+            } else {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+        String::from_utf8(decoded).unwrap()
+    }
+
+    fn list_keys(dir: &std::path::Path, prefix: &str, keys: &mut Vec<String>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let key = format!("{}{}", prefix, path.file_name().unwrap().to_str().unwrap());
+            if path.is_dir() {
+                list_keys(&path, &format!("{}/", key), keys);
+            } else {
+                keys.push(key);
+            }
+        }
+    }
+
+    fn handle(stream: std::net::TcpStream, root: &std::path::Path) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut headers = vec![];
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line.split_at(line.find(':').unwrap());
+            headers.push((name.to_lowercase(), value[1..].trim().to_owned()));
+        }
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+
+        let mut tokens = request_line.split_whitespace();
+        let method = tokens.next().unwrap().to_owned();
+        let target = tokens.next().unwrap().to_owned();
+        let (path, query) = match target.find('?') {
+            Some(index) => (&target[..index], &target[(index + 1)..]),
+            None => (target.as_str(), ""),
+        };
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_at(pair.find('=').unwrap_or(pair.len()));
+                (
+                    percent_decode(name),
+                    percent_decode(value.trim_start_matches('=')),
+                )
+            })
+            .collect::<Vec<_>>();
+        let query_value = |name: &str| {
+            query
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+        let path = percent_decode(path);
+        let mut segments = path[1..].splitn(2, '/');
+        let bucket = segments.next().unwrap_or("").to_owned();
+        let key = segments.next().unwrap_or("").to_owned();
+
+        let is_signed = header("authorization")
+            .map(|auth| auth.starts_with("AWS4-HMAC-SHA256 Credential=minio/"))
+            .unwrap_or(false)
+            && header("x-amz-date").is_some();
+
+        let (status, extra_headers, body) = if !is_signed {
+            ("403 Forbidden", vec![], b"AccessDenied".to_vec())
+        } else if key.is_empty() && method == "GET" {
+            let prefix = query_value("prefix").unwrap_or_default();
+            let start = query_value("continuation-token")
+                .map(|token| token.parse::<usize>().unwrap())
+                .unwrap_or(0);
+            let mut keys = vec![];
+            list_keys(&root.join(&bucket), "", &mut keys);
+            keys.sort();
+            let keys = keys
+                .into_iter()
+                .filter(|key| key.starts_with(&prefix))
+                .collect::<Vec<_>>();
+            let end = (start + 2).min(keys.len());
+            let mut body = String::from("<ListBucketResult>");
+            for key in &keys[start..end] {
+                body.push_str(&format!("<Contents><Key>{}</Key></Contents>", key));
+            }
+            if end < keys.len() {
+                body.push_str(&format!(
+                    "<IsTruncated>true</IsTruncated><NextContinuationToken>{}</NextContinuationToken>",
+                    end
+                ));
+            } else {
+                body.push_str("<IsTruncated>false</IsTruncated>");
+            }
+            body.push_str("</ListBucketResult>");
+            ("200 OK", vec![], body.into_bytes())
+        } else {
+            match std::fs::read(root.join(&bucket).join(&key)) {
+                Ok(data) => match header("range") {
+                    Some(range) => {
+                        let range = range.trim_start_matches("bytes=");
+                        let (first, last) = range.split_at(range.find('-').unwrap());
+                        let first = first.parse::<usize>().unwrap();
+                        let last = last[1..].parse::<usize>().unwrap().min(data.len() - 1);
+                        let content_range =
+                            format!("Content-Range: bytes {}-{}/{}", first, last, data.len());
+                        (
+                            "206 Partial Content",
+                            vec![content_range],
+                            data[first..=last].to_vec(),
+                        )
+                    }
+                    None => ("200 OK", vec![], data),
+                },
+                Err(_) => ("404 Not Found", vec![], b"NoSuchKey".to_vec()),
+            }
+        };
+
+        let mut stream = stream;
+        write!(stream, "HTTP/1.1 {}\r\n", status)?;
+        write!(stream, "Content-Length: {}\r\n", body.len())?;
+        write!(stream, "Connection: close\r\n")?;
+        for header in extra_headers {
+            write!(stream, "{}\r\n", header)?;
+        }
+        write!(stream, "\r\n")?;
+        if method != "HEAD" {
+            stream.write_all(&body)?;
+        }
+        stream.flush()?;
+        Ok(())
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = stream.unwrap();
+            let root = root.clone();
+            thread::spawn(move || handle(stream, &root));
+        }
+    });
+
+    Ok(port)
+}
+
+#[cfg(feature = "s3")]
+#[async_std::test]
+async fn storage_test() -> Result<()> {
+    use std::sync::Arc;
+
+    let root = DATA_DIR.join("storage_test");
+    let dir = root.join("bucket").join("data");
+    std::fs::create_dir_all(&dir)?;
+    for index in 0..5 {
+        let start = index * 100;
+        write_numbered_records(
+            &dir.join(format!("part-{}.tfrecord", index)),
+            start..(start + 10 + index),
+        )?;
+    }
+    std::fs::write(dir.join("README"), b"not a record file")?;
+    let expect = (0..5)
+        .flat_map(|index| (index * 100)..(index * 100 + 10 + index))
+        .collect::<Vec<u32>>();
+
+    // local storage
+    let local_prefix = format!("{}/part-", dir.display());
+    let dataset = DatasetInit::default()
+        .from_storage_prefix(Arc::new(LocalStorage), &local_prefix)
+        .await?;
+    let numbers = dataset
+        .stream::<Vec<u8>>()
+        .map_ok(parse_number)
+        .try_collect::<Vec<_>>()
+        .await?;
+    ensure!(numbers == expect, "unexpected records from local storage");
+    let numbers = RecordStreamInit::default()
+        .open_storage::<Vec<u8>>(
+            Arc::new(LocalStorage),
+            &format!("{}/part-3.tfrecord", dir.display()),
+        )
+        .await?
+        .map_ok(parse_number)
+        .try_collect::<Vec<_>>()
+        .await?;
+    ensure!(
+        numbers == expect[33..46],
+        "unexpected records from a local stream"
+    );
+
+    // S3-compatible storage
+    let port = spawn_s3_stand_in(root.clone())?;
+    let storage = S3StorageInit::default()
+        .with_endpoint(format!("http://127.0.0.1:{}", port))
+        .with_credentials("minio", "minio123")
+        .build()?;
+
+    let paths = storage.list("s3://bucket/data/").await?;
+    ensure!(paths.len() == 6, "unexpected listing {:?}", paths);
+    ensure!(
+        storage.size("s3://bucket/data/README").await? == 17,
+        "unexpected object size"
+    );
+    ensure!(
+        storage
+            .read_range("s3://bucket/data/README", 4, 100)
+            .await?
+            == b"a record file",
+        "unexpected ranged read"
+    );
+    ensure!(
+        storage.size("s3://bucket/data/missing").await.is_err(),
+        "expect missing object"
+    );
+
+    let storage = Arc::new(storage);
+    let numbers = RecordStreamInit::default()
+        .open_storage::<Vec<u8>>(storage.clone(), "s3://bucket/data/part-4.tfrecord")
+        .await?
+        .map_ok(parse_number)
+        .try_collect::<Vec<_>>()
+        .await?;
+    ensure!(
+        numbers == expect[46..],
+        "unexpected records from an S3 stream"
+    );
+    ensure!(
+        RecordStreamInit::default()
+            .open_storage::<Vec<u8>>(storage.clone(), "s3://bucket/data/README")
+            .await?
+            .try_collect::<Vec<_>>()
+            .await
+            .is_err(),
+        "expect corrupted records"
+    );
+
+    let mut dataset = DatasetInit::default()
+        .from_storage_prefix(storage, "s3://bucket/data/part-")
+        .await?;
+    ensure!(
+        dataset.num_records() == expect.len(),
+        "unexpected number of records"
+    );
+    let numbers = dataset
+        .stream::<Vec<u8>>()
+        .map_ok(parse_number)
+        .try_collect::<Vec<_>>()
+        .await?;
+    ensure!(numbers == expect, "unexpected records from S3 storage");
+    for index in (0..expect.len()).rev().step_by(7) {
+        let number = parse_number(dataset.get::<Vec<u8>>(index).await?.unwrap());
+        ensure!(number == expect[index], "unexpected random access");
+    }
+
+    // s3:// URLs are plain paths unless a storage is given
+    ensure!(
+        DatasetInit::default()
+            .from_prefix("s3://bucket/data/part-")
+            .await
+            .is_err(),
+        "expect no S3 access"
+    );
+
+    // the storage settings are loaded from environment variables on request
+    std::env::set_var("AWS_ENDPOINT_URL", format!("http://127.0.0.1:{}", port));
+    std::env::set_var("AWS_ACCESS_KEY_ID", "minio");
+    std::env::set_var("AWS_SECRET_ACCESS_KEY", "minio123");
+    let storage = S3StorageInit::from_env().build()?;
+    let mut dataset = DatasetInit::default()
+        .with_max_open_files(NonZeroUsize::new(1).unwrap())
+        .from_storage_prefix(Arc::new(storage), "s3://bucket/data/part-")
+        .await?;
+    ensure!(
+        dataset.num_records() == expect.len(),
+        "unexpected number of records"
+    );
+    let number = parse_number(dataset.get::<Vec<u8>>(3).await?.unwrap());
+    ensure!(number == expect[3], "unexpected record");
+
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -0,0 +1,305 @@
+mod common;
+
+use common::*;
+use half::{bf16, f16};
+use num_complex::Complex;
+use tfrecord::protos::{tensor_shape_proto::Dim, DataType, TensorProto, TensorShapeProto};
+
+fn tensor_shape(shape: &[i64]) -> Option<TensorShapeProto> {
+    Some(TensorShapeProto {
+        dim: shape
+            .iter()
+            .map(|&size| Dim {
+                size,
+                name: "".into(),
+            })
+            .collect(),
+        unknown_rank: false,
+    })
+}
+
+#[test]
+fn tensor_proto_decode_test() -> Result<()> {
+    // tensor_content
+    let tensor = TensorProto {
+        dtype: DataType::DtFloat as i32,
+        tensor_shape: tensor_shape(&[2, 2]),
+        tensor_content: [1.0f32, 2.0, 3.0, 4.0]
+            .iter()
+            .flat_map(|value| value.to_le_bytes().to_vec())
+            .collect(),
+        ..Default::default()
+    };
+    ensure!(tensor.shape()? == [2, 2], "unexpected shape");
+    ensure!(
+        tensor.to_vec::<f32>()? == [1.0, 2.0, 3.0, 4.0],
+        "unexpected values"
+    );
+    ensure!(tensor.to_vec::<f64>().is_err(), "expect dtype mismatch");
+
+    // typed values are padded with the last value
+    let tensor = TensorProto {
+        dtype: DataType::DtInt32 as i32,
+        tensor_shape: tensor_shape(&[4]),
+        int_val: vec![7, 8],
+        ..Default::default()
+    };
+    ensure!(tensor.to_vec::<i32>()? == [7, 8, 8, 8], "unexpected values");
+
+    // a scalar without values is zero
+    let tensor = TensorProto {
+        dtype: DataType::DtInt64 as i32,
+        ..Default::default()
+    };
+    ensure!(tensor.shape()?.is_empty(), "expect a scalar");
+    ensure!(tensor.to_vec::<i64>()? == [0], "unexpected values");
+
+    // half precision
+    let values = [f16::from_f32(0.5), f16::from_f32(-2.0)];
+    let tensor = TensorProto {
+        dtype: DataType::DtHalf as i32,
+        tensor_shape: tensor_shape(&[2]),
+        half_val: values.iter().map(|value| value.to_bits() as i32).collect(),
+        ..Default::default()
+    };
+    ensure!(tensor.to_vec::<f16>()? == values, "unexpected values");
+    let tensor = TensorProto {
+        dtype: DataType::DtBfloat16 as i32,
+        tensor_shape: tensor_shape(&[1]),
+        tensor_content: bf16::from_f32(3.0).to_le_bytes().to_vec(),
+        ..Default::default()
+    };
+    ensure!(
+        tensor.to_vec::<bf16>()? == [bf16::from_f32(3.0)],
+        "unexpected values"
+    );
+
+    // bool and complex values
+    let tensor = TensorProto {
+        dtype: DataType::DtBool as i32,
+        tensor_shape: tensor_shape(&[3]),
+        bool_val: vec![true, false],
+        ..Default::default()
+    };
+    ensure!(
+        tensor.to_vec::<bool>()? == [true, false, false],
+        "unexpected values"
+    );
+    let tensor = TensorProto {
+        dtype: DataType::DtComplex64 as i32,
+        tensor_shape: tensor_shape(&[2]),
+        scomplex_val: vec![1.0, -1.0, 0.5, 2.0],
+        ..Default::default()
+    };
+    ensure!(
+        tensor.to_vec::<Complex<f32>>()? == [Complex::new(1.0, -1.0), Complex::new(0.5, 2.0)],
+        "unexpected values"
+    );
+
+    // strings in string_val and tensor_content
+    let tensor = TensorProto {
+        dtype: DataType::DtString as i32,
+        tensor_shape: tensor_shape(&[2]),
+        string_val: vec![b"cat".to_vec(), b"dog".to_vec()],
+        ..Default::default()
+    };
+    ensure!(
+        tensor.to_bytes_list()? == [b"cat".to_vec(), b"dog".to_vec()],
+        "unexpected values"
+    );
+    let strings = vec!["", "tfrecord", "a"];
+    let tensor = TensorProto::from(&strings);
+    ensure!(
+        tensor.string_val == [b"".to_vec(), b"tfrecord".to_vec(), b"a".to_vec()],
+        "expect values in string_val"
+    );
+    ensure!(
+        tensor.to_bytes_list()? == tensor.string_val,
+        "unexpected values"
+    );
+
+    // the string encoding of tf.io.serialize_tensor
+    let tensor = TensorProto {
+        dtype: DataType::DtString as i32,
+        tensor_shape: tensor_shape(&[2]),
+        tensor_content: vec![2, 0, b'a', b'b'],
+        ..Default::default()
+    };
+    ensure!(
+        tensor.to_bytes_list()? == [b"ab".to_vec(), vec![]],
+        "unexpected values"
+    );
+
+    // malformed tensors
+    let tensor = TensorProto {
+        dtype: DataType::DtDouble as i32,
+        tensor_shape: tensor_shape(&[3]),
+        tensor_content: vec![0; 16],
+        ..Default::default()
+    };
+    ensure!(tensor.to_vec::<f64>().is_err(), "expect truncated content");
+    let tensor = TensorProto {
+        dtype: DataType::DtString as i32,
+        tensor_shape: tensor_shape(&[2]),
+        tensor_content: vec![3, 1, b'a', b'b'],
+        ..Default::default()
+    };
+    ensure!(tensor.to_bytes_list().is_err(), "expect truncated content");
+    let tensor = TensorProto {
+        dtype: DataType::DtFloat as i32,
+        tensor_shape: tensor_shape(&[-1, 2]),
+        ..Default::default()
+    };
+    ensure!(tensor.shape().is_err(), "expect invalid shape");
+
+    // hostile shapes
+    for &dtype in &[DataType::DtFloat, DataType::DtString] {
+        let tensor = TensorProto {
+            dtype: dtype as i32,
+            tensor_shape: tensor_shape(&[1 << 40, 1 << 40]),
+            tensor_content: vec![0; 16],
+            ..Default::default()
+        };
+        ensure!(tensor.shape()? == [1 << 40, 1 << 40], "unexpected shape");
+        ensure!(
+            tensor.to_vec::<f32>().is_err() && tensor.to_bytes_list().is_err(),
+            "expect overflow"
+        );
+    }
+    let tensor = TensorProto {
+        dtype: DataType::DtString as i32,
+        tensor_shape: tensor_shape(&[1 << 40]),
+        tensor_content: vec![0; 16],
+        ..Default::default()
+    };
+    ensure!(tensor.to_bytes_list().is_err(), "expect truncated content");
+
+    Ok(())
+}
+
+#[test]
+fn extended_dtype_test() -> Result<()> {
+    use tfrecord::{protos::HistogramProto, TensorProtoDecode};
+
+    fn roundtrip<T>(values: &[T], bytes: &[u8]) -> Result<()>
+    where
+        T: TensorProtoDecode + PartialEq + std::fmt::Debug,
+    {
+        let tensor = TensorProto {
+            dtype: T::DATA_TYPE as i32,
+            tensor_shape: tensor_shape(&[values.len() as i64]),
+            tensor_content: values.iter().flat_map(|value| value.to_bytes()).collect(),
+            ..Default::default()
+        };
+        ensure!(tensor.tensor_content == bytes, "unexpected bytes");
+        ensure!(tensor.to_vec::<T>()? == values, "unexpected values");
+        Ok(())
+    }
+
+    roundtrip(&[true, false], &[1, 0])?;
+    roundtrip(&[f16::from_f32(1.0)], &[0x00, 0x3c])?;
+    roundtrip(&[bf16::from_f32(1.0)], &[0x80, 0x3f])?;
+    roundtrip(
+        &[Complex::new(1.0f32, -2.0)],
+        &[0, 0, 0x80, 0x3f, 0, 0, 0, 0xc0],
+    )?;
+    roundtrip(
+        &[Complex::new(0.0f64, 1.0)],
+        &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xf0, 0x3f],
+    )?;
+
+    // histograms
+    let histogram = HistogramProto::from(vec![f16::from_f32(-1.5), f16::from_f32(2.5)]);
+    ensure!(
+        (histogram.min, histogram.max) == (-1.5, 2.5),
+        "unexpected histogram"
+    );
+    let histogram = HistogramProto::from(vec![true, true, false]);
+    ensure!(
+        histogram.num == 3.0 && histogram.sum == 2.0,
+        "unexpected histogram"
+    );
+    let histogram = HistogramProto::from(vec![Complex::new(3.0f64, 4.0)]);
+    ensure!(histogram.sum == 5.0, "expect the magnitude");
+
+    Ok(())
+}
+
+#[cfg(feature = "with-ndarray")]
+#[test]
+fn tensor_proto_ndarray_test() -> Result<()> {
+    use ndarray::{arr1, Array3, ArrayD};
+    use std::convert::TryFrom;
+    use tfrecord::protos::HistogramProto;
+
+    let array = Array3::from_shape_fn((2, 3, 4), |(i, j, k)| (i * 12 + j * 4 + k) as i16);
+    let tensor = TensorProto::from(&array);
+    let decoded = ArrayD::<i16>::try_from(&tensor)?;
+    ensure!(decoded == array.into_dyn(), "unexpected array");
+    ensure!(
+        ArrayD::<u16>::try_from(&tensor).is_err(),
+        "expect dtype mismatch"
+    );
+
+    // extended data types
+    let array = arr1(&[bf16::from_f32(0.5), bf16::from_f32(-4.0)]);
+    let tensor = TensorProto::from(&array);
+    ensure!(
+        tensor.dtype == DataType::DtBfloat16 as i32,
+        "unexpected dtype"
+    );
+    ensure!(
+        ArrayD::<bf16>::try_from(&tensor)? == array.into_dyn(),
+        "unexpected array"
+    );
+    let array = arr1(&[Complex::new(1.0f64, 2.0)]);
+    ensure!(
+        ArrayD::<Complex<f64>>::try_from(TensorProto::from(&array))? == array.into_dyn(),
+        "unexpected array"
+    );
+    let histogram = HistogramProto::try_from(arr1(&[f16::from_f32(1.0), f16::from_f32(3.0)]))?;
+    ensure!(histogram.sum == 4.0, "unexpected histogram");
+
+    // string tensors
+    let tensor = TensorProto::from(vec!["a", "bc"]);
+    ensure!(
+        ArrayD::<Vec<u8>>::try_from(&tensor)?.into_raw_vec() == [b"a".to_vec(), b"bc".to_vec()],
+        "unexpected array"
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "with-tch")]
+#[test]
+fn tensor_proto_tch_test() -> Result<()> {
+    use std::convert::TryFrom;
+    use tch::{Kind, Tensor};
+
+    let tensor = Tensor::of_slice(&[1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]).view(&[3, 2]);
+    let proto = TensorProto::try_from(&tensor)?;
+    let decoded = Tensor::try_from(&proto)?;
+    ensure!(decoded.size() == [3, 2], "unexpected shape");
+    ensure!(decoded.kind() == Kind::Float, "unexpected kind");
+    ensure!(
+        Feature::try_from(&decoded)? == Feature::FloatList(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]),
+        "unexpected values"
+    );
+
+    // extended data types
+    let proto = TensorProto::try_from(&tensor.to_kind(Kind::Half))?;
+    ensure!(proto.dtype == DataType::DtHalf as i32, "unexpected dtype");
+    ensure!(
+        proto.to_vec::<f16>()?[5] == f16::from_f32(6.0),
+        "unexpected values"
+    );
+    let decoded = Tensor::try_from(&proto)?;
+    ensure!(decoded.kind() == Kind::Half, "unexpected kind");
+    let proto = TensorProto::try_from(&tensor.gt(3.0))?;
+    ensure!(
+        proto.to_vec::<bool>()? == [false, false, false, true, true, true],
+        "unexpected values"
+    );
+
+    Ok(())
+}
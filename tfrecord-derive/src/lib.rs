@@ -0,0 +1,361 @@
+//! Derive macros for the [tfrecord](https://docs.rs/tfrecord/) crate.
+//!
+//! The macros are re-exported by tfrecord with the `derive` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Field, Fields, Ident, Lit, Meta, NestedMeta,
+    Result, Type,
+};
+
+/// Derive `TfExample`, `GenericRecord` and the conversions from and to `Example` and `RawExample`.
+///
+/// The struct must have named fields. Every field type implements `FeatureValue`,
+/// or `TfExample` if the field is flattened. The fields accept these attributes.
+///
+/// - `#[tfrecord(key = "name")]`: Set the feature key. It defaults to the field name.
+/// - `#[tfrecord(default)]`: Use [Default::default] if the feature is missing.
+/// - `#[tfrecord(default = "path")]`: Call the function if the feature is missing.
+/// - `#[tfrecord(len = 3)]`: Require the feature to have exactly the number of values.
+/// - `#[tfrecord(flatten)]`: Store the fields of the nested type in the same example.
+/// - `#[tfrecord(prefix = "image/")]`: Flatten the nested type with the prefix added to its keys.
+/// - `#[tfrecord(skip)]`: Skip the field and use [Default::default] when decoding.
+///
+/// The `Option` fields are optional. They are `None` if the features are missing,
+/// and the features are omitted if they are `None`.
+#[proc_macro_derive(TfExample, attributes(tfrecord))]
+pub fn derive_tf_example(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+enum FieldKind {
+    Feature {
+        key: String,
+        default: Option<TokenStream2>,
+        len: Option<usize>,
+    },
+    Flatten {
+        prefix: String,
+    },
+    Skip,
+}
+
+struct FieldDef<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    kind: FieldKind,
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "TfExample cannot be derived for generic types",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "TfExample can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "TfExample can only be derived for structs",
+            ))
+        }
+    };
+    let fields = fields.iter().map(parse_field).collect::<Result<Vec<_>>>()?;
+
+    let decode_fields = fields.iter().map(decode_field).collect::<Vec<_>>();
+    let encode_fields = fields.iter().map(encode_field).collect::<Vec<_>>();
+    let field_idents = fields.iter().map(|field| field.ident).collect::<Vec<_>>();
+
+    // the length check is only emitted when needed
+    let has_len = fields
+        .iter()
+        .any(|field| matches!(field.kind, FieldKind::Feature { len: Some(_), .. }));
+    let check_len = if has_len {
+        quote! {
+            fn check_len(
+                name: &str,
+                feature: &::tfrecord::Feature,
+                expect: usize,
+            ) -> ::std::result::Result<(), ::tfrecord::Error> {
+                let found = match feature {
+                    ::tfrecord::Feature::BytesList(values) => values.len(),
+                    ::tfrecord::Feature::FloatList(values) => values.len(),
+                    ::tfrecord::Feature::Int64List(values) => values.len(),
+                    ::tfrecord::Feature::None => return Ok(()),
+                };
+                if found != expect {
+                    return Err(::tfrecord::Error::FeatureMismatchError {
+                        name: name.to_owned(),
+                        expect: format!("{} values", expect),
+                        found: format!("{} values", found),
+                    });
+                }
+                Ok(())
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let expanded = quote! {
+        impl ::tfrecord::TfExample for #name {
+            fn from_example_with_prefix(
+                example: &::tfrecord::Example,
+                prefix: &str,
+            ) -> ::std::result::Result<Self, ::tfrecord::Error> {
+                #check_len
+                // the fields are not bound to locals to not shadow the parameters
+                Ok(Self {
+                    #(#field_idents: #decode_fields,)*
+                })
+            }
+
+            fn write_example_with_prefix(
+                self,
+                example: &mut ::tfrecord::Example,
+                prefix: &str,
+            ) -> ::std::result::Result<(), ::tfrecord::Error> {
+                #check_len
+                #(#encode_fields)*
+                Ok(())
+            }
+        }
+
+        impl ::tfrecord::GenericRecord for #name {
+            fn from_bytes(bytes: Vec<u8>) -> ::std::result::Result<Self, ::tfrecord::Error> {
+                let example = <::tfrecord::Example as ::tfrecord::GenericRecord>::from_bytes(bytes)?;
+                <Self as ::tfrecord::TfExample>::from_example(&example)
+            }
+
+            fn to_bytes(record: Self) -> ::std::result::Result<Vec<u8>, ::tfrecord::Error> {
+                let example = <Self as ::tfrecord::TfExample>::into_example(record)?;
+                <::tfrecord::Example as ::tfrecord::GenericRecord>::to_bytes(example)
+            }
+        }
+
+        impl ::std::convert::TryFrom<&::tfrecord::Example> for #name {
+            type Error = ::tfrecord::Error;
+
+            fn try_from(example: &::tfrecord::Example) -> ::std::result::Result<Self, Self::Error> {
+                <Self as ::tfrecord::TfExample>::from_example(example)
+            }
+        }
+
+        impl ::std::convert::TryFrom<::tfrecord::Example> for #name {
+            type Error = ::tfrecord::Error;
+
+            fn try_from(example: ::tfrecord::Example) -> ::std::result::Result<Self, Self::Error> {
+                <Self as ::tfrecord::TfExample>::from_example(&example)
+            }
+        }
+
+        impl ::std::convert::TryFrom<&::tfrecord::RawExample> for #name {
+            type Error = ::tfrecord::Error;
+
+            fn try_from(example: &::tfrecord::RawExample) -> ::std::result::Result<Self, Self::Error> {
+                let example = ::tfrecord::Example::from(example);
+                <Self as ::tfrecord::TfExample>::from_example(&example)
+            }
+        }
+
+        impl ::std::convert::TryFrom<::tfrecord::RawExample> for #name {
+            type Error = ::tfrecord::Error;
+
+            fn try_from(example: ::tfrecord::RawExample) -> ::std::result::Result<Self, Self::Error> {
+                let example = ::tfrecord::Example::from(example);
+                <Self as ::tfrecord::TfExample>::from_example(&example)
+            }
+        }
+
+        impl ::std::convert::TryFrom<#name> for ::tfrecord::Example {
+            type Error = ::tfrecord::Error;
+
+            fn try_from(record: #name) -> ::std::result::Result<Self, Self::Error> {
+                <#name as ::tfrecord::TfExample>::into_example(record)
+            }
+        }
+
+        impl ::std::convert::TryFrom<#name> for ::tfrecord::RawExample {
+            type Error = ::tfrecord::Error;
+
+            fn try_from(record: #name) -> ::std::result::Result<Self, Self::Error> {
+                let example = <#name as ::tfrecord::TfExample>::into_example(record)?;
+                Ok(::tfrecord::RawExample::from(example))
+            }
+        }
+    };
+
+    Ok(expanded)
+}
+
+fn parse_field(field: &Field) -> Result<FieldDef<'_>> {
+    let ident = field.ident.as_ref().unwrap();
+    let mut key = None;
+    let mut default = None;
+    let mut len = None;
+    let mut flatten = false;
+    let mut prefix = None;
+    let mut skip = false;
+
+    for attr in field.attrs.iter() {
+        if !attr.path.is_ident("tfrecord") {
+            continue;
+        }
+
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => {
+                return Err(Error::new_spanned(
+                    meta,
+                    r#"expect attributes like #[tfrecord(key = "name")]"#,
+                ))
+            }
+        };
+
+        for nested in list.nested.iter() {
+            match nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("default") => {
+                    default = Some(quote! { ::std::default::Default::default() });
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("flatten") => {
+                    flatten = true;
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => {
+                    skip = true;
+                }
+                NestedMeta::Meta(Meta::NameValue(pair)) => {
+                    let lit = &pair.lit;
+                    match (pair.path.get_ident(), lit) {
+                        (Some(name), Lit::Str(value)) if name == "key" => {
+                            key = Some(value.value());
+                        }
+                        (Some(name), Lit::Str(value)) if name == "default" => {
+                            let path = value.parse::<syn::ExprPath>()?;
+                            default = Some(quote! { #path() });
+                        }
+                        (Some(name), Lit::Int(value)) if name == "len" => {
+                            len = Some(value.base10_parse::<usize>()?);
+                        }
+                        (Some(name), Lit::Str(value)) if name == "prefix" => {
+                            prefix = Some(value.value());
+                        }
+                        _ => return Err(Error::new_spanned(pair, "unknown tfrecord attribute")),
+                    }
+                }
+                nested => return Err(Error::new_spanned(nested, "unknown tfrecord attribute")),
+            }
+        }
+    }
+
+    let is_feature_attr = key.is_some() || default.is_some() || len.is_some();
+    let kind = if skip {
+        if is_feature_attr || flatten || prefix.is_some() {
+            return Err(Error::new_spanned(
+                field,
+                "the skipped field cannot have other tfrecord attributes",
+            ));
+        }
+        FieldKind::Skip
+    } else if flatten || prefix.is_some() {
+        if is_feature_attr {
+            return Err(Error::new_spanned(
+                field,
+                "the flattened field cannot have key, default or len attributes",
+            ));
+        }
+        FieldKind::Flatten {
+            prefix: prefix.unwrap_or_default(),
+        }
+    } else {
+        FieldKind::Feature {
+            key: key.unwrap_or_else(|| ident.to_string()),
+            default,
+            len,
+        }
+    };
+
+    Ok(FieldDef {
+        ident,
+        ty: &field.ty,
+        kind,
+    })
+}
+
+fn decode_field(field: &FieldDef) -> TokenStream2 {
+    let ty = field.ty;
+
+    match &field.kind {
+        FieldKind::Feature { key, default, len } => {
+            let check = len.map(|len| quote! { check_len(&name, feature, #len)?; });
+            let missing = match default {
+                Some(default) => quote! { #default },
+                None => quote! { <#ty as ::tfrecord::FeatureValue>::from_missing(&name)? },
+            };
+            quote! {{
+                let name = format!("{}{}", prefix, #key);
+                match example.get(&name) {
+                    Some(feature) => {
+                        #check
+                        <#ty as ::tfrecord::FeatureValue>::from_feature(&name, feature)?
+                    }
+                    None => #missing,
+                }
+            }}
+        }
+        FieldKind::Flatten {
+            prefix: field_prefix,
+        } => quote! {
+            <#ty as ::tfrecord::TfExample>::from_example_with_prefix(
+                example,
+                &format!("{}{}", prefix, #field_prefix),
+            )?
+        },
+        FieldKind::Skip => quote! { ::std::default::Default::default() },
+    }
+}
+
+fn encode_field(field: &FieldDef) -> TokenStream2 {
+    let ident = field.ident;
+    let ty = field.ty;
+
+    match &field.kind {
+        FieldKind::Feature { key, len, .. } => {
+            let check = len.map(|len| quote! { check_len(&name, &feature, #len)?; });
+            quote! {
+                if let Some(feature) = <#ty as ::tfrecord::FeatureValue>::into_feature(self.#ident) {
+                    let name = format!("{}{}", prefix, #key);
+                    #check
+                    example.insert(name, feature);
+                }
+            }
+        }
+        FieldKind::Flatten {
+            prefix: field_prefix,
+        } => quote! {
+            <#ty as ::tfrecord::TfExample>::write_example_with_prefix(
+                self.#ident,
+                example,
+                &format!("{}{}", prefix, #field_prefix),
+            )?;
+        },
+        FieldKind::Skip => quote! {},
+    }
+}